@@ -0,0 +1,118 @@
+//! 数据目录自动备份
+//!
+//! 每天把关键数据文件复制到 backups/<日期> 下并轮换清理，
+//! 保护长期整理的电台数据不被误爬取或文件损坏毁掉。
+
+use std::path::{Path, PathBuf};
+
+/// 备份根目录名
+const BACKUP_DIR: &str = "backups";
+
+/// 参与备份的数据文件
+const BACKUP_FILES: [&str; 4] = [
+    "stations.json",
+    "settings.json",
+    "custom_stations.json",
+    "install_selection.json",
+];
+
+/// 最多保留的备份数量
+const MAX_BACKUPS: usize = 7;
+
+/// 列出现有备份名称（按时间从旧到新）
+pub fn list_backups(data_dir: &Path) -> Vec<String> {
+    let backups_dir = data_dir.join(BACKUP_DIR);
+    let mut names: Vec<String> = std::fs::read_dir(backups_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // 备份目录按日期命名，字典序即时间序
+    names.sort();
+    names
+}
+
+/// 创建一份新备份，返回备份名称
+///
+/// 同一天重复调用会覆盖当天的备份内容。
+pub fn create_backup(data_dir: &Path) -> anyhow::Result<String> {
+    let name = chrono::Local::now().format("%Y%m%d").to_string();
+    let target_dir = data_dir.join(BACKUP_DIR).join(&name);
+    std::fs::create_dir_all(&target_dir)?;
+
+    let mut copied = 0;
+    for file_name in BACKUP_FILES {
+        let source = data_dir.join(file_name);
+        if source.exists() {
+            std::fs::copy(&source, target_dir.join(file_name))?;
+            copied += 1;
+        }
+    }
+
+    if copied == 0 {
+        // 没有可备份的文件时不留空目录
+        let _ = std::fs::remove_dir(&target_dir);
+        anyhow::bail!("没有可备份的数据文件");
+    }
+
+    prune_backups(data_dir);
+    log::debug!("backup created: {} ({} files)", name, copied);
+    Ok(name)
+}
+
+/// 当天还没有备份时创建一份，返回新备份名称
+pub fn backup_if_due(data_dir: &Path) -> anyhow::Result<Option<String>> {
+    let today = chrono::Local::now().format("%Y%m%d").to_string();
+    if data_dir.join(BACKUP_DIR).join(&today).exists() {
+        return Ok(None);
+    }
+
+    create_backup(data_dir).map(Some)
+}
+
+/// 从指定备份恢复数据文件，返回恢复的文件列表
+pub fn restore_backup(data_dir: &Path, name: &str) -> anyhow::Result<Vec<String>> {
+    let source_dir = data_dir.join(BACKUP_DIR).join(name);
+    if !source_dir.is_dir() {
+        anyhow::bail!("备份不存在: {}", name);
+    }
+
+    let mut restored = Vec::new();
+    for file_name in BACKUP_FILES {
+        let source = source_dir.join(file_name);
+        if source.exists() {
+            std::fs::copy(&source, data_dir.join(file_name))?;
+            restored.push(file_name.to_string());
+        }
+    }
+
+    if restored.is_empty() {
+        anyhow::bail!("备份中没有可恢复的文件: {}", name);
+    }
+
+    log::info!("已从备份恢复: {} ({:?})", name, restored);
+    Ok(restored)
+}
+
+/// 清理最旧的备份，只保留最近 MAX_BACKUPS 份
+fn prune_backups(data_dir: &Path) {
+    let names = list_backups(data_dir);
+    if names.len() <= MAX_BACKUPS {
+        return;
+    }
+
+    let backups_dir = data_dir.join(BACKUP_DIR);
+    for name in &names[..names.len() - MAX_BACKUPS] {
+        let path: PathBuf = backups_dir.join(name);
+        if let Err(e) = std::fs::remove_dir_all(&path) {
+            log::warn!("清理旧备份失败 {:?}: {}", path, e);
+        } else {
+            log::debug!("pruned old backup: {}", name);
+        }
+    }
+}
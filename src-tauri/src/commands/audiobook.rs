@@ -0,0 +1,244 @@
+//! 有声书频道相关命令
+//!
+//! 把按集连载的音频（B 站合集、播客源等）做成频道：
+//! 集列表严格按顺序排列，每个频道持久化一个书签（当前集 + 集内位置），
+//! 起播时总是从书签记录的确切位置继续。
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::radio::{Station, ID_PREFIX_AUDIOBOOK};
+use crate::AppState;
+
+/// 有声书数据文件名
+const AUDIOBOOKS_FILE: &str = "audiobooks.json";
+
+/// 有声书的一集
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudiobookEpisode {
+    /// 本集标题
+    pub title: String,
+    /// 本集音频地址
+    pub url: String,
+}
+
+/// 有声书书签
+///
+/// 只记录一个位置：换集即清零集内位置，保证"从上次停下的地方继续"
+/// 永远指向一个确切的点。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudiobookBookmark {
+    /// 当前集序号（从 0 开始）
+    #[serde(default)]
+    pub episode: usize,
+    /// 集内位置（秒）
+    #[serde(default)]
+    pub position_secs: u64,
+}
+
+/// 有声书频道
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudiobookChannel {
+    /// 频道 ID（带 book: 前缀）
+    pub id: String,
+    /// 书名
+    pub name: String,
+    /// 集列表，顺序即播放顺序
+    pub episodes: Vec<AudiobookEpisode>,
+    /// 播放书签
+    #[serde(default)]
+    pub bookmark: AudiobookBookmark,
+}
+
+/// 从文件加载有声书频道
+pub(crate) fn load_audiobooks_from_file(data_dir: &std::path::Path) -> Vec<AudiobookChannel> {
+    let path = data_dir.join(AUDIOBOOKS_FILE);
+    if !path.exists() {
+        return Vec::new();
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 保存有声书频道到文件
+pub(crate) fn save_audiobooks_to_file(
+    data_dir: &std::path::Path,
+    channels: &[AudiobookChannel],
+) -> Result<(), String> {
+    let path = data_dir.join(AUDIOBOOKS_FILE);
+    let json = serde_json::to_string_pretty(channels).map_err(|e| e.to_string())?;
+    crate::utils::fs::write_atomic(&path, json).map_err(|e| e.to_string())?;
+    log::debug!("audiobooks saved: {:?}", path);
+    Ok(())
+}
+
+/// 把有声书频道合并进现有电台列表
+///
+/// 每个频道表现为一个电台，流地址指向书签所在的那一集，
+/// 换集后重新同步列表即可切到新地址。
+pub(crate) fn merge_audiobook_stations(data_dir: &std::path::Path, stations: &mut Vec<Station>) {
+    let channels = load_audiobooks_from_file(data_dir);
+    for channel in channels {
+        if channel.episodes.is_empty() || stations.iter().any(|s| s.id == channel.id) {
+            continue;
+        }
+        // 集列表缩短后书签可能越界，按最后一集处理
+        let index = channel.bookmark.episode.min(channel.episodes.len() - 1);
+        let episode = &channel.episodes[index];
+        stations.push(Station {
+            id: channel.id.clone(),
+            name: channel.name.clone(),
+            subtitle: format!("第 {}/{} 集 · {}", index + 1, channel.episodes.len(), episode.title),
+            image: String::new(),
+            province: "有声书".to_string(),
+            play_url_low: Some(episode.url.clone()),
+            mp3_play_url_low: None,
+            mp3_play_url_high: None,
+            is_custom: false,
+            bitrate: None,
+            language: None,
+            url_expires_at: None,
+        });
+    }
+}
+
+/// 查书签的集内位置（秒），供流服务器起播时做输入端偏移
+///
+/// 没有该频道或位置为 0 时返回 None。
+pub(crate) fn bookmark_offset(data_dir: &std::path::Path, station_id: &str) -> Option<u64> {
+    load_audiobooks_from_file(data_dir)
+        .into_iter()
+        .find(|channel| channel.id == station_id)
+        .map(|channel| channel.bookmark.position_secs)
+        .filter(|secs| *secs > 0)
+}
+
+/// 添加有声书频道
+#[tauri::command]
+pub async fn add_audiobook_channel(
+    name: String,
+    episodes: Vec<AudiobookEpisode>,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<AudiobookChannel, String> {
+    if name.trim().is_empty() {
+        return Err("书名不能为空".to_string());
+    }
+    if episodes.is_empty() {
+        return Err("集列表不能为空".to_string());
+    }
+    if episodes.iter().any(|ep| ep.url.trim().is_empty()) {
+        return Err("集列表中存在空地址".to_string());
+    }
+
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir().clone();
+
+    let channel = AudiobookChannel {
+        id: format!(
+            "{}{}",
+            ID_PREFIX_AUDIOBOOK,
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+        ),
+        name: name.trim().to_string(),
+        episodes,
+        bookmark: AudiobookBookmark::default(),
+    };
+
+    let mut channels = load_audiobooks_from_file(&data_dir);
+    channels.push(channel.clone());
+    save_audiobooks_to_file(&data_dir, &channels)?;
+
+    // 热更新到所有服务器实例
+    state.sync_stations_to_servers().await;
+
+    log::info!("添加有声书: {}（{} 集）", channel.name, channel.episodes.len());
+    Ok(channel)
+}
+
+/// 加载有声书频道列表
+#[tauri::command]
+pub async fn list_audiobook_channels(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<AudiobookChannel>, String> {
+    let state = state.lock().await;
+    Ok(load_audiobooks_from_file(state.crawler.data_dir()))
+}
+
+/// 更新有声书书签
+///
+/// 前端在播放过程中定期上报，停播后下次起播就能从这里继续。
+#[tauri::command]
+pub async fn set_bookmark(
+    channel_id: String,
+    episode: usize,
+    position_secs: u64,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir().clone();
+
+    let mut channels = load_audiobooks_from_file(&data_dir);
+    let channel = channels
+        .iter_mut()
+        .find(|c| c.id == channel_id)
+        .ok_or("未找到该有声书")?;
+    if episode >= channel.episodes.len() {
+        return Err(format!("集序号越界（共 {} 集）", channel.episodes.len()));
+    }
+
+    let episode_changed = channel.bookmark.episode != episode;
+    channel.bookmark = AudiobookBookmark {
+        episode,
+        position_secs,
+    };
+    save_audiobooks_to_file(&data_dir, &channels)?;
+
+    // 换集才需要重建电台列表，集内位置只在起播时读取
+    if episode_changed {
+        state.sync_stations_to_servers().await;
+    }
+    Ok(())
+}
+
+/// 跳转到指定集并从头播放
+#[tauri::command]
+pub async fn jump_to_episode(
+    channel_id: String,
+    episode: usize,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir().clone();
+
+    let mut channels = load_audiobooks_from_file(&data_dir);
+    let channel = channels
+        .iter_mut()
+        .find(|c| c.id == channel_id)
+        .ok_or("未找到该有声书")?;
+    if episode >= channel.episodes.len() {
+        return Err(format!("集序号越界（共 {} 集）", channel.episodes.len()));
+    }
+
+    channel.bookmark = AudiobookBookmark {
+        episode,
+        position_secs: 0,
+    };
+    let title = channel.episodes[episode].title.clone();
+    save_audiobooks_to_file(&data_dir, &channels)?;
+
+    // 热更新后该频道的流地址即指向新一集
+    state.sync_stations_to_servers().await;
+
+    log::info!("有声书跳转: {} -> 第 {} 集 {}", channel_id, episode + 1, title);
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! 数据备份相关命令
+
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use super::custom::merge_custom_stations;
+use crate::backup;
+use crate::AppState;
+
+/// 列出现有备份名称
+#[tauri::command]
+pub async fn list_backups(state: State<'_, Arc<Mutex<AppState>>>) -> Result<Vec<String>, String> {
+    let state = state.lock().await;
+    Ok(backup::list_backups(state.crawler.data_dir()))
+}
+
+/// 立即创建一份备份，返回备份名称
+#[tauri::command]
+pub async fn create_backup(state: State<'_, Arc<Mutex<AppState>>>) -> Result<String, String> {
+    let state = state.lock().await;
+    backup::create_backup(state.crawler.data_dir()).map_err(|e| e.to_string())
+}
+
+/// 从指定备份恢复数据文件，并重新加载电台数据
+#[tauri::command]
+pub async fn restore_backup(
+    name: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<String>, String> {
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir().clone();
+
+    let restored = backup::restore_backup(&data_dir, &name).map_err(|e| e.to_string())?;
+    state.logger.info(
+        "app",
+        format!("已从备份 {} 恢复 {} 个数据文件", name, restored.len()),
+    );
+
+    // 恢复后重新加载电台数据，与 load_saved_stations 保持一致
+    let stations = state.crawler.load_stations().map_err(|e| e.to_string())?;
+    state.crawler.set_stations(stations.clone()).await;
+
+    let mut stations_for_server = stations;
+    merge_custom_stations(&data_dir, &mut stations_for_server);
+    state
+        .server
+        .state()
+        .load_stations(stations_for_server)
+        .await;
+
+    Ok(restored)
+}
@@ -0,0 +1,147 @@
+//! B 站相关命令
+
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::radio::bilibili::{CdnBenchmark, SearchItem};
+use crate::AppState;
+
+/// 对指定 B 站电台的各 CDN 主机测速，帮助用户配置 CDN 偏好
+#[tauri::command]
+pub async fn benchmark_bilibili_cdn(
+    station_id: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<CdnBenchmark>, String> {
+    let server_state = {
+        let s = state.lock().await;
+        s.server.state()
+    };
+
+    let station = {
+        let stations = server_state.stations.read().await;
+        stations
+            .get(&station_id)
+            .cloned()
+            .ok_or_else(|| format!("电台不存在: {}", station_id))?
+    };
+
+    server_state
+        .bilibili
+        .benchmark_cdn(station.raw_id())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// B 站风控限制状态
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BilibiliRateLimit {
+    /// 是否正处于风控退避期
+    pub limited: bool,
+    /// 预计恢复的剩余秒数
+    pub retry_after_secs: u64,
+}
+
+/// 查询 B 站是否被风控限制，供 UI 展示"B 站暂时被限流"提示
+#[tauri::command]
+pub async fn get_bilibili_rate_limit(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<BilibiliRateLimit, String> {
+    let server_state = {
+        let s = state.lock().await;
+        s.server.state()
+    };
+    let remaining = server_state.bilibili.rate_limited_secs();
+    Ok(BilibiliRateLimit {
+        limited: remaining.is_some(),
+        retry_after_secs: remaining.unwrap_or(0),
+    })
+}
+
+/// B 站接口健康报告
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiHealthReport {
+    /// 是否正处于风控退避期
+    pub rate_limited: bool,
+    /// 结构漂移条目，按累计次数降序
+    pub schema_drifts: Vec<SchemaDriftEntry>,
+}
+
+/// 单条结构漂移记录
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaDriftEntry {
+    /// 漂移描述，形如 "playurl: dash.audio 不是数组"
+    pub issue: String,
+    /// 累计出现次数
+    pub count: u64,
+}
+
+/// 查询 B 站接口健康状况
+///
+/// 宽松解析让播放在 B 站小改接口时继续工作，这里把累计的
+/// 结构漂移报出来，字段真被改掉时维护者和用户能尽早知道。
+#[tauri::command]
+pub async fn get_api_health(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<ApiHealthReport, String> {
+    let server_state = {
+        let s = state.lock().await;
+        s.server.state()
+    };
+    let mut schema_drifts: Vec<SchemaDriftEntry> = server_state
+        .bilibili
+        .schema_drift_counts()
+        .into_iter()
+        .map(|(issue, count)| SchemaDriftEntry { issue, count })
+        .collect();
+    schema_drifts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.issue.cmp(&b.issue)));
+
+    Ok(ApiHealthReport {
+        rate_limited: server_state.bilibili.rate_limited_secs().is_some(),
+        schema_drifts,
+    })
+}
+
+/// 按关键词从 B 站搜索池里随机挑一个视频
+///
+/// 结果池按关键词缓存并探测真实页数，稀疏关键词不会再因为
+/// 盲选到空页而失败。
+#[tauri::command]
+pub async fn get_random_bilibili_audio(
+    keyword: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<SearchItem, String> {
+    let server_state = {
+        let s = state.lock().await;
+        s.server.state()
+    };
+    server_state
+        .bilibili
+        .get_random_audio(&keyword)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 上报一次 B 站播放结果（听完 / 跳过）
+///
+/// 行为统计按关键词和 UP 主累计，后续随机选台会偏向
+/// 用户真正听完的内容。
+#[tauri::command]
+pub async fn report_bilibili_playback(
+    keyword: String,
+    author: String,
+    completed: bool,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), String> {
+    let server_state = {
+        let s = state.lock().await;
+        s.server.state()
+    };
+    server_state
+        .bilibili
+        .note_playback_result(&keyword, &author, completed);
+    Ok(())
+}
@@ -6,12 +6,20 @@ use tauri::State;
 use tokio::sync::Mutex;
 
 use super::custom::merge_custom_stations;
+use crate::radio::sii::SiiMetadata;
 use crate::radio::SiiGenerator;
+use crate::settings::{load_settings_from_file, settings_fingerprint};
 use crate::AppState;
 
 /// 安装列表配置文件名
 const INSTALL_SELECTION_FILE: &str = "install_selection.json";
 
+/// SII 变体文件目录名
+const SII_VARIANTS_DIR: &str = "sii_variants";
+
+/// 停用电台列表文件名
+const DISABLED_STATIONS_FILE: &str = "disabled_stations.json";
+
 /// 安装列表状态
 #[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -20,13 +28,87 @@ pub struct InstallSelectionState {
     pub has_saved_selection: bool,
 }
 
+/// 按当前设置构建 SII 生成器
+fn build_generator(state: &AppState, port: u16) -> SiiGenerator {
+    let settings = load_settings_from_file(state.crawler.data_dir());
+    let mut generator = SiiGenerator::new("127.0.0.1", port)
+        .with_pin_central_stations(settings.pin_central_stations)
+        .with_default_bitrate(settings.transcode_bitrate_kbps)
+        .with_encoding(settings.sii_encoding)
+        .with_settings_hash(settings_fingerprint(&settings));
+    if settings.sii_order_by_play_count {
+        generator = generator
+            .with_play_counts(crate::radio::stream::load_play_counts(state.crawler.data_dir()));
+    }
+    generator
+}
+
 /// 合并自定义电台到电台列表
 async fn get_all_stations(state: &AppState) -> Vec<crate::radio::Station> {
     let mut stations = state.crawler.get_stations().await;
     merge_custom_stations(state.crawler.data_dir(), &mut stations);
+    super::vtc::merge_vtc_stations(state.crawler.data_dir(), &mut stations);
+    super::audiobook::merge_audiobook_stations(state.crawler.data_dir(), &mut stations);
+    remove_disabled_stations(state.crawler.data_dir(), &mut stations);
+    append_genre_channels(state.crawler.data_dir(), &mut stations);
     stations
 }
 
+/// 从文件加载停用电台 ID 集合
+pub(crate) fn load_disabled_station_ids(data_dir: &std::path::Path) -> HashSet<String> {
+    std::fs::read_to_string(data_dir.join(DISABLED_STATIONS_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// 从列表中剔除已停用的电台
+///
+/// 停用只影响 SII 生成：电台保留在数据和服务器端点里，
+/// 重新启用后无需重新爬取就回到生成结果中。
+pub(crate) fn remove_disabled_stations(
+    data_dir: &std::path::Path,
+    stations: &mut Vec<crate::radio::Station>,
+) {
+    let disabled = load_disabled_station_ids(data_dir);
+    if !disabled.is_empty() {
+        stations.retain(|station| !disabled.contains(&station.id));
+    }
+}
+
+/// 按设置附加流派聚合虚拟电台，每个流派一个条目
+pub(crate) fn append_genre_channels(
+    data_dir: &std::path::Path,
+    stations: &mut Vec<crate::radio::Station>,
+) {
+    let settings = load_settings_from_file(data_dir);
+    if !settings.genre_channels.enabled || stations.is_empty() {
+        return;
+    }
+
+    let genres: std::collections::BTreeSet<&'static str> = stations
+        .iter()
+        .map(|station| SiiGenerator::get_genre(station))
+        .collect();
+
+    for genre in genres {
+        stations.push(crate::radio::Station {
+            id: format!("{}{}", crate::radio::stream::GENRE_CHANNEL_PREFIX, genre),
+            name: format!("{}聚合台", SiiGenerator::genre_display_name(genre)),
+            subtitle: "流派聚合虚拟电台".to_string(),
+            image: String::new(),
+            province: "虚拟".to_string(),
+            play_url_low: None,
+            mp3_play_url_low: None,
+            mp3_play_url_high: None,
+            is_custom: false,
+            bitrate: None,
+            language: None,
+            url_expires_at: None,
+        });
+    }
+}
+
 /// 从文件加载安装列表
 pub(crate) fn load_install_selection_from_file(data_dir: &std::path::Path) -> Option<Vec<String>> {
     let path = data_dir.join(INSTALL_SELECTION_FILE);
@@ -45,7 +127,7 @@ pub(crate) fn save_install_selection_to_file(
 ) -> Result<(), String> {
     let path = data_dir.join(INSTALL_SELECTION_FILE);
     let json = serde_json::to_string_pretty(station_ids).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+    crate::utils::fs::write_atomic(&path, json).map_err(|e| e.to_string())?;
     log::debug!("install selection saved: {:?}", path);
     Ok(())
 }
@@ -87,7 +169,7 @@ pub async fn generate_sii(state: State<'_, Arc<Mutex<AppState>>>) -> Result<Stri
     }
 
     let port = *state.server.state().port.read().await;
-    let generator = SiiGenerator::new("127.0.0.1", port);
+    let generator = build_generator(&state, port);
     let content = generator.generate(&stations);
 
     // 保存到数据目录
@@ -118,7 +200,7 @@ pub async fn generate_sii_with_selection(
     }
 
     let port = *state.server.state().port.read().await;
-    let generator = SiiGenerator::new("127.0.0.1", port);
+    let generator = build_generator(&state, port);
     let content = generator.generate(&selected_stations);
 
     let path = state.crawler.data_dir().join("live_streams.sii");
@@ -129,9 +211,14 @@ pub async fn generate_sii_with_selection(
     Ok(path.to_string_lossy().to_string())
 }
 
-/// 安装 SII 到欧卡2目录
+/// 生成 SII 内容预览（不写入任何文件）
+///
+/// `station_ids` 为空时预览全部电台，否则仅预览选中的电台。
 #[tauri::command]
-pub async fn install_sii_to_ets2(state: State<'_, Arc<Mutex<AppState>>>) -> Result<String, String> {
+pub async fn generate_sii_preview(
+    station_ids: Vec<String>,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<String, String> {
     let state = state.lock().await;
 
     let stations = get_all_stations(&state).await;
@@ -139,8 +226,140 @@ pub async fn install_sii_to_ets2(state: State<'_, Arc<Mutex<AppState>>>) -> Resu
         return Err("没有电台数据，请先爬取电台".to_string());
     }
 
+    let selected_stations = if station_ids.is_empty() {
+        stations
+    } else {
+        let filtered = filter_stations_by_ids(stations, &station_ids);
+        if filtered.is_empty() {
+            return Err("请至少选择一个电台".to_string());
+        }
+        filtered
+    };
+
     let port = *state.server.state().port.read().await;
-    let generator = SiiGenerator::new("127.0.0.1", port);
+    let generator = build_generator(&state, port);
+
+    Ok(generator.generate(&selected_stations))
+}
+
+/// 生成多份 SII 变体文件（全量一份，每个流派各一份）
+///
+/// 变体保存在数据目录的 sii_variants 下，配合 `install_sii_variant`
+/// 可以快速切换游戏内的电台列表，无需重新生成。
+#[tauri::command]
+pub async fn generate_sii_variants(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<String>, String> {
+    let state = state.lock().await;
+
+    let stations = get_all_stations(&state).await;
+    if stations.is_empty() {
+        return Err("没有电台数据，请先爬取电台".to_string());
+    }
+
+    let port = *state.server.state().port.read().await;
+    let generator = build_generator(&state, port);
+    let variants_dir = state.crawler.data_dir().join(SII_VARIANTS_DIR);
+
+    // 全量变体 + 按流派分组的变体
+    let mut by_genre: std::collections::BTreeMap<&'static str, Vec<crate::radio::Station>> =
+        std::collections::BTreeMap::new();
+    for station in &stations {
+        by_genre
+            .entry(SiiGenerator::get_genre(station))
+            .or_default()
+            .push(station.clone());
+    }
+
+    let mut groups: Vec<(String, Vec<crate::radio::Station>)> =
+        vec![("all".to_string(), stations)];
+    groups.extend(
+        by_genre
+            .into_iter()
+            .map(|(genre, list)| (genre.to_string(), list)),
+    );
+
+    let mut names = Vec::new();
+    for (name, group) in groups {
+        let content = generator.generate(&group);
+        let path = variants_dir.join(format!("{}.sii", name));
+        generator
+            .save_to_file(&content, &path)
+            .map_err(|e| e.to_string())?;
+        names.push(name);
+    }
+
+    Ok(names)
+}
+
+/// 列出已生成的 SII 变体名
+#[tauri::command]
+pub async fn list_sii_variants(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<String>, String> {
+    let state = state.lock().await;
+    let variants_dir = state.crawler.data_dir().join(SII_VARIANTS_DIR);
+
+    let mut names = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&variants_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("sii") {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    names.push(stem.to_string());
+                }
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// 把指定 SII 变体安装到欧卡2目录
+///
+/// 直接复制已生成的变体文件，避免重复编码处理。
+#[tauri::command]
+pub async fn install_sii_variant(
+    name: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<String, String> {
+    // 变体名来自文件名，不允许路径穿越
+    if name.is_empty() || name.contains(['/', '\\', '.']) {
+        return Err("无效的变体名".to_string());
+    }
+
+    let state = state.lock().await;
+    let path = state
+        .crawler
+        .data_dir()
+        .join(SII_VARIANTS_DIR)
+        .join(format!("{}.sii", name));
+    let bytes =
+        std::fs::read(&path).map_err(|_| "变体文件不存在，请先生成变体".to_string())?;
+
+    let ets2_paths = SiiGenerator::detect_ets2_paths();
+    if ets2_paths.is_empty() {
+        return Err("未找到欧卡2文档目录".to_string());
+    }
+
+    let target = ets2_paths[0].join("live_streams.sii");
+    crate::utils::fs::write_atomic(&target, bytes).map_err(|e| e.to_string())?;
+    log::info!("SII 变体已安装: {} -> {:?}", name, target);
+
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// 重新生成全量 SII 并安装到游戏目录
+///
+/// `install_sii_to_ets2` 的内部实现，端口变化后的自动重装也走这里。
+pub(crate) async fn regenerate_and_install_sii(state: &AppState) -> Result<String, String> {
+    let stations = get_all_stations(state).await;
+    if stations.is_empty() {
+        return Err("没有电台数据，请先爬取电台".to_string());
+    }
+
+    let port = *state.server.state().port.read().await;
+    let generator = build_generator(state, port);
     let content = generator.generate(&stations);
 
     let path = generator
@@ -150,6 +369,22 @@ pub async fn install_sii_to_ets2(state: State<'_, Arc<Mutex<AppState>>>) -> Resu
     Ok(path.to_string_lossy().to_string())
 }
 
+/// 游戏目录中已安装 SII 的元数据；文件缺失或非本应用生成时为 None
+pub(crate) fn installed_sii_metadata() -> Option<SiiMetadata> {
+    let path = SiiGenerator::detect_ets2_paths()
+        .first()?
+        .join("live_streams.sii");
+    let content = std::fs::read_to_string(path).ok()?;
+    SiiGenerator::parse_metadata(&content)
+}
+
+/// 安装 SII 到欧卡2目录
+#[tauri::command]
+pub async fn install_sii_to_ets2(state: State<'_, Arc<Mutex<AppState>>>) -> Result<String, String> {
+    let state = state.lock().await;
+    regenerate_and_install_sii(&state).await
+}
+
 /// 安装选中电台到欧卡2目录
 #[tauri::command]
 pub async fn install_sii_to_ets2_with_selection(
@@ -169,7 +404,7 @@ pub async fn install_sii_to_ets2_with_selection(
     }
 
     let port = *state.server.state().port.read().await;
-    let generator = SiiGenerator::new("127.0.0.1", port);
+    let generator = build_generator(&state, port);
     let content = generator.generate(&selected_stations);
 
     let path = generator
@@ -179,6 +414,91 @@ pub async fn install_sii_to_ets2_with_selection(
     Ok(path.to_string_lossy().to_string())
 }
 
+/// 为每个启用的额外服务器实例生成独立 SII 文件
+///
+/// 文件写到数据目录，名为 `live_streams_{port}.sii`，地址和默认
+/// 码率都指向对应实例，用户可自行装到需要的地方。
+#[tauri::command]
+pub async fn generate_extra_server_sii(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<String>, String> {
+    let state = state.lock().await;
+
+    let stations = get_all_stations(&state).await;
+    if stations.is_empty() {
+        return Err("没有电台数据，请先爬取电台".to_string());
+    }
+
+    let settings = load_settings_from_file(state.crawler.data_dir());
+    let mut paths = Vec::new();
+    for cfg in settings.extra_servers.iter().filter(|cfg| cfg.enabled) {
+        let generator = SiiGenerator::new("127.0.0.1", cfg.port)
+            .with_pin_central_stations(settings.pin_central_stations)
+            .with_default_bitrate(
+                cfg.transcode_bitrate_kbps
+                    .unwrap_or(settings.transcode_bitrate_kbps),
+            )
+            .with_encoding(settings.sii_encoding)
+            .with_settings_hash(settings_fingerprint(&settings));
+        let content = generator.generate(&stations);
+        let path = state
+            .crawler
+            .data_dir()
+            .join(format!("live_streams_{}.sii", cfg.port));
+        generator
+            .save_to_file(&content, &path)
+            .map_err(|e| e.to_string())?;
+        paths.push(path.to_string_lossy().to_string());
+    }
+
+    Ok(paths)
+}
+
+/// 已安装 SII 文件的元数据检查结果
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledSiiInfo {
+    pub path: String,
+    /// 是否由本应用生成（存在元数据块）
+    pub ours: bool,
+    /// 端口或设置与当前不一致，建议重新生成安装
+    pub stale: bool,
+    pub metadata: Option<SiiMetadata>,
+}
+
+/// 读取游戏目录中已安装 SII 的元数据块
+///
+/// 用于判断游戏里的 live_streams.sii 是否出自本应用、
+/// 是否因端口或设置变更而过期。
+#[tauri::command]
+pub async fn read_installed_sii_metadata(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<InstalledSiiInfo, String> {
+    let ets2_paths = SiiGenerator::detect_ets2_paths();
+    if ets2_paths.is_empty() {
+        return Err("未找到欧卡2文档目录".to_string());
+    }
+    let path = ets2_paths[0].join("live_streams.sii");
+    let content = std::fs::read_to_string(&path)
+        .map_err(|_| "游戏目录中没有 live_streams.sii".to_string())?;
+    let metadata = SiiGenerator::parse_metadata(&content);
+
+    let state = state.lock().await;
+    let port = *state.server.state().port.read().await;
+    let settings = load_settings_from_file(state.crawler.data_dir());
+    let current_hash = settings_fingerprint(&settings);
+
+    let stale = metadata
+        .as_ref()
+        .is_some_and(|meta| meta.port != port || meta.settings_hash != current_hash);
+    Ok(InstalledSiiInfo {
+        path: path.to_string_lossy().to_string(),
+        ours: metadata.is_some(),
+        stale,
+        metadata,
+    })
+}
+
 /// 获取欧卡2文档目录
 #[tauri::command]
 pub fn get_ets2_paths() -> Vec<String> {
@@ -215,6 +535,124 @@ pub async fn load_install_selection(
     }
 }
 
+/// 电台名转写审计结果条目
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationNameIssue {
+    pub station_id: String,
+    /// 原始中文名
+    pub station_name: String,
+    /// 自动转写得到的英文名
+    pub english_name: String,
+    /// 问题类型: duplicate（与其他电台转写后撞名）/ fallback（落入通用兜底名）
+    pub issue: String,
+}
+
+/// 审计自动转写的电台英文名质量
+///
+/// 找出转写后与其他电台撞名（在 SII 里无法区分）或落入
+/// "CN Radio N" 这类通用兜底名的电台，提示用户手动改名。
+#[tauri::command]
+pub async fn audit_station_names(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<StationNameIssue>, String> {
+    let state = state.lock().await;
+
+    let stations = get_all_stations(&state).await;
+    if stations.is_empty() {
+        return Err("没有电台数据，请先爬取电台".to_string());
+    }
+
+    // 统计各英文名出现次数，识别撞名
+    let mut name_counts: HashMap<String, usize> = HashMap::new();
+    let english_names: Vec<String> = stations
+        .iter()
+        .map(|station| {
+            let english = SiiGenerator::to_english_name(&station.name);
+            *name_counts.entry(english.clone()).or_insert(0) += 1;
+            english
+        })
+        .collect();
+
+    let mut issues = Vec::new();
+    for (station, english) in stations.iter().zip(english_names) {
+        let is_fallback =
+            !english.is_ascii() || english == "Radio CN" || english.starts_with("CN Radio ");
+        let issue = if is_fallback {
+            "fallback"
+        } else if name_counts.get(&english).copied().unwrap_or(0) > 1 {
+            "duplicate"
+        } else {
+            continue;
+        };
+        issues.push(StationNameIssue {
+            station_id: station.id.clone(),
+            station_name: station.name.clone(),
+            english_name: english,
+            issue: issue.to_string(),
+        });
+    }
+
+    // 撞名的排一起，方便用户逐组处理
+    issues.sort_by(|a, b| {
+        a.english_name
+            .cmp(&b.english_name)
+            .then_with(|| a.station_id.cmp(&b.station_id))
+    });
+    Ok(issues)
+}
+
+/// 批量启用 / 停用电台的 SII 成员资格
+///
+/// 停用的电台保留在数据和服务器里，只是不再写入生成的 SII，
+/// 取代以前"要么全量要么每次手动挑选"的方式。返回当前停用总数。
+#[tauri::command]
+pub async fn set_stations_enabled(
+    station_ids: Vec<String>,
+    enabled: bool,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<usize, String> {
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir();
+
+    let mut disabled = load_disabled_station_ids(data_dir);
+    for station_id in &station_ids {
+        if enabled {
+            disabled.remove(station_id);
+        } else {
+            disabled.insert(station_id.clone());
+        }
+    }
+
+    // 排序后落盘，保证文件内容稳定、diff 友好
+    let mut sorted: Vec<&String> = disabled.iter().collect();
+    sorted.sort();
+    let json = serde_json::to_string_pretty(&sorted).map_err(|e| e.to_string())?;
+    crate::utils::fs::write_atomic(&data_dir.join(DISABLED_STATIONS_FILE), json)
+        .map_err(|e| e.to_string())?;
+
+    log::info!(
+        "电台启用状态已更新: {} 个电台设为{}，当前共停用 {} 个",
+        station_ids.len(),
+        if enabled { "启用" } else { "停用" },
+        disabled.len()
+    );
+    Ok(disabled.len())
+}
+
+/// 读取当前停用的电台 ID 列表
+#[tauri::command]
+pub async fn get_disabled_stations(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<String>, String> {
+    let state = state.lock().await;
+    let mut station_ids: Vec<String> = load_disabled_station_ids(state.crawler.data_dir())
+        .into_iter()
+        .collect();
+    station_ids.sort();
+    Ok(station_ids)
+}
+
 /// 保存安装列表
 #[tauri::command]
 pub async fn save_install_selection(
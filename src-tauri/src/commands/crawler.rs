@@ -4,7 +4,6 @@ use std::sync::Arc;
 use tauri::{AppHandle, Emitter, State};
 use tokio::sync::Mutex;
 
-use super::custom::merge_custom_stations;
 use crate::radio::{get_province_stats, CrawlProgress, Station};
 use crate::AppState;
 
@@ -22,10 +21,13 @@ pub async fn crawl_stations(
     app: AppHandle,
     state: State<'_, Arc<Mutex<AppState>>>,
 ) -> Result<Vec<Station>, String> {
-    // 获取 data_dir，然后立即释放锁
-    let data_dir = {
+    // 获取 data_dir 和进度广播句柄，然后立即释放锁
+    let (data_dir, progress_tx) = {
         let s = state.lock().await;
-        s.crawler.data_dir().clone()
+        (
+            s.crawler.data_dir().clone(),
+            s.server.state().crawl_progress_tx.clone(),
+        )
     };
 
     // 创建一个临时的爬虫实例进行爬取（不持有锁）
@@ -43,6 +45,8 @@ pub async fn crawl_stations(
                 progress.stations_found
             );
             let _ = app_clone.emit("crawl-progress", &progress);
+            // 同步推送给 HTTP SSE 订阅者
+            let _ = progress_tx.send(progress);
         })
         .await
         .map_err(|e| {
@@ -52,13 +56,11 @@ pub async fn crawl_stations(
 
     log::info!("电台数据刷新完成: {}", stations.len());
 
-    // 重新获取锁来更新状态
+    // 重新获取锁来更新状态，并热更新到所有服务器实例
     {
         let s = state.lock().await;
         s.crawler.set_stations(stations.clone()).await;
-        let mut stations_for_server = stations.clone();
-        merge_custom_stations(s.crawler.data_dir(), &mut stations_for_server);
-        s.server.state().load_stations(stations_for_server).await;
+        s.sync_stations_to_servers().await;
     }
 
     Ok(stations)
@@ -74,6 +76,50 @@ pub async fn get_province_statistics(
     Ok(get_province_stats(&stations))
 }
 
+/// 本地电台推荐结果
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalStationSuggestion {
+    /// 检测到的省份
+    pub province: String,
+    /// 电台列表，检测省份的电台排在最前
+    pub stations: Vec<Station>,
+}
+
+/// 按 IP 归属地推荐本地电台
+///
+/// 检测所在省份并把该省电台排到最前，帮助新用户完成初次选择。
+/// 检测失败时返回错误，前端回退到默认列表即可。
+#[tauri::command]
+pub async fn suggest_local_stations(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<LocalStationSuggestion, String> {
+    let province = crate::utils::geo::detect_province()
+        .await
+        .ok_or("无法检测所在地区")?;
+
+    let state = state.lock().await;
+    let stations = state.crawler.get_stations().await;
+    if stations.is_empty() {
+        return Err("没有电台数据，请先爬取电台".to_string());
+    }
+
+    let (mut local, rest): (Vec<Station>, Vec<Station>) = stations
+        .into_iter()
+        .partition(|station| station.province == province);
+    let local_count = local.len();
+    local.extend(rest);
+
+    state.logger.info(
+        "crawler",
+        format!("检测到所在省份: {}，本地电台 {} 个", province, local_count),
+    );
+    Ok(LocalStationSuggestion {
+        province,
+        stations: local,
+    })
+}
+
 /// 加载已保存的电台数据
 #[tauri::command]
 pub async fn load_saved_stations(
@@ -83,17 +129,9 @@ pub async fn load_saved_stations(
 
     let stations = state.crawler.load_stations().map_err(|e| e.to_string())?;
 
-    // 更新缓存
+    // 更新缓存并热更新到所有服务器实例
     state.crawler.set_stations(stations.clone()).await;
-
-    // 更新服务器
-    let mut stations_for_server = stations.clone();
-    merge_custom_stations(state.crawler.data_dir(), &mut stations_for_server);
-    state
-        .server
-        .state()
-        .load_stations(stations_for_server)
-        .await;
+    state.sync_stations_to_servers().await;
 
     Ok(stations)
 }
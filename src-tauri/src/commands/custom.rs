@@ -6,12 +6,50 @@ use tauri::State;
 use tokio::sync::Mutex;
 
 use super::config::{load_install_selection_from_file, save_install_selection_to_file};
-use crate::radio::Station;
+use crate::radio::{Station, ID_PREFIX_CUSTOM};
 use crate::AppState;
 
 /// 自定义电台数据文件名
 const CUSTOM_STATIONS_FILE: &str = "custom_stations.json";
 
+/// 频道图标目录名
+const CHANNEL_IMAGES_DIR: &str = "channel_images";
+
+/// 频道图标允许的扩展名
+const ALLOWED_IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "gif"];
+
+/// 频道 ID 转图标文件名主干
+///
+/// ID 中的冒号等字符不适合做文件名，统一替换为下划线。
+fn channel_image_stem(channel_id: &str) -> String {
+    channel_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// 查找频道已保存的图标文件
+pub(crate) fn find_channel_image(
+    data_dir: &std::path::Path,
+    channel_id: &str,
+) -> Option<std::path::PathBuf> {
+    let stem = channel_image_stem(channel_id);
+    ALLOWED_IMAGE_EXTENSIONS
+        .iter()
+        .map(|ext| {
+            data_dir
+                .join(CHANNEL_IMAGES_DIR)
+                .join(format!("{}.{}", stem, ext))
+        })
+        .find(|path| path.exists())
+}
+
 /// 从文件加载自定义电台
 pub(crate) fn load_custom_stations_from_file(data_dir: &std::path::Path) -> Vec<Station> {
     let path = data_dir.join(CUSTOM_STATIONS_FILE);
@@ -40,13 +78,13 @@ pub(crate) fn merge_custom_stations(data_dir: &std::path::Path, stations: &mut V
 }
 
 /// 保存自定义电台到文件
-fn save_custom_stations_to_file(
+pub(crate) fn save_custom_stations_to_file(
     data_dir: &std::path::Path,
     stations: &[Station],
 ) -> Result<(), String> {
     let path = data_dir.join(CUSTOM_STATIONS_FILE);
     let json = serde_json::to_string_pretty(stations).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+    crate::utils::fs::write_atomic(&path, json).map_err(|e| e.to_string())?;
     log::debug!("custom stations saved: {:?}", path);
     Ok(())
 }
@@ -61,12 +99,8 @@ pub async fn load_custom_stations(
     let custom_stations = load_custom_stations_from_file(data_dir);
     log::debug!("custom stations loaded: {}", custom_stations.len());
 
-    // 同步到服务器状态
-    let server_state = state.server.state();
-    for station in &custom_stations {
-        let mut stations_map = server_state.stations.write().await;
-        stations_map.insert(station.id.clone(), station.clone());
-    }
+    // 热更新到所有服务器实例
+    state.sync_stations_to_servers().await;
 
     Ok(custom_stations)
 }
@@ -88,9 +122,10 @@ pub async fn add_custom_station(
     let state = state.lock().await;
     let data_dir = state.crawler.data_dir().clone();
 
-    // 生成唯一 ID
+    // 生成唯一 ID，带来源命名空间前缀（旧数据中的 custom_ 前缀仍然兼容）
     let id = format!(
-        "custom_{}",
+        "{}{}",
+        ID_PREFIX_CUSTOM,
         std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default()
@@ -107,6 +142,9 @@ pub async fn add_custom_station(
         mp3_play_url_low: None,
         mp3_play_url_high: None,
         is_custom: true,
+        bitrate: None,
+        language: Station::detect_language(name.trim()),
+        url_expires_at: None,
     };
 
     // 加载现有自定义电台并追加
@@ -114,12 +152,8 @@ pub async fn add_custom_station(
     custom_stations.push(station.clone());
     save_custom_stations_to_file(&data_dir, &custom_stations)?;
 
-    // 同步到服务器状态
-    let server_state = state.server.state();
-    {
-        let mut stations_map = server_state.stations.write().await;
-        stations_map.insert(station.id.clone(), station.clone());
-    }
+    // 热更新到所有服务器实例
+    state.sync_stations_to_servers().await;
 
     log::info!("添加自定义电台: {}", station.name);
     Ok(station)
@@ -154,17 +188,71 @@ pub async fn remove_custom_station(
         }
     }
 
-    // 从服务器状态中移除
-    let server_state = state.server.state();
-    {
-        let mut stations_map = server_state.stations.write().await;
-        stations_map.remove(&id);
-    }
+    // 热更新到所有服务器实例（重建列表后已不含被删电台）
+    state.sync_stations_to_servers().await;
 
     log::info!("删除自定义电台: {}", id);
     Ok(())
 }
 
+/// 设置频道图标
+///
+/// 把本地图片复制到数据目录并通过服务器的 `/images/:id` 端点提供，
+/// 自定义和 B 站频道在桌面端、网页端都能显示像样的台标。
+/// 频道在自定义电台列表里时顺带更新其 image 字段并持久化。
+#[tauri::command]
+pub async fn set_channel_image(
+    channel_id: String,
+    file_path: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<String, String> {
+    if channel_id.trim().is_empty() {
+        return Err("频道 ID 不能为空".to_string());
+    }
+    let source = std::path::PathBuf::from(file_path.trim());
+    if !source.is_file() {
+        return Err("图片文件不存在".to_string());
+    }
+    let ext = source
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+    if !ALLOWED_IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        return Err(format!(
+            "不支持的图片格式，请使用 {}",
+            ALLOWED_IMAGE_EXTENSIONS.join(" / ")
+        ));
+    }
+
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir().clone();
+    let images_dir = data_dir.join(CHANNEL_IMAGES_DIR);
+    std::fs::create_dir_all(&images_dir).map_err(|e| e.to_string())?;
+
+    // 先清掉其他扩展名的旧图标，保证每个频道只有一份
+    let stem = channel_image_stem(&channel_id);
+    for old_ext in ALLOWED_IMAGE_EXTENSIONS {
+        let _ = std::fs::remove_file(images_dir.join(format!("{}.{}", stem, old_ext)));
+    }
+    let target = images_dir.join(format!("{}.{}", stem, ext));
+    std::fs::copy(&source, &target).map_err(|e| e.to_string())?;
+
+    let port = *state.server.state().port.read().await;
+    let image_url = format!("http://127.0.0.1:{}/images/{}", port, channel_id);
+
+    // 自定义电台列表里有这个频道时，把图标地址写回并持久化
+    let mut custom_stations = load_custom_stations_from_file(&data_dir);
+    if let Some(station) = custom_stations.iter_mut().find(|s| s.id == channel_id) {
+        station.image = image_url.clone();
+        save_custom_stations_to_file(&data_dir, &custom_stations)?;
+        state.sync_stations_to_servers().await;
+    }
+
+    log::info!("频道图标已更新: {} -> {:?}", channel_id, target);
+    Ok(image_url)
+}
+
 /// 更新自定义电台
 #[tauri::command]
 pub async fn update_custom_station(
@@ -196,12 +284,8 @@ pub async fn update_custom_station(
     let updated = station.clone();
     save_custom_stations_to_file(&data_dir, &custom_stations)?;
 
-    // 同步到服务器状态
-    let server_state = state.server.state();
-    {
-        let mut stations_map = server_state.stations.write().await;
-        stations_map.insert(id.clone(), updated.clone());
-    }
+    // 热更新到所有服务器实例
+    state.sync_stations_to_servers().await;
 
     log::info!("更新自定义电台: {}", updated.name);
     Ok(updated)
@@ -16,6 +16,15 @@ pub async fn get_diagnostic_logs(
     Ok(state.logger.recent())
 }
 
+/// 获取启动阶段记录的错误。
+///
+/// 数据目录不可用等问题发生在窗口可用之前，前端起来后
+/// 用这个命令拉取并给出处理建议。
+#[tauri::command]
+pub async fn get_startup_errors() -> Result<Vec<String>, String> {
+    Ok(crate::startup_errors())
+}
+
 /// 清空诊断日志。
 #[tauri::command]
 pub async fn clear_diagnostic_logs(state: State<'_, Arc<Mutex<AppState>>>) -> Result<(), String> {
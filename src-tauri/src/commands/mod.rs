@@ -1,13 +1,29 @@
 //! Tauri 命令模块
 
+pub mod audiobook;
+pub mod backup;
+pub mod bilibili;
 pub mod config;
 pub mod crawler;
 pub mod custom;
 pub mod logs;
 pub mod server;
+pub mod settings;
+pub mod share;
+pub mod storage;
+pub mod tags;
+pub mod vtc;
 
+pub use audiobook::*;
+pub use backup::*;
+pub use bilibili::*;
 pub use config::*;
 pub use crawler::*;
 pub use custom::*;
 pub use logs::*;
 pub use server::*;
+pub use settings::*;
+pub use share::*;
+pub use storage::*;
+pub use tags::*;
+pub use vtc::*;
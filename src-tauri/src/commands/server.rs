@@ -5,14 +5,37 @@ use tauri::State;
 use tokio::sync::Mutex;
 
 use super::custom::merge_custom_stations;
+use crate::radio::stream::{
+    genre_playlist, tour_playlist, GENRE_CHANNEL_PREFIX, INTERRUPT_CHANNEL_ID, TOUR_CHANNEL_ID,
+};
 use crate::radio::ServerStatus;
 use crate::AppState;
 
+/// 被空闲自动停止的服务器在用户再次操作时自动拉起
+pub(crate) async fn resume_if_auto_stopped(state: &mut AppState) {
+    use std::sync::atomic::Ordering;
+    if !state.auto_stopped.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    match state.server.start().await {
+        Ok(()) => state
+            .logger
+            .info("server", "检测到新的操作，已自动恢复被空闲停止的服务器"),
+        Err(e) => state
+            .logger
+            .warn("server", "自动恢复服务器失败", Some(e.to_string())),
+    }
+}
+
 /// 启动流媒体服务器
 #[tauri::command]
 pub async fn start_server(state: State<'_, Arc<Mutex<AppState>>>) -> Result<(), String> {
     let mut state = state.lock().await;
     state.logger.info("server", "收到启动服务器请求");
+    // 用户手动启动，清除空闲停止标记
+    state
+        .auto_stopped
+        .store(false, std::sync::atomic::Ordering::Relaxed);
 
     // 确保电台数据已加载到服务器，并合并自定义电台。
     let mut stations = state.crawler.get_stations().await;
@@ -34,11 +57,24 @@ pub async fn start_server(state: State<'_, Arc<Mutex<AppState>>>) -> Result<(),
             .info("server", format!("已合并自定义电台: {}", custom_count));
     }
 
-    state.server.state().load_stations(stations).await;
+    state.server.state().load_stations(stations.clone()).await;
 
     // 启动服务器
     state.server.start().await.map_err(|e| e.to_string())?;
 
+    // 启动已启用的额外实例（局域网收听等），失败不影响主实例
+    let logger = state.logger.clone();
+    for server in state.extra_servers.iter_mut() {
+        server.state().load_stations(stations.clone()).await;
+        match server.start().await {
+            Ok(()) => {
+                let port = *server.state().port.read().await;
+                logger.info("server", format!("额外服务器实例已启动，端口 {}", port));
+            }
+            Err(e) => logger.warn("server", "额外服务器实例启动失败", Some(e.to_string())),
+        }
+    }
+
     let status = state.server.state().get_status().await;
     log::info!("服务器已启动，可用电台: {}", status.total_stations);
     state.logger.info(
@@ -46,6 +82,43 @@ pub async fn start_server(state: State<'_, Arc<Mutex<AppState>>>) -> Result<(),
         format!("服务器可用电台: {}", status.total_stations),
     );
 
+    // 端口被占用时会自动切换；游戏里的 SII 若还指向旧端口，
+    // 电台会集体失效——按设置自动重装，或提醒用户重新生成。
+    if let Some(meta) = super::config::installed_sii_metadata() {
+        if meta.port != status.port {
+            let settings = crate::settings::load_settings_from_file(state.crawler.data_dir());
+            if settings.auto_reinstall_sii {
+                match super::config::regenerate_and_install_sii(&state).await {
+                    Ok(path) => state.logger.info(
+                        "server",
+                        format!("端口已变为 {}，SII 已自动重新安装: {}", status.port, path),
+                    ),
+                    Err(e) => {
+                        state
+                            .logger
+                            .warn("server", "端口变化后自动重装 SII 失败", Some(e))
+                    }
+                }
+            } else {
+                state.logger.warn(
+                    "server",
+                    format!(
+                        "游戏中的 SII 指向端口 {}，当前端口为 {}，请重新生成并安装",
+                        meta.port, status.port
+                    ),
+                    None::<String>,
+                );
+                state.logger.emit_event(
+                    "sii-stale",
+                    serde_json::json!({
+                        "installedPort": meta.port,
+                        "currentPort": status.port,
+                    }),
+                );
+            }
+        }
+    }
+
     Ok(())
 }
 
@@ -54,7 +127,14 @@ pub async fn start_server(state: State<'_, Arc<Mutex<AppState>>>) -> Result<(),
 pub async fn stop_server(state: State<'_, Arc<Mutex<AppState>>>) -> Result<(), String> {
     let mut state = state.lock().await;
     state.logger.info("server", "收到停止服务器请求");
+    // 用户手动停止，不再视为空闲停止
+    state
+        .auto_stopped
+        .store(false, std::sync::atomic::Ordering::Relaxed);
     state.server.stop().await;
+    for server in state.extra_servers.iter_mut() {
+        server.stop().await;
+    }
     log::info!("服务器已停止");
     Ok(())
 }
@@ -69,6 +149,68 @@ pub async fn stop_active_streams(state: State<'_, Arc<Mutex<AppState>>>) -> Resu
     Ok(())
 }
 
+/// 暂停所有活动流（挂起 FFmpeg 进程，不终止）
+///
+/// 锁屏等场景临时静音，恢复时无需重连，返回受影响的流数量。
+#[tauri::command]
+pub async fn pause_all_streams(state: State<'_, Arc<Mutex<AppState>>>) -> Result<usize, String> {
+    let state = state.lock().await;
+    state.logger.info("stream", "收到暂停全部流请求");
+    let mut count = state.server.state().pause_all_streams().await;
+    for server in &state.extra_servers {
+        count += server.state().pause_all_streams().await;
+    }
+    Ok(count)
+}
+
+/// 恢复所有被暂停的流，返回受影响的流数量
+#[tauri::command]
+pub async fn resume_all_streams(state: State<'_, Arc<Mutex<AppState>>>) -> Result<usize, String> {
+    let state = state.lock().await;
+    state.logger.info("stream", "收到恢复全部流请求");
+    let mut count = state.server.state().resume_all_streams().await;
+    for server in &state.extra_servers {
+        count += server.state().resume_all_streams().await;
+    }
+    Ok(count)
+}
+
+/// 设置流媒体服务器的首选监听端口并持久化
+///
+/// 服务器正在运行时立即按新端口重启（重启失败返回错误）；
+/// 未运行时只更新配置，下次启动生效。
+#[tauri::command]
+pub async fn set_server_port(
+    port: u16,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), String> {
+    if port < 1024 {
+        return Err("端口需在 1024-65535 之间".to_string());
+    }
+
+    let mut state = state.lock().await;
+    let data_dir = state.crawler.data_dir();
+    let mut settings = crate::settings::load_settings_from_file(data_dir);
+    settings.server_port = port;
+    crate::settings::save_settings_to_file(data_dir, &settings)?;
+
+    state.server.set_port(port).await;
+    state
+        .logger
+        .info("server", format!("首选端口已设置为 {}", port));
+
+    // 运行中立即重启按新端口监听，游戏端只需重连一次
+    if state.server.is_running() {
+        state.server.stop().await;
+        state.server.start().await.map_err(|e| e.to_string())?;
+        let actual = *state.server.state().port.read().await;
+        state
+            .logger
+            .info("server", format!("服务器已按新端口重启，实际监听 {}", actual));
+    }
+    Ok(())
+}
+
 /// 获取服务器状态
 #[tauri::command]
 pub async fn get_server_status(
@@ -80,11 +222,633 @@ pub async fn get_server_status(
     let port = *server_state.port.read().await;
     let active_streams = server_state.active_streams.read().await.len();
     let total_stations = server_state.stations.read().await.len();
+    let (startup_latency_p50_ms, startup_latency_p95_ms) =
+        server_state.startup_latency_percentiles().await;
 
     Ok(ServerStatus {
         running: is_running,
         port,
         active_streams,
         total_stations,
+        startup_latency_p50_ms,
+        startup_latency_p95_ms,
+    })
+}
+
+/// 电台吞吐统计，供 UI 画实时码率曲线
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamStats {
+    pub station_id: String,
+    /// 每秒一个 kbps 样本，旧的在前
+    pub kbps_samples: Vec<u32>,
+}
+
+/// 获取指定电台的实时吞吐样本
+#[tauri::command]
+pub async fn get_stream_stats(
+    station_id: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<StreamStats, String> {
+    let server_state = {
+        let s = state.lock().await;
+        s.server.state()
+    };
+    let kbps_samples = server_state.get_stream_stats(&station_id).await;
+    Ok(StreamStats {
+        station_id,
+        kbps_samples,
     })
 }
+
+/// 服务器可达性自检结果
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReachabilityReport {
+    /// 实际测试的地址
+    pub url: String,
+    /// 是否请求成功
+    pub reachable: bool,
+    /// 面向用户的诊断结论
+    pub diagnosis: String,
+}
+
+/// 从后端对 host:port/health 发起真实 HTTP 请求，诊断游戏里看不到电台的原因
+///
+/// `host` 不传时测本机回环地址；填局域网 IP 可以排查防火墙问题。
+#[tauri::command]
+pub async fn test_server_reachability(
+    host: Option<String>,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<ReachabilityReport, String> {
+    let (running, port, logger) = {
+        let s = state.lock().await;
+        let status = s.server.state().get_status().await;
+        (status.running, status.port, s.logger.clone())
+    };
+
+    let host = host
+        .filter(|h| !h.trim().is_empty())
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    let is_loopback = host == "127.0.0.1" || host == "localhost";
+    let url = format!("http://{}:{}/health", host, port);
+
+    if !running {
+        return Ok(ReachabilityReport {
+            url,
+            reachable: false,
+            diagnosis: "服务器未启动，请先启动流媒体服务器".to_string(),
+        });
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let report = match client.get(&url).send().await {
+        Ok(response) if response.status().is_success() => ReachabilityReport {
+            url,
+            reachable: true,
+            diagnosis: "服务器可达。游戏内仍看不到电台时，请确认 SII 文件已安装且其中端口一致"
+                .to_string(),
+        },
+        Ok(response) => ReachabilityReport {
+            url,
+            reachable: false,
+            diagnosis: format!(
+                "收到异常状态码 {}，该端口可能被其他程序占用",
+                response.status()
+            ),
+        },
+        Err(e) if e.is_timeout() => ReachabilityReport {
+            url,
+            reachable: false,
+            diagnosis: if is_loopback {
+                "连接超时，本机防火墙或安全软件可能拦截了该端口".to_string()
+            } else {
+                "连接超时，请检查防火墙是否放行该端口，或主机地址是否正确".to_string()
+            },
+        },
+        Err(e) if e.is_connect() => ReachabilityReport {
+            url,
+            reachable: false,
+            diagnosis: if is_loopback {
+                "连接被拒绝，服务器可能刚停止或实际端口与显示不一致".to_string()
+            } else {
+                "无法建立连接，服务器只监听本机回环地址，外部主机无法访问".to_string()
+            },
+        },
+        Err(e) => ReachabilityReport {
+            url,
+            reachable: false,
+            diagnosis: format!("请求失败: {}", e),
+        },
+    };
+
+    logger.info(
+        "server",
+        format!("可达性自检 {} -> {}", report.url, report.diagnosis),
+    );
+    Ok(report)
+}
+
+/// 模拟游戏拉流的自检结果
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackTestReport {
+    pub station_id: String,
+    /// 是否判定为游戏内可正常播放
+    pub ok: bool,
+    /// 从发起请求到收到首个音频字节的耗时（毫秒）
+    pub first_byte_ms: Option<u64>,
+    /// 读取到的字节数
+    pub bytes_read: usize,
+    /// 校验通过的完整 MP3 帧数
+    pub valid_frames: usize,
+    /// 面向用户的结论
+    pub detail: String,
+}
+
+/// 拉取几秒音频的字节数上限
+const PLAYBACK_TEST_MAX_BYTES: usize = 96 * 1024;
+/// 拉取音频的总时长上限（秒）
+const PLAYBACK_TEST_MAX_SECS: u64 = 8;
+/// 判定可播放所需的最少完整帧数
+const PLAYBACK_TEST_MIN_FRAMES: usize = 10;
+
+/// 像游戏一样连接本地 /stream/:id 读几秒音频并校验 MP3 帧，
+/// 一键回答"这个电台进游戏能不能放"。
+#[tauri::command]
+pub async fn test_station_playback(
+    station_id: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<PlaybackTestReport, String> {
+    let (running, port, logger) = {
+        let mut s = state.lock().await;
+        resume_if_auto_stopped(&mut s).await;
+        let status = s.server.state().get_status().await;
+        (status.running, status.port, s.logger.clone())
+    };
+
+    if !running {
+        return Ok(PlaybackTestReport {
+            station_id,
+            ok: false,
+            first_byte_ms: None,
+            bytes_read: 0,
+            valid_frames: 0,
+            detail: "服务器未启动，请先启动流媒体服务器".to_string(),
+        });
+    }
+
+    let report = run_playback_test(port, station_id).await;
+    logger.info(
+        "server",
+        format!("拉流自检 {} -> {}", report.station_id, report.detail),
+    );
+    Ok(report)
+}
+
+/// 拉流自检的核心逻辑，静音诊断也会复用
+async fn run_playback_test(port: u16, station_id: String) -> PlaybackTestReport {
+    let url = format!("http://127.0.0.1:{}/stream/{}", port, station_id);
+    let client = match reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(5))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            return PlaybackTestReport {
+                station_id,
+                ok: false,
+                first_byte_ms: None,
+                bytes_read: 0,
+                valid_frames: 0,
+                detail: format!("创建 HTTP 客户端失败: {}", e),
+            };
+        }
+    };
+
+    let start = std::time::Instant::now();
+    let response = match client.get(&url).send().await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            return PlaybackTestReport {
+                station_id,
+                ok: false,
+                first_byte_ms: None,
+                bytes_read: 0,
+                valid_frames: 0,
+                detail: format!("服务器返回 {}，电台可能无可用流地址", response.status()),
+            };
+        }
+        Err(e) => {
+            return PlaybackTestReport {
+                station_id,
+                ok: false,
+                first_byte_ms: None,
+                bytes_read: 0,
+                valid_frames: 0,
+                detail: format!("连接本地流失败: {}", e),
+            };
+        }
+    };
+
+    // 像游戏一样持续读流，直到拿到足够字节或超时
+    use tokio_stream::StreamExt;
+    let deadline = tokio::time::Instant::now()
+        + tokio::time::Duration::from_secs(PLAYBACK_TEST_MAX_SECS);
+    let mut stream = response.bytes_stream();
+    let mut audio = Vec::new();
+    let mut first_byte_ms = None;
+
+    while audio.len() < PLAYBACK_TEST_MAX_BYTES {
+        let chunk = tokio::select! {
+            chunk = stream.next() => chunk,
+            _ = tokio::time::sleep_until(deadline) => break,
+        };
+        match chunk {
+            Some(Ok(bytes)) => {
+                if first_byte_ms.is_none() && !bytes.is_empty() {
+                    first_byte_ms = Some(start.elapsed().as_millis() as u64);
+                }
+                audio.extend_from_slice(&bytes);
+            }
+            _ => break,
+        }
+    }
+
+    let valid_frames = crate::radio::mp3::count_valid_frames(&audio);
+    let ok = valid_frames >= PLAYBACK_TEST_MIN_FRAMES;
+    let detail = if ok {
+        format!(
+            "播放正常：{} 个完整 MP3 帧，首字节 {}ms",
+            valid_frames,
+            first_byte_ms.unwrap_or(0)
+        )
+    } else if audio.is_empty() {
+        "超时未收到音频数据，上游流可能不可用".to_string()
+    } else {
+        format!(
+            "收到 {} 字节但只校验出 {} 个完整 MP3 帧，输出可能已损坏",
+            audio.len(),
+            valid_frames
+        )
+    };
+
+    PlaybackTestReport {
+        station_id,
+        ok,
+        first_byte_ms,
+        bytes_read: audio.len(),
+        valid_frames,
+        detail,
+    }
+}
+
+/// 静音诊断的单项检查结果
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SilenceCheck {
+    /// 检查名称
+    pub name: String,
+    /// 是否通过
+    pub passed: bool,
+    /// 检查结论
+    pub finding: String,
+}
+
+/// "为什么这个电台没声音"一键诊断报告
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SilenceReport {
+    pub station_id: String,
+    pub station_name: String,
+    /// 各项检查结果，按执行顺序排列
+    pub checks: Vec<SilenceCheck>,
+    /// 可能原因与建议，按可能性从高到低排列
+    pub likely_causes: Vec<String>,
+}
+
+/// 上游流地址探测的超时（秒）
+const SILENCE_PROBE_TIMEOUT_SECS: u64 = 5;
+/// FFmpeg 试解码的最长等待（秒）
+const SILENCE_DECODE_TIMEOUT_SECS: u64 = 12;
+
+/// 一键诊断"电台没有声音"：依次检查地址解析、上游可达性、
+/// FFmpeg 解码和本地拉流，汇总成按可能性排序的原因列表。
+#[tauri::command]
+pub async fn diagnose_silence(
+    station_id: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<SilenceReport, String> {
+    let (server_state, running, port, ffmpeg_path, logger) = {
+        let mut s = state.lock().await;
+        resume_if_auto_stopped(&mut s).await;
+        let status = s.server.state().get_status().await;
+        let server_state = s.server.state();
+        let ffmpeg_path = server_state.ffmpeg_path.clone();
+        (
+            server_state,
+            status.running,
+            status.port,
+            ffmpeg_path,
+            s.logger.clone(),
+        )
+    };
+
+    let station = {
+        let stations = server_state.stations.read().await;
+        stations.get(&station_id).cloned()
+    };
+    let station = station.ok_or_else(|| format!("电台不存在: {}", station_id))?;
+
+    let mut checks = Vec::new();
+    let mut likely_causes = Vec::new();
+
+    if !running {
+        checks.push(SilenceCheck {
+            name: "服务器状态".to_string(),
+            passed: false,
+            finding: "流媒体服务器未启动".to_string(),
+        });
+        likely_causes.push("服务器未启动：请先启动流媒体服务器再进游戏".to_string());
+        return Ok(SilenceReport {
+            station_id,
+            station_name: station.name,
+            checks,
+            likely_causes,
+        });
+    }
+
+    // 1. 刷新并解析流地址
+    let stream_url = server_state.resolve_station_url(&station).await;
+    checks.push(SilenceCheck {
+        name: "流地址解析".to_string(),
+        passed: stream_url.is_some(),
+        finding: match &stream_url {
+            Some(url) => format!(
+                "已解析: {}...",
+                crate::radio::stream::truncate_utf8(url, 60)
+            ),
+            None => "无法获取流地址".to_string(),
+        },
+    });
+    let Some(stream_url) = stream_url else {
+        likely_causes
+            .push("无可用流地址：上游接口可能已变更，或该电台已停播".to_string());
+        likely_causes.push("建议重新爬取电台数据后再试".to_string());
+        return Ok(SilenceReport {
+            station_id,
+            station_name: station.name,
+            checks,
+            likely_causes,
+        });
+    };
+
+    // 2. 上游可达性
+    let upstream_ok = probe_upstream(&stream_url).await;
+    checks.push(SilenceCheck {
+        name: "上游可达性".to_string(),
+        passed: upstream_ok.is_ok(),
+        finding: match &upstream_ok {
+            Ok(status) => format!("HTTP {}", status),
+            Err(e) => e.clone(),
+        },
+    });
+    if upstream_ok.is_err() {
+        likely_causes.push(
+            "上游流地址不可达：签名可能已过期或存在地域限制，稍后重试或重新爬取".to_string(),
+        );
+    }
+
+    // 3. FFmpeg 试解码 1 秒，确认编解码兼容并截取 stderr 关键输出
+    let decode = probe_decode(&ffmpeg_path, &stream_url).await;
+    checks.push(SilenceCheck {
+        name: "FFmpeg 解码".to_string(),
+        passed: decode.is_ok(),
+        finding: match &decode {
+            Ok(audio_line) => audio_line.clone(),
+            Err(tail) => tail.clone(),
+        },
+    });
+    if let Err(tail) = &decode {
+        let lower = tail.to_ascii_lowercase();
+        if lower.contains("403") || lower.contains("forbidden") {
+            likely_causes.push("上游返回 403：流地址签名失效，需要刷新地址".to_string());
+        } else if lower.contains("audio") && lower.contains("none") {
+            likely_causes.push("源中没有可用音频轨".to_string());
+        } else {
+            likely_causes.push("FFmpeg 无法解码该源：编码格式可能不受支持".to_string());
+        }
+    }
+
+    // 4. 本地拉流自检（完整链路）
+    let playback = run_playback_test(port, station_id.clone()).await;
+    checks.push(SilenceCheck {
+        name: "本地拉流".to_string(),
+        passed: playback.ok,
+        finding: playback.detail.clone(),
+    });
+    if !playback.ok && upstream_ok.is_ok() && decode.is_ok() {
+        likely_causes.push(
+            "上游正常但本地输出异常：检查 FFmpeg 版本，或查看诊断日志中的转码错误".to_string(),
+        );
+    }
+
+    if likely_causes.is_empty() {
+        likely_causes.push(
+            "各项检查均通过：电台本身可能正在静音播放（如深夜停播时段），\
+             也可能是音量增益设置过低，可开启电平监测确认"
+                .to_string(),
+        );
+    }
+
+    logger.push(
+        "info",
+        "server",
+        format!("静音诊断完成，{} 项检查", checks.len()),
+        Some(station_id.clone()),
+        Some(station.name.clone()),
+        Some(likely_causes.join("；")),
+    );
+    Ok(SilenceReport {
+        station_id,
+        station_name: station.name,
+        checks,
+        likely_causes,
+    })
+}
+
+/// 对上游流地址发起带超时的 GET，返回状态码或错误描述
+async fn probe_upstream(url: &str) -> Result<u16, String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(SILENCE_PROBE_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| e.to_string())?;
+    match client
+        .get(url)
+        .header(reqwest::header::RANGE, "bytes=0-1")
+        .send()
+        .await
+    {
+        Ok(response) if response.status().is_success() => Ok(response.status().as_u16()),
+        Ok(response) => Err(format!("HTTP {}", response.status())),
+        Err(e) if e.is_timeout() => Err("连接超时".to_string()),
+        Err(e) => Err(format!("请求失败: {}", e)),
+    }
+}
+
+/// 用 FFmpeg 试解码 1 秒，成功时返回音频流描述，失败时返回 stderr 尾部
+async fn probe_decode(
+    ffmpeg_path: &std::path::PathBuf,
+    stream_url: &str,
+) -> Result<String, String> {
+    let mut cmd = tokio::process::Command::new(ffmpeg_path);
+    cmd.args(["-i", stream_url, "-t", "1", "-f", "null", "-"])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        #[allow(unused_imports)]
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = match tokio::time::timeout(
+        std::time::Duration::from_secs(SILENCE_DECODE_TIMEOUT_SECS),
+        cmd.output(),
+    )
+    .await
+    {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return Err(format!("启动 FFmpeg 失败: {}", e)),
+        Err(_) => return Err("FFmpeg 解码超时".to_string()),
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() {
+        // 抓取输入音频流的描述行，如 "Stream #0:0: Audio: aac ..."
+        let audio_line = stderr
+            .lines()
+            .find(|line| line.contains("Audio:"))
+            .map(|line| line.trim().to_string())
+            .unwrap_or_else(|| "解码成功".to_string());
+        Ok(audio_line)
+    } else {
+        // 失败时取 stderr 尾部最后几行非空输出
+        let tail: Vec<&str> = stderr
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        let start = tail.len().saturating_sub(3);
+        Err(tail[start..].join(" | "))
+    }
+}
+
+/// 频道预告中的单个条目
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelPreviewItem {
+    pub station_id: String,
+    pub station_name: String,
+    pub province: String,
+    /// 该条目预计播放的分钟数
+    pub minutes: u64,
+}
+
+/// 频道预告最多展示多少个条目
+const CHANNEL_PREVIEW_LIMIT: usize = 10;
+
+/// 预告虚拟频道接下来会轮播的电台
+///
+/// 复用各频道真实的选台逻辑（不实际播放），让用户在长途出发前
+/// 就能看到插播 / 环游 / 流派聚合频道接下来的节目单。
+#[tauri::command]
+pub async fn preview_channel_items(
+    channel_id: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<ChannelPreviewItem>, String> {
+    let server_state = {
+        let s = state.lock().await;
+        s.server.state()
+    };
+    let settings = crate::settings::load_settings_from_file(&server_state.data_dir);
+
+    if channel_id == INTERRUPT_CHANNEL_ID {
+        let cfg = settings.interrupt_channel;
+        if !cfg.enabled {
+            return Err("插播频道未启用".to_string());
+        }
+        let (base, cutin) = {
+            let stations = server_state.stations.read().await;
+            (
+                stations.get(&cfg.base_station_id).cloned(),
+                stations.get(&cfg.cutin_station_id).cloned(),
+            )
+        };
+        let (base, cutin) = match (base, cutin) {
+            (Some(base), Some(cutin)) => (base, cutin),
+            _ => return Err("插播频道配置的电台不存在".to_string()),
+        };
+        // 与播放逻辑一致：基础电台与插播电台交替
+        let items = (0..CHANNEL_PREVIEW_LIMIT)
+            .map(|i| {
+                let play_base = i % 2 == 0;
+                let (station, minutes) = if play_base {
+                    (&base, cfg.interval_minutes)
+                } else {
+                    (&cutin, cfg.cutin_minutes)
+                };
+                ChannelPreviewItem {
+                    station_id: station.id.clone(),
+                    station_name: station.name.clone(),
+                    province: station.province.clone(),
+                    minutes,
+                }
+            })
+            .collect();
+        return Ok(items);
+    }
+
+    let (playlist, minutes) = if channel_id == TOUR_CHANNEL_ID {
+        let cfg = settings.tour_channel;
+        if !cfg.enabled {
+            return Err("环游频道未启用".to_string());
+        }
+        let stations = server_state.stations.read().await;
+        (tour_playlist(&stations), cfg.minutes_per_station)
+    } else if let Some(genre) = channel_id.strip_prefix(GENRE_CHANNEL_PREFIX) {
+        let cfg = settings.genre_channels;
+        if !cfg.enabled {
+            return Err("流派聚合频道未启用".to_string());
+        }
+        let stations = server_state.stations.read().await;
+        (genre_playlist(&stations, genre), cfg.minutes_per_station)
+    } else {
+        return Err(format!("未知的频道 ID: {}", channel_id));
+    };
+
+    if playlist.is_empty() {
+        return Err("该频道没有可用电台".to_string());
+    }
+
+    // 轮播从头开始，列表不足时循环展示
+    let items = (0..CHANNEL_PREVIEW_LIMIT)
+        .map(|i| {
+            let station = &playlist[i % playlist.len()];
+            ChannelPreviewItem {
+                station_id: station.id.clone(),
+                station_name: station.name.clone(),
+                province: station.province.clone(),
+                minutes,
+            }
+        })
+        .collect();
+    Ok(items)
+}
@@ -0,0 +1,155 @@
+//! 应用设置相关命令
+
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::settings::{load_settings_from_file, save_settings_to_file, AppSettings};
+use crate::AppState;
+
+/// 读取应用设置
+#[tauri::command]
+pub async fn load_settings(state: State<'_, Arc<Mutex<AppState>>>) -> Result<AppSettings, String> {
+    let state = state.lock().await;
+    Ok(load_settings_from_file(state.crawler.data_dir()))
+}
+
+/// 保存应用设置
+#[tauri::command]
+pub async fn save_settings(
+    settings: AppSettings,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir();
+    save_settings_to_file(data_dir, &settings)?;
+
+    // JSON 行日志开关立即生效，不必重启应用
+    state.logger.set_json_log_path(
+        settings
+            .json_log
+            .then(|| data_dir.join(crate::diagnostics::JSON_LOG_FILE)),
+    );
+    // 搜索分区限定同样立即生效，分区变化时会丢弃旧搜索池
+    state
+        .server
+        .state()
+        .bilibili
+        .set_search_tid(settings.bilibili_search_tid);
+    // 云听签名 key 覆盖与镜像地址列表热生效，改完无需重启
+    apply_yunting_key(&state, &settings.yunting_api_key);
+    apply_yunting_mirrors(&state, &settings.yunting_mirror_urls);
+    Ok(())
+}
+
+/// 把云听签名 key 覆盖应用到所有持有 API 客户端的实例
+fn apply_yunting_key(state: &AppState, key: &str) {
+    state
+        .crawler
+        .api()
+        .set_api_key_override(Some(key.to_string()));
+    state
+        .server
+        .state()
+        .api
+        .set_api_key_override(Some(key.to_string()));
+    for server in &state.extra_servers {
+        server.state().api.set_api_key_override(Some(key.to_string()));
+    }
+}
+
+/// 把云听镜像地址列表应用到所有持有 API 客户端的实例
+fn apply_yunting_mirrors(state: &AppState, urls: &[String]) {
+    state.crawler.api().set_mirror_urls(urls);
+    state.server.state().api.set_mirror_urls(urls);
+    for server in &state.extra_servers {
+        server.state().api.set_mirror_urls(urls);
+    }
+}
+
+/// 从配置的清单地址拉取最新云听签名 key 并应用
+///
+/// 清单是一个返回 `{"apiKey": "..."}` 的 JSON 地址（如 GitHub raw），
+/// 云听轮换 key 时维护者更新清单，用户点一下刷新即可恢复，
+/// 不必等应用发版。成功后写入设置并立即热生效，返回新 key。
+#[tauri::command]
+pub async fn refresh_yunting_key(state: State<'_, Arc<Mutex<AppState>>>) -> Result<String, String> {
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir();
+
+    let mut settings = load_settings_from_file(data_dir);
+    if settings.yunting_key_manifest_url.trim().is_empty() {
+        return Err("未配置云听 key 清单地址".to_string());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let manifest: serde_json::Value = client
+        .get(settings.yunting_key_manifest_url.trim())
+        .send()
+        .await
+        .map_err(|e| format!("拉取 key 清单失败: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("拉取 key 清单失败: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("key 清单不是合法 JSON: {}", e))?;
+
+    let key = manifest
+        .get("apiKey")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "key 清单缺少 apiKey 字段".to_string())?
+        .to_string();
+    if !crate::radio::api::is_valid_api_key(&key) {
+        return Err("清单中的 apiKey 不是 32 位十六进制".to_string());
+    }
+
+    settings.yunting_api_key = key.clone();
+    save_settings_to_file(data_dir, &settings)?;
+    apply_yunting_key(&state, &key);
+
+    log::info!("云听签名 key 已从清单刷新");
+    state.logger.info("settings", "云听签名 key 已从清单刷新并应用");
+    Ok(key)
+}
+
+/// 恢复默认设置
+///
+/// 写入一份全新的默认设置并返回给前端，供设置页"恢复默认"使用。
+#[tauri::command]
+pub async fn reset_settings(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<AppSettings, String> {
+    let state = state.lock().await;
+    let settings = AppSettings::default();
+    save_settings_to_file(state.crawler.data_dir(), &settings)?;
+    log::info!("设置已恢复为默认值");
+    state.logger.info("app", "设置已恢复为默认值");
+    Ok(settings)
+}
+
+/// 设置电台音量增益（dB）
+///
+/// 增益为 0 时移除该电台的配置，下次播放恢复原始音量。
+#[tauri::command]
+pub async fn set_station_gain(
+    id: String,
+    db: f32,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir();
+
+    let mut settings = load_settings_from_file(data_dir);
+    if db == 0.0 {
+        settings.station_gains.remove(&id);
+    } else {
+        settings.station_gains.insert(id.clone(), db);
+    }
+    save_settings_to_file(data_dir, &settings)?;
+
+    log::info!("设置电台增益: {} -> {}dB", id, db);
+    Ok(())
+}
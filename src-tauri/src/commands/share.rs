@@ -0,0 +1,181 @@
+//! 电台集合分享码
+//!
+//! 把选中的电台（含自定义电台）打包成一段紧凑的 base64 字符串，
+//! 贴到群里或 gist 上，朋友一键导入即可获得相同的电台集合。
+
+use std::sync::Arc;
+
+use base64::Engine as _;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use super::custom::merge_custom_stations;
+use crate::radio::Station;
+use crate::AppState;
+
+/// 分享码前缀，便于识别和防止误粘贴
+const SHARE_CODE_PREFIX: &str = "ouka2-share:";
+
+/// 当前分享码格式版本
+const SHARE_CODE_VERSION: u32 = 1;
+
+/// 分享码载荷
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SharePayload {
+    /// 格式版本，便于将来不兼容升级
+    version: u32,
+    /// 打包的电台
+    stations: Vec<Station>,
+}
+
+/// 把电台列表编码为分享码
+fn encode_share_code(stations: Vec<Station>) -> Result<String, String> {
+    let payload = SharePayload {
+        version: SHARE_CODE_VERSION,
+        stations,
+    };
+    let json = serde_json::to_string(&payload).map_err(|e| e.to_string())?;
+    Ok(format!(
+        "{}{}",
+        SHARE_CODE_PREFIX,
+        base64::engine::general_purpose::STANDARD.encode(json)
+    ))
+}
+
+/// 解码分享码为电台列表
+fn decode_share_code(code: &str) -> Result<Vec<Station>, String> {
+    let encoded = code
+        .trim()
+        .strip_prefix(SHARE_CODE_PREFIX)
+        .ok_or("不是有效的分享码")?;
+    let json = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|_| "分享码已损坏".to_string())?;
+    let payload: SharePayload =
+        serde_json::from_slice(&json).map_err(|_| "分享码内容无法解析".to_string())?;
+    if payload.version > SHARE_CODE_VERSION {
+        return Err("分享码来自更新版本的应用，请先升级".to_string());
+    }
+    Ok(payload.stations)
+}
+
+/// 导出选中电台的分享码
+#[tauri::command]
+pub async fn export_share_code(
+    station_ids: Vec<String>,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<String, String> {
+    if station_ids.is_empty() {
+        return Err("请至少选择一个电台".to_string());
+    }
+
+    let state = state.lock().await;
+    let mut stations = state.crawler.get_stations().await;
+    merge_custom_stations(state.crawler.data_dir(), &mut stations);
+
+    let selected: Vec<Station> = station_ids
+        .iter()
+        .filter_map(|id| stations.iter().find(|s| &s.id == id).cloned())
+        .collect();
+    if selected.is_empty() {
+        return Err("选中的电台不存在".to_string());
+    }
+
+    encode_share_code(selected)
+}
+
+/// 导入分享码，返回新增的电台数
+///
+/// 也接受 gist raw 等 URL，先下载内容再按分享码解析。
+/// 导入的电台追加到自定义电台列表，已存在的 ID 跳过。
+#[tauri::command]
+pub async fn import_share_code(
+    code: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<usize, String> {
+    let code = if code.trim().starts_with("http://") || code.trim().starts_with("https://") {
+        reqwest::Client::new()
+            .get(code.trim())
+            .timeout(std::time::Duration::from_secs(30))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .text()
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        code
+    };
+
+    let imported = decode_share_code(&code)?;
+    if imported.is_empty() {
+        return Err("分享码中没有电台".to_string());
+    }
+
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir().clone();
+
+    // 计算已存在的 ID（爬取 + 自定义），只追加新电台
+    let mut existing = state.crawler.get_stations().await;
+    merge_custom_stations(&data_dir, &mut existing);
+    let existing_ids: std::collections::HashSet<String> =
+        existing.into_iter().map(|s| s.id).collect();
+
+    let mut custom_stations = super::custom::load_custom_stations_from_file(&data_dir);
+    let mut added = 0;
+    for station in imported {
+        if existing_ids.contains(&station.id) {
+            continue;
+        }
+        custom_stations.push(station);
+        added += 1;
+    }
+
+    if added > 0 {
+        super::custom::save_custom_stations_to_file(&data_dir, &custom_stations)?;
+        state.sync_stations_to_servers().await;
+    }
+
+    state
+        .logger
+        .info("share", format!("分享码导入完成，新增 {} 个电台", added));
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn share_code_roundtrip() {
+        let station = Station {
+            id: "custom:1".to_string(),
+            name: "测试电台".to_string(),
+            subtitle: String::new(),
+            image: String::new(),
+            province: "自定义".to_string(),
+            play_url_low: Some("http://example.com/live.m3u8".to_string()),
+            mp3_play_url_low: None,
+            mp3_play_url_high: None,
+            is_custom: true,
+            bitrate: None,
+            language: None,
+            url_expires_at: None,
+        };
+
+        let code = encode_share_code(vec![station.clone()]).unwrap();
+        assert!(code.starts_with(SHARE_CODE_PREFIX));
+
+        let decoded = decode_share_code(&code).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].id, station.id);
+        assert_eq!(decoded[0].name, station.name);
+    }
+
+    #[test]
+    fn decode_rejects_garbage() {
+        assert!(decode_share_code("随便一段文本").is_err());
+        assert!(decode_share_code("ouka2-share:!!!").is_err());
+    }
+}
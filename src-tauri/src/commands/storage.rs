@@ -0,0 +1,17 @@
+//! 磁盘用量相关命令
+
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::storage::StorageUsage;
+use crate::AppState;
+
+/// 按类别统计数据目录的磁盘占用
+#[tauri::command]
+pub async fn get_storage_usage(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<StorageUsage, String> {
+    let state = state.lock().await;
+    Ok(crate::storage::storage_usage(state.crawler.data_dir()))
+}
@@ -0,0 +1,115 @@
+//! 电台标签与备注相关命令
+//!
+//! 给电台附加自由标签和备注（如「适合夜间开车」「广告多」），
+//! 可作为搜索面板的筛选条件，也可配合按选择生成 SII 使用。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use super::custom::merge_custom_stations;
+use crate::radio::Station;
+use crate::AppState;
+
+/// 标签数据文件名
+const STATION_TAGS_FILE: &str = "station_tags.json";
+
+/// 单个电台的标签与备注
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StationAnnotation {
+    pub tags: Vec<String>,
+    pub note: String,
+}
+
+/// 从文件加载全部标签数据
+pub(crate) fn load_station_tags_from_file(
+    data_dir: &std::path::Path,
+) -> HashMap<String, StationAnnotation> {
+    let path = data_dir.join(STATION_TAGS_FILE);
+    if !path.exists() {
+        return HashMap::new();
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+/// 保存标签数据到文件
+fn save_station_tags_to_file(
+    data_dir: &std::path::Path,
+    annotations: &HashMap<String, StationAnnotation>,
+) -> Result<(), String> {
+    let path = data_dir.join(STATION_TAGS_FILE);
+    let json = serde_json::to_string_pretty(annotations).map_err(|e| e.to_string())?;
+    crate::utils::fs::write_atomic(&path, json).map_err(|e| e.to_string())?;
+    log::debug!("station tags saved: {:?}", path);
+    Ok(())
+}
+
+/// 设置电台的标签与备注
+///
+/// 标签和备注同时为空时移除该电台的记录。
+#[tauri::command]
+pub async fn tag_station(
+    id: String,
+    tags: Vec<String>,
+    note: Option<String>,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir();
+
+    let tags: Vec<String> = tags
+        .into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    let note = note.unwrap_or_default().trim().to_string();
+
+    let mut annotations = load_station_tags_from_file(data_dir);
+    if tags.is_empty() && note.is_empty() {
+        annotations.remove(&id);
+    } else {
+        annotations.insert(id, StationAnnotation { tags, note });
+    }
+
+    save_station_tags_to_file(data_dir, &annotations)
+}
+
+/// 获取全部电台的标签与备注
+#[tauri::command]
+pub async fn get_station_tags(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<HashMap<String, StationAnnotation>, String> {
+    let state = state.lock().await;
+    Ok(load_station_tags_from_file(state.crawler.data_dir()))
+}
+
+/// 按标签筛选电台
+#[tauri::command]
+pub async fn get_stations_by_tag(
+    tag: String,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<Station>, String> {
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir();
+
+    let annotations = load_station_tags_from_file(data_dir);
+    let mut stations = state.crawler.get_stations().await;
+    merge_custom_stations(data_dir, &mut stations);
+
+    Ok(stations
+        .into_iter()
+        .filter(|station| {
+            annotations
+                .get(&station.id)
+                .map(|annotation| annotation.tags.iter().any(|t| t == &tag))
+                .unwrap_or(false)
+        })
+        .collect())
+}
@@ -0,0 +1,100 @@
+//! 车队共享电台列表同步
+//!
+//! 从车队维护的 URL 拉取电台列表 JSON 并缓存到本地，
+//! 合并策略见 [`crate::settings::VtcConflictPolicy`]。
+
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::radio::Station;
+use crate::settings::{load_settings_from_file, VtcConflictPolicy};
+use crate::AppState;
+
+/// 车队电台列表缓存文件名
+const VTC_STATIONS_FILE: &str = "vtc_stations.json";
+
+/// 从文件加载已缓存的车队电台列表
+pub(crate) fn load_vtc_stations_from_file(data_dir: &std::path::Path) -> Vec<Station> {
+    let path = data_dir.join(VTC_STATIONS_FILE);
+    if !path.exists() {
+        return Vec::new();
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 把缓存的车队电台合并进现有列表
+///
+/// 未启用同步时不做任何事；ID 冲突按设置的策略处理。
+pub(crate) fn merge_vtc_stations(data_dir: &std::path::Path, stations: &mut Vec<Station>) {
+    let settings = load_settings_from_file(data_dir);
+    if !settings.vtc_sync.enabled {
+        return;
+    }
+
+    let vtc_stations = load_vtc_stations_from_file(data_dir);
+    if vtc_stations.is_empty() {
+        return;
+    }
+
+    for vtc_station in vtc_stations {
+        match stations.iter_mut().find(|s| s.id == vtc_station.id) {
+            Some(existing) => {
+                if settings.vtc_sync.conflict_policy == VtcConflictPolicy::PreferRemote {
+                    *existing = vtc_station;
+                }
+            }
+            None => stations.push(vtc_station),
+        }
+    }
+}
+
+/// 拉取共享列表并写入本地缓存，返回条目数
+pub(crate) async fn fetch_and_store(
+    url: &str,
+    data_dir: &std::path::Path,
+) -> Result<usize, String> {
+    let response = reqwest::Client::new()
+        .get(url)
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("共享列表地址返回 {}", response.status()));
+    }
+
+    let stations: Vec<Station> = response.json().await.map_err(|e| e.to_string())?;
+    if stations.iter().any(|s| s.id.trim().is_empty()) {
+        return Err("共享列表中存在空 ID 的电台".to_string());
+    }
+
+    let count = stations.len();
+    let json = serde_json::to_string_pretty(&stations).map_err(|e| e.to_string())?;
+    crate::utils::fs::write_atomic(&data_dir.join(VTC_STATIONS_FILE), json)
+        .map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+/// 立即同步车队共享电台列表
+#[tauri::command]
+pub async fn sync_vtc_stations(state: State<'_, Arc<Mutex<AppState>>>) -> Result<usize, String> {
+    let state = state.lock().await;
+    let data_dir = state.crawler.data_dir().clone();
+
+    let settings = load_settings_from_file(&data_dir);
+    if settings.vtc_sync.url.trim().is_empty() {
+        return Err("未配置车队共享列表地址".to_string());
+    }
+
+    let count = fetch_and_store(&settings.vtc_sync.url, &data_dir).await?;
+    state.sync_stations_to_servers().await;
+
+    state
+        .logger
+        .info("vtc", format!("车队电台列表已同步，共 {} 个电台", count));
+    Ok(count)
+}
@@ -0,0 +1,107 @@
+//! 崩溃与 panic 上报（可选）
+//!
+//! 用户很少主动反馈崩溃。开启后 panic 钩子会把回溯和最近的
+//! 诊断日志写成崩溃报告文件，下次启动时上传到配置的端点
+//! （Sentry 兼容或自建接收服务均可），上传成功后删除本地文件。
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::{DiagnosticLogEntry, DiagnosticLogger};
+
+/// 崩溃报告文件名（数据目录下）
+const CRASH_REPORT_FILE: &str = "crash_report.json";
+
+/// 随报告携带的最近日志条数
+const REPORT_LOG_CONTEXT: usize = 50;
+
+/// 一次崩溃的完整上下文
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    /// 崩溃时间（本地时间）
+    pub time: String,
+    /// 应用版本
+    pub app_version: String,
+    /// panic 消息
+    pub message: String,
+    /// panic 位置（文件:行号），无法获取时为空
+    pub location: String,
+    /// 完整回溯
+    pub backtrace: String,
+    /// 崩溃前最近的诊断日志
+    pub recent_logs: Vec<DiagnosticLogEntry>,
+}
+
+/// 安装 panic 钩子，崩溃时把报告写到数据目录
+///
+/// 链式保留原有钩子，不影响默认的 stderr 输出。只在用户
+/// 配置了上报端点后才安装。
+pub fn install_panic_hook(data_dir: PathBuf, logger: DiagnosticLogger) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "未知 panic".to_string()
+        };
+        let location = info
+            .location()
+            .map(|loc| format!("{}:{}", loc.file(), loc.line()))
+            .unwrap_or_default();
+
+        let mut recent_logs = logger.recent();
+        if recent_logs.len() > REPORT_LOG_CONTEXT {
+            recent_logs.drain(..recent_logs.len() - REPORT_LOG_CONTEXT);
+        }
+
+        let report = CrashReport {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            message,
+            location,
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            recent_logs,
+        };
+
+        // panic 过程中尽量少做事：直接写文件，失败就放弃
+        if let Ok(json) = serde_json::to_string_pretty(&report) {
+            let _ = std::fs::write(data_dir.join(CRASH_REPORT_FILE), json);
+        }
+
+        previous(info);
+    }));
+}
+
+/// 上传上次崩溃留下的报告
+///
+/// 返回 Ok(true) 表示找到报告并上传成功（本地文件已删除），
+/// Ok(false) 表示没有待上传的报告。
+pub async fn upload_pending_report(data_dir: &Path, endpoint: &str) -> Result<bool, String> {
+    let path = data_dir.join(CRASH_REPORT_FILE);
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    let json = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let report: CrashReport = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(endpoint)
+        .json(&report)
+        .timeout(std::time::Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err(format!("上报端点返回 {}", response.status()));
+    }
+
+    let _ = std::fs::remove_file(&path);
+    Ok(true)
+}
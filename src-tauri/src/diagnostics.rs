@@ -7,6 +7,11 @@ use tauri::{AppHandle, Emitter};
 const MAX_LOG_ENTRIES: usize = 1000;
 const LOG_EVENT: &str = "diagnostic-log";
 
+/// JSON 行日志文件名（数据目录下）
+pub const JSON_LOG_FILE: &str = "diagnostics.jsonl";
+/// JSON 日志文件超过此大小时轮转，旧内容保留在 .1 文件里
+const MAX_JSON_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
 /// 前端诊断面板使用的结构化日志。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -27,6 +32,8 @@ pub struct DiagnosticLogEntry {
 pub struct DiagnosticLogger {
     entries: Arc<Mutex<VecDeque<DiagnosticLogEntry>>>,
     app_handle: Arc<Mutex<Option<AppHandle>>>,
+    /// JSON 行日志文件路径，None 表示未开启
+    json_log_path: Arc<Mutex<Option<std::path::PathBuf>>>,
 }
 
 impl DiagnosticLogger {
@@ -34,6 +41,41 @@ impl DiagnosticLogger {
         Self {
             entries: Arc::new(Mutex::new(VecDeque::with_capacity(MAX_LOG_ENTRIES))),
             app_handle: Arc::new(Mutex::new(None)),
+            json_log_path: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 开启 / 关闭 JSON 行日志文件
+    ///
+    /// 开启后每条日志以单行 JSON 追加到该文件，传 None 关闭。
+    pub fn set_json_log_path(&self, path: Option<std::path::PathBuf>) {
+        if let Ok(mut p) = self.json_log_path.lock() {
+            *p = path;
+        }
+    }
+
+    /// 追加一条 JSON 行日志（未开启时直接返回，写失败静默忽略）
+    fn append_json_log(&self, entry: &DiagnosticLogEntry) {
+        let path = match self.json_log_path.lock() {
+            Ok(p) => p.clone(),
+            Err(_) => None,
+        };
+        let Some(path) = path else { return };
+
+        // 文件过大时轮转一次，避免无限增长
+        if std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_JSON_LOG_BYTES) {
+            let _ = std::fs::rename(&path, path.with_extension("jsonl.1"));
+        }
+
+        if let Ok(json) = serde_json::to_string(entry) {
+            use std::io::Write;
+            if let Ok(mut file) = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+            {
+                let _ = writeln!(file, "{}", json);
+            }
         }
     }
 
@@ -43,6 +85,15 @@ impl DiagnosticLogger {
         }
     }
 
+    /// 把任意结构化事件发到前端（诊断日志之外的实时数据，如电平表）
+    pub fn emit_event<T: Serialize + Clone>(&self, event: &str, payload: T) {
+        if let Ok(handle) = self.app_handle.lock() {
+            if let Some(app) = handle.as_ref() {
+                let _ = app.emit(event, payload);
+            }
+        }
+    }
+
     pub fn recent(&self) -> Vec<DiagnosticLogEntry> {
         self.entries
             .lock()
@@ -82,6 +133,8 @@ impl DiagnosticLogger {
             entries.push_back(entry.clone());
         }
 
+        self.append_json_log(&entry);
+
         if let Ok(handle) = self.app_handle.lock() {
             if let Some(app) = handle.as_ref() {
                 let _ = app.emit(LOG_EVENT, entry);
@@ -2,14 +2,18 @@
 //!
 //! 将云听电台转换为欧卡2可用格式的桌面应用
 
+mod backup;
 mod commands;
+mod crash;
 mod diagnostics;
 mod radio;
+mod settings;
+mod storage;
 mod utils;
 
 use std::path::PathBuf;
 use std::sync::Arc;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tokio::sync::Mutex;
 
 use commands::custom::merge_custom_stations;
@@ -18,11 +22,39 @@ use diagnostics::DiagnosticLogger;
 use radio::{Crawler, StreamServer};
 use utils::{check_ffmpeg, FFmpegManager};
 
+/// 启动阶段的错误收集
+///
+/// 这些错误发生在窗口可用之前（数据目录不可用、主循环异常等），
+/// 不能直接弹给用户；先记下来，前端起来后通过
+/// `get_startup_errors` 拉取并展示处理建议。
+static STARTUP_ERRORS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+/// 记录一条启动错误
+pub(crate) fn record_startup_error(message: impl Into<String>) {
+    let message = message.into();
+    log::error!("启动错误: {}", message);
+    if let Ok(mut errors) = STARTUP_ERRORS.lock() {
+        errors.push(message);
+    }
+}
+
+/// 读取已记录的启动错误
+pub(crate) fn startup_errors() -> Vec<String> {
+    STARTUP_ERRORS
+        .lock()
+        .map(|errors| errors.clone())
+        .unwrap_or_default()
+}
+
 /// 应用全局状态
 pub struct AppState {
     pub crawler: Crawler,
     pub server: StreamServer,
+    /// 额外的服务器实例（独立端口 / 转码档位），按设置创建
+    pub extra_servers: Vec<StreamServer>,
     pub logger: DiagnosticLogger,
+    /// 服务器是否因空闲被自动停止（区别于用户手动停止）
+    pub auto_stopped: std::sync::atomic::AtomicBool,
 }
 
 impl AppState {
@@ -32,10 +64,43 @@ impl AppState {
         server_port: u16,
         logger: DiagnosticLogger,
     ) -> Self {
+        // 按设置创建启用的额外实例，与主实例共享数据目录和 FFmpeg
+        let extra_servers = settings::load_settings_from_file(&data_dir)
+            .extra_servers
+            .iter()
+            .filter(|cfg| cfg.enabled)
+            .map(|cfg| {
+                StreamServer::with_profile(
+                    cfg.port,
+                    ffmpeg_path.clone(),
+                    data_dir.clone(),
+                    logger.clone(),
+                    cfg.transcode_bitrate_kbps,
+                )
+            })
+            .collect();
+
         Self {
+            server: StreamServer::new(server_port, ffmpeg_path, data_dir.clone(), logger.clone()),
+            extra_servers,
             crawler: Crawler::new(data_dir),
-            server: StreamServer::new(server_port, ffmpeg_path, logger.clone()),
             logger,
+            auto_stopped: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    /// 把最新电台列表（爬虫缓存 + 自定义电台）推送到所有服务器实例
+    ///
+    /// 爬取、导入、自定义电台增删改后都应调用，让正在运行的
+    /// 服务器不需要重启就能看到最新列表。
+    pub async fn sync_stations_to_servers(&self) {
+        let mut stations = self.crawler.get_stations().await;
+        merge_custom_stations(self.crawler.data_dir(), &mut stations);
+        commands::vtc::merge_vtc_stations(self.crawler.data_dir(), &mut stations);
+        commands::audiobook::merge_audiobook_stations(self.crawler.data_dir(), &mut stations);
+        self.server.state().load_stations(stations.clone()).await;
+        for server in &self.extra_servers {
+            server.state().load_stations(stations.clone()).await;
         }
     }
 }
@@ -45,57 +110,303 @@ pub fn run() {
     // 初始化日志
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
-    tauri::Builder::default()
+    let run_result = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .setup(|app| {
-            // 获取应用数据目录
-            let data_dir = app.path().app_data_dir().expect("无法获取应用数据目录");
+            // 获取应用数据目录；个别系统上取不到时回退临时目录，
+            // 应用照常打开，错误通过启动诊断展示给用户
+            let data_dir = match app.path().app_data_dir() {
+                Ok(dir) => dir,
+                Err(e) => {
+                    let fallback = std::env::temp_dir().join("ouka2-app");
+                    record_startup_error(format!(
+                        "无法获取应用数据目录（{}），已回退到临时目录 {}，设置和电台数据可能无法长期保存",
+                        e,
+                        fallback.display()
+                    ));
+                    fallback
+                }
+            };
 
             // 确保目录存在
-            std::fs::create_dir_all(&data_dir).ok();
+            if let Err(e) = std::fs::create_dir_all(&data_dir) {
+                record_startup_error(format!(
+                    "无法创建数据目录 {}：{}",
+                    data_dir.display(),
+                    e
+                ));
+            }
 
             log::debug!("app data dir: {:?}", data_dir);
             let logger = DiagnosticLogger::new();
             logger.attach_app(app.handle().clone());
+            if settings::load_settings_from_file(&data_dir).json_log {
+                logger.set_json_log_path(Some(data_dir.join(diagnostics::JSON_LOG_FILE)));
+            }
             logger.info("app", "应用启动，诊断日志已初始化");
 
+            // 把更早阶段收集到的启动错误补进诊断日志
+            for message in startup_errors() {
+                logger.warn("app", message, None::<String>);
+            }
+
+            // 崩溃上报（可选）：配置了端点才安装 panic 钩子，
+            // 并在启动时补传上次崩溃留下的报告
+            let crash_report_url =
+                settings::load_settings_from_file(&data_dir).crash_report_url;
+            if !crash_report_url.is_empty() {
+                crash::install_panic_hook(data_dir.clone(), logger.clone());
+                let crash_data_dir = data_dir.clone();
+                let crash_logger = logger.clone();
+                tauri::async_runtime::spawn(async move {
+                    match crash::upload_pending_report(&crash_data_dir, &crash_report_url).await
+                    {
+                        Ok(true) => crash_logger.info("app", "上次崩溃报告已上传"),
+                        Ok(false) => {}
+                        Err(e) => crash_logger.warn("app", "崩溃报告上传失败", Some(e)),
+                    }
+                });
+            }
+
             // 检测 FFmpeg
             let resource_dir = app.path().resource_dir().ok();
-            let ffmpeg_path = FFmpegManager::detect_ffmpeg(resource_dir.as_ref())
-                .unwrap_or_else(|| PathBuf::from("ffmpeg"));
-            logger.info("ffmpeg", format!("FFmpeg 路径: {}", ffmpeg_path.display()));
+            let (ffmpeg_path, ffmpeg_source) =
+                FFmpegManager::detect_ffmpeg_with_source(resource_dir.as_ref())
+                    .unwrap_or_else(|| (PathBuf::from("ffmpeg"), "未检测到，按 PATH 兜底"));
+            logger.info(
+                "ffmpeg",
+                format!(
+                    "FFmpeg 路径: {}（来源: {}）",
+                    ffmpeg_path.display(),
+                    ffmpeg_source
+                ),
+            );
 
-            // 创建应用状态
+            // 创建应用状态，端口取设置里的首选端口
+            let server_port = settings::load_settings_from_file(&data_dir).server_port;
             let state = Arc::new(Mutex::new(AppState::new(
-                data_dir,
+                data_dir.clone(),
                 ffmpeg_path,
-                3000,
+                server_port,
                 logger,
             )));
 
             // 管理状态
             app.manage(state.clone());
 
+            // 每日自动备份数据目录（每小时检查一次是否到期），
+            // 顺带按配额清理录音目录
+            let backup_data_dir = data_dir.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_secs(3600));
+                loop {
+                    interval.tick().await;
+                    match backup::backup_if_due(&backup_data_dir) {
+                        Ok(Some(name)) => log::info!("已创建每日备份: {}", name),
+                        Ok(None) => {}
+                        Err(e) => log::warn!("每日备份失败: {}", e),
+                    }
+
+                    let quota_mb =
+                        settings::load_settings_from_file(&backup_data_dir).recordings_quota_mb;
+                    let removed = storage::cleanup_recordings(&backup_data_dir, quota_mb);
+                    if removed > 0 {
+                        log::info!("录音配额清理完成，删除 {} 个文件", removed);
+                    }
+                }
+            });
+
+            // 跟随游戏：检测到游戏启动就拉起服务器，游戏退出后停止
+            let follow_state = state.clone();
+            let follow_data_dir = data_dir.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut sys = sysinfo::System::new();
+                let mut game_was_running = false;
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_secs(15));
+                loop {
+                    interval.tick().await;
+                    if !settings::load_settings_from_file(&follow_data_dir).follow_game {
+                        game_was_running = false;
+                        continue;
+                    }
+
+                    // 进程枚举是阻塞操作，放到阻塞线程池里做
+                    let (game_running, sys_back) =
+                        tauri::async_runtime::spawn_blocking(move || {
+                            let running = utils::game::is_game_running(&mut sys);
+                            (running, sys)
+                        })
+                        .await
+                        .unwrap_or_else(|_| (false, sysinfo::System::new()));
+                    sys = sys_back;
+
+                    if game_running == game_was_running {
+                        continue;
+                    }
+                    game_was_running = game_running;
+
+                    let mut s = follow_state.lock().await;
+                    if game_running {
+                        if !s.server.is_running() {
+                            match s.server.start().await {
+                                Ok(()) => s
+                                    .logger
+                                    .info("server", "检测到游戏启动，已自动启动服务器"),
+                                Err(e) => s.logger.warn(
+                                    "server",
+                                    "跟随游戏启动服务器失败",
+                                    Some(e.to_string()),
+                                ),
+                            }
+                        }
+                    } else if s.server.is_running() {
+                        s.logger.info("server", "游戏已退出，自动停止服务器");
+                        s.server.stop().await;
+                    }
+                }
+            });
+
+            // 车队共享电台列表定时同步
+            let vtc_state = state.clone();
+            let vtc_data_dir = data_dir.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut last_sync: Option<tokio::time::Instant> = None;
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_secs(60));
+                loop {
+                    interval.tick().await;
+                    let sync = settings::load_settings_from_file(&vtc_data_dir).vtc_sync;
+                    if !sync.enabled || sync.url.trim().is_empty() {
+                        continue;
+                    }
+                    let due = last_sync.is_none_or(|at| {
+                        at.elapsed().as_secs() >= sync.interval_minutes.max(1) * 60
+                    });
+                    if !due {
+                        continue;
+                    }
+                    last_sync = Some(tokio::time::Instant::now());
+
+                    match commands::vtc::fetch_and_store(&sync.url, &vtc_data_dir).await {
+                        Ok(count) => {
+                            let s = vtc_state.lock().await;
+                            s.sync_stations_to_servers().await;
+                            s.logger.info(
+                                "vtc",
+                                format!("车队电台列表已定时同步，共 {} 个电台", count),
+                            );
+                        }
+                        Err(e) => {
+                            let s = vtc_state.lock().await;
+                            s.logger.warn("vtc", "车队电台列表同步失败", Some(e));
+                        }
+                    }
+                }
+            });
+
+            // 空闲自动停止：连续 N 分钟没有活动流就停掉服务器释放端口
+            let idle_state = state.clone();
+            let idle_data_dir = data_dir.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut idle_since: Option<tokio::time::Instant> = None;
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_secs(60));
+                loop {
+                    interval.tick().await;
+                    let minutes =
+                        settings::load_settings_from_file(&idle_data_dir).idle_stop_minutes;
+                    if minutes == 0 {
+                        idle_since = None;
+                        continue;
+                    }
+
+                    let mut s = idle_state.lock().await;
+                    if !s.server.is_running() {
+                        idle_since = None;
+                        continue;
+                    }
+                    if !s.server.state().active_streams.read().await.is_empty() {
+                        idle_since = None;
+                        continue;
+                    }
+
+                    match idle_since {
+                        None => idle_since = Some(tokio::time::Instant::now()),
+                        Some(since) if since.elapsed().as_secs() >= minutes * 60 => {
+                            s.logger.info(
+                                "server",
+                                format!("空闲超过 {} 分钟，自动停止流媒体服务器", minutes),
+                            );
+                            s.server.stop().await;
+                            s.auto_stopped
+                                .store(true, std::sync::atomic::Ordering::Relaxed);
+                            idle_since = None;
+                        }
+                        Some(_) => {}
+                    }
+                }
+            });
+
             // 尝试加载已保存的电台数据
             let state_clone = state.clone();
+            let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 let state = state_clone.lock().await;
+
+                // 先做数据完整性检查，损坏时尝试自动修复并通知前端
+                if let Some(message) = state.crawler.check_and_repair_data() {
+                    state
+                        .logger
+                        .warn("app", message.clone(), None::<String>);
+                    let _ = app_handle.emit("data-integrity", message);
+                }
+
                 if let Ok(stations) = state.crawler.load_stations() {
                     if !stations.is_empty() {
-                        state.crawler.set_stations(stations.clone()).await;
-                        let mut stations_for_server = stations;
-                        merge_custom_stations(state.crawler.data_dir(), &mut stations_for_server);
-                        state
-                            .server
-                            .state()
-                            .load_stations(stations_for_server)
-                            .await;
+                        state.crawler.set_stations(stations).await;
+                        state.sync_stations_to_servers().await;
                         log::debug!("loaded saved stations");
                         state.logger.info("app", "已加载本地保存的电台数据");
                     }
                 }
             });
 
+            // 按设置自动恢复流媒体服务器，并预热上次退出时还在播放的电台。
+            // 游戏端在应用重启期间会一直重试旧地址，越早恢复越好。
+            let resume_state = state.clone();
+            let resume_data_dir = data_dir.clone();
+            tauri::async_runtime::spawn(async move {
+                let settings = settings::load_settings_from_file(&resume_data_dir);
+                if !settings.auto_start_server {
+                    return;
+                }
+
+                {
+                    let mut s = resume_state.lock().await;
+                    if let Err(e) = s.server.start().await {
+                        s.logger
+                            .warn("server", "自动启动流媒体服务器失败", Some(e.to_string()));
+                        return;
+                    }
+                    s.logger.info("server", "已按设置自动启动流媒体服务器");
+                }
+
+                let station_ids = radio::load_runtime_state(&resume_data_dir);
+                if station_ids.is_empty() {
+                    return;
+                }
+
+                // 等本地电台数据加载完成后再预热
+                tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+                let server_state = {
+                    let s = resume_state.lock().await;
+                    s.server.state()
+                };
+                server_state.prewarm_stations(&station_ids).await;
+            });
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -104,30 +415,86 @@ pub fn run() {
             crawl_stations,
             get_province_statistics,
             load_saved_stations,
+            suggest_local_stations,
             // 服务器命令
             start_server,
             stop_server,
             stop_active_streams,
+            pause_all_streams,
+            resume_all_streams,
+            set_server_port,
             get_server_status,
+            get_stream_stats,
+            test_server_reachability,
+            test_station_playback,
+            diagnose_silence,
+            preview_channel_items,
             get_diagnostic_logs,
             clear_diagnostic_logs,
+            get_startup_errors,
             // 配置命令
             generate_sii,
             generate_sii_with_selection,
+            generate_sii_preview,
+            generate_sii_variants,
+            generate_extra_server_sii,
+            list_sii_variants,
+            install_sii_variant,
             install_sii_to_ets2,
             install_sii_to_ets2_with_selection,
+            read_installed_sii_metadata,
             get_ets2_paths,
             get_app_data_dir,
             load_install_selection,
             save_install_selection,
+            set_stations_enabled,
+            get_disabled_stations,
+            audit_station_names,
+            load_settings,
+            save_settings,
+            reset_settings,
+            set_station_gain,
+            refresh_yunting_key,
+            // 备份命令
+            list_backups,
+            create_backup,
+            restore_backup,
+            // 存储命令
+            get_storage_usage,
+            // 标签命令
+            tag_station,
+            get_station_tags,
+            get_stations_by_tag,
+            // B 站命令
+            benchmark_bilibili_cdn,
+            get_random_bilibili_audio,
+            get_bilibili_rate_limit,
+            get_api_health,
+            report_bilibili_playback,
             // 自定义电台命令
             add_custom_station,
             remove_custom_station,
             update_custom_station,
             load_custom_stations,
+            set_channel_image,
+            // 车队同步命令
+            sync_vtc_stations,
+            // 有声书命令
+            add_audiobook_channel,
+            list_audiobook_channels,
+            set_bookmark,
+            jump_to_episode,
+            // 分享码命令
+            export_share_code,
+            import_share_code,
             // 工具命令
             check_ffmpeg,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .run(tauri::generate_context!());
+
+    // 主循环无法启动时窗口还不存在，只能写日志留痕，
+    // 不再 expect() 直接 panic
+    if let Err(e) = run_result {
+        record_startup_error(format!("应用主循环启动失败: {}", e));
+    }
 }
@@ -7,26 +7,176 @@ use reqwest::Client;
 use std::collections::HashMap;
 use std::time::Duration;
 
-/// API 密钥（从云听网站前端JS中提取）
+/// 内置 API 密钥（从云听网站前端JS中提取）
+///
+/// 云听轮换 key 时可通过设置覆盖热修，这里只作为兜底。
 const API_KEY: &str = "f0fc4c668392f9f9a447e48584c214ee";
-/// API 基础URL
+/// 内置 API 基础URL
+///
+/// 个别运营商下该域名会被地理封锁或长时间超时，可在设置里
+/// 配置镜像地址做自动故障转移，内置地址始终作为最后兜底。
 const BASE_URL: &str = "https://ytmsout.radio.cn";
 
+/// 连续失败多少次后熔断
+const BREAKER_FAILURE_THRESHOLD: u32 = 5;
+/// 熔断后暂停请求的秒数
+const BREAKER_OPEN_SECS: u64 = 60;
+
+/// 上游熔断器状态
+///
+/// radio.cn 持续故障时避免每个播放请求都去撞上游：
+/// 连续失败达到阈值后打开熔断，期间请求直接快速失败
+/// （调用方会回退到缓存的流地址）；熔断期结束进入半开，
+/// 放行探测请求，成功则恢复，失败则重新熔断。
+struct CircuitBreaker {
+    consecutive_failures: u32,
+    open_until: Option<std::time::Instant>,
+}
+
+/// 校验 key 是否是云听签名 key 的格式（32 位十六进制）
+pub fn is_valid_api_key(key: &str) -> bool {
+    key.len() == 32 && key.chars().all(|c| c.is_ascii_hexdigit())
+}
+
 /// 云听电台 API 客户端
 pub struct RadioApi {
     client: Client,
+    breaker: std::sync::Mutex<CircuitBreaker>,
+    /// 签名 key 覆盖，None 时使用内置常量
+    api_key_override: std::sync::RwLock<Option<String>>,
+    /// 生效的基础地址列表，末尾总是内置官方地址
+    base_urls: std::sync::RwLock<Vec<String>>,
+    /// 上次请求成功的主机在 `base_urls` 里的下标，后续请求从它开始尝试
+    preferred_host: std::sync::atomic::AtomicUsize,
 }
 
 impl RadioApi {
     /// 创建新的 API 客户端
     pub fn new() -> Self {
+        // 爬取是大量小请求：开启压缩省流量，HTTP/2 保活 +
+        // 空闲连接池让同主机请求复用连接，省去反复握手
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .connect_timeout(Duration::from_secs(10))
+            .gzip(true)
+            .brotli(true)
+            .pool_max_idle_per_host(8)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .http2_keep_alive_interval(Duration::from_secs(30))
+            .http2_keep_alive_while_idle(true)
             .build()
             .unwrap_or_else(|_| Client::new());
 
-        Self { client }
+        Self {
+            client,
+            breaker: std::sync::Mutex::new(CircuitBreaker {
+                consecutive_failures: 0,
+                open_until: None,
+            }),
+            api_key_override: std::sync::RwLock::new(None),
+            base_urls: std::sync::RwLock::new(vec![BASE_URL.to_string()]),
+            preferred_host: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    /// 设置镜像地址列表，按顺序优先于内置官方地址尝试
+    ///
+    /// 条目会去掉首尾空白和末尾斜杠，空条目忽略；
+    /// 内置官方地址（如不在列表里）自动追加到末尾兜底。
+    pub fn set_mirror_urls(&self, urls: &[String]) {
+        let mut normalized: Vec<String> = urls
+            .iter()
+            .map(|u| u.trim().trim_end_matches('/').to_string())
+            .filter(|u| !u.is_empty())
+            .collect();
+        if !normalized.iter().any(|u| u == BASE_URL) {
+            normalized.push(BASE_URL.to_string());
+        }
+        if let Ok(mut current) = self.base_urls.write() {
+            *current = normalized;
+        }
+        self.preferred_host
+            .store(0, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// 按尝试顺序返回候选主机：上次成功的主机优先，其余按配置顺序轮转
+    fn host_candidates(&self) -> Vec<String> {
+        let hosts = self
+            .base_urls
+            .read()
+            .map(|h| h.clone())
+            .unwrap_or_else(|_| vec![BASE_URL.to_string()]);
+        let start = self.preferred_host.load(std::sync::atomic::Ordering::Relaxed) % hosts.len();
+        let mut rotated = Vec::with_capacity(hosts.len());
+        rotated.extend_from_slice(&hosts[start..]);
+        rotated.extend_from_slice(&hosts[..start]);
+        rotated
+    }
+
+    /// 记住请求成功的主机，之后的请求从它开始尝试
+    fn note_host_success(&self, host: &str) {
+        if let Ok(hosts) = self.base_urls.read() {
+            if let Some(index) = hosts.iter().position(|h| h == host) {
+                self.preferred_host
+                    .store(index, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// 设置签名 key 覆盖，None 或空白恢复内置 key
+    pub fn set_api_key_override(&self, key: Option<String>) {
+        let normalized = key
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty());
+        if let Ok(mut current) = self.api_key_override.write() {
+            *current = normalized;
+        }
+    }
+
+    /// 当前生效的签名 key：设置覆盖优先，内置常量兜底
+    fn current_api_key(&self) -> String {
+        self.api_key_override
+            .read()
+            .ok()
+            .and_then(|key| key.clone())
+            .unwrap_or_else(|| API_KEY.to_string())
+    }
+
+    /// 检查熔断器是否放行请求
+    fn breaker_allows_request(&self) -> bool {
+        let mut breaker = self.breaker.lock().unwrap();
+        match breaker.open_until {
+            Some(until) if std::time::Instant::now() < until => false,
+            Some(_) => {
+                // 熔断期结束，进入半开状态放行探测请求
+                breaker.open_until = None;
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// 记录一次成功请求，关闭熔断
+    fn record_success(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.consecutive_failures = 0;
+        breaker.open_until = None;
+    }
+
+    /// 记录一次失败请求，达到阈值时打开熔断
+    fn record_failure(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.consecutive_failures += 1;
+        if breaker.consecutive_failures >= BREAKER_FAILURE_THRESHOLD {
+            breaker.open_until = Some(
+                std::time::Instant::now() + Duration::from_secs(BREAKER_OPEN_SECS),
+            );
+            log::warn!(
+                "radio api 连续失败 {} 次，熔断 {} 秒",
+                breaker.consecutive_failures,
+                BREAKER_OPEN_SECS
+            );
+        }
     }
 
     /// 生成 API 签名
@@ -36,7 +186,7 @@ impl RadioApi {
     /// 2. 拼接为 key=value&key=value 格式
     /// 3. 追加 timestamp 和 key
     /// 4. MD5 哈希并转大写
-    pub fn generate_sign(params: &HashMap<String, String>, timestamp: i64) -> String {
+    pub fn generate_sign(params: &HashMap<String, String>, timestamp: i64, key: &str) -> String {
         // 按键排序
         let mut sorted_keys: Vec<_> = params.keys().collect();
         sorted_keys.sort();
@@ -50,9 +200,9 @@ impl RadioApi {
 
         // 构建签名字符串
         let sign_text = if param_str.is_empty() {
-            format!("timestamp={}&key={}", timestamp, API_KEY)
+            format!("timestamp={}&key={}", timestamp, key)
         } else {
-            format!("{}&timestamp={}&key={}", param_str, timestamp, API_KEY)
+            format!("{}&timestamp={}&key={}", param_str, timestamp, key)
         };
 
         // MD5 哈希并转大写
@@ -60,54 +210,100 @@ impl RadioApi {
         format!("{:X}", digest)
     }
 
-    /// 发起 API 请求
+    /// 发起 API 请求（带熔断保护）
     async fn request<T: serde::de::DeserializeOwned>(
         &self,
         endpoint: &str,
         params: HashMap<String, String>,
+    ) -> anyhow::Result<T> {
+        if !self.breaker_allows_request() {
+            anyhow::bail!("上游接口熔断中，稍后自动恢复");
+        }
+
+        let result = self.request_inner(endpoint, params).await;
+        match &result {
+            Ok(_) => self.record_success(),
+            Err(_) => self.record_failure(),
+        }
+        result
+    }
+
+    /// 发起 API 请求
+    ///
+    /// 按候选顺序依次尝试各镜像主机：连接失败、超时或返回错误状态码
+    /// 时切到下一个，成功的主机会被记住优先用于后续请求。
+    async fn request_inner<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: HashMap<String, String>,
     ) -> anyhow::Result<T> {
         let timestamp = chrono::Utc::now().timestamp_millis();
-        let sign = Self::generate_sign(&params, timestamp);
+        let sign = Self::generate_sign(&params, timestamp, &self.current_api_key());
 
-        // 构建 URL
+        // 构建查询串
         let query_string: String = params
             .iter()
             .map(|(k, v)| format!("{}={}", k, v))
             .collect::<Vec<_>>()
             .join("&");
 
-        let url = if query_string.is_empty() {
-            format!("{}{}", BASE_URL, endpoint)
-        } else {
-            format!("{}{}?{}", BASE_URL, endpoint, query_string)
-        };
-
-        log::debug!("radio api request: {}", url);
-
-        // 发送请求
-        let response = match self
-            .client
-            .get(&url)
-            .header("equipmentId", "0000")
-            .header("platformCode", "WEB")
-            .header("Content-Type", "application/json")
-            .header("timestamp", timestamp.to_string())
-            .header("sign", sign)
-            .send()
-            .await
-        {
-            Ok(resp) => resp,
-            Err(e) => {
-                log::error!("radio api request failed: {}", e);
-                return Err(e.into());
+        let hosts = self.host_candidates();
+        let mut last_err: Option<anyhow::Error> = None;
+        for host in &hosts {
+            let url = if query_string.is_empty() {
+                format!("{}{}", host, endpoint)
+            } else {
+                format!("{}{}?{}", host, endpoint, query_string)
+            };
+
+            log::debug!("radio api request: {}", url);
+
+            // 发送请求
+            let response = match self
+                .client
+                .get(&url)
+                .header("equipmentId", "0000")
+                .header("platformCode", "WEB")
+                .header("Content-Type", "application/json")
+                .header("timestamp", timestamp.to_string())
+                .header("sign", sign.clone())
+                .send()
+                .await
+            {
+                Ok(resp) => resp,
+                Err(e) => {
+                    log::warn!("radio api 主机 {} 请求失败，尝试下一个镜像: {}", host, e);
+                    last_err = Some(e.into());
+                    continue;
+                }
+            };
+
+            log::debug!("radio api status: {}", response.status());
+            if !response.status().is_success() {
+                // 地理封锁常表现为 403 之类的错误状态，换镜像重试
+                log::warn!(
+                    "radio api 主机 {} 返回 {}，尝试下一个镜像",
+                    host,
+                    response.status()
+                );
+                last_err = Some(anyhow::anyhow!("HTTP {}", response.status()));
+                continue;
             }
-        };
 
-        log::debug!("radio api status: {}", response.status());
+            self.note_host_success(host);
+            let text = response.text().await?;
+            return Self::parse_response(&text);
+        }
 
-        let text = response.text().await?;
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("没有可用的云听主机")))
+    }
 
-        let data: ApiResponse<T> = match serde_json::from_str(&text) {
+    /// 解析 API 响应体
+    ///
+    /// 独立成纯函数，配合录制的 JSON fixture 做解析回归测试，
+    /// 上游字段改名或结构变化时测试会先于线上请求暴露问题。
+    fn parse_response<T: serde::de::DeserializeOwned>(text: &str) -> anyhow::Result<T> {
+        let data: ApiResponse<T> = match serde_json::from_str(text) {
             Ok(d) => d,
             Err(e) => {
                 log::error!("radio api json parse failed: {}", e);
@@ -250,10 +446,135 @@ mod tests {
         params.insert("provinceCode".to_string(), "0".to_string());
 
         let timestamp = 1704067200000i64; // 固定时间戳用于测试
-        let sign = RadioApi::generate_sign(&params, timestamp);
+        let sign = RadioApi::generate_sign(&params, timestamp, API_KEY);
 
         // 签名应该是32位大写十六进制字符串
         assert_eq!(sign.len(), 32);
         assert!(sign.chars().all(|c| c.is_ascii_hexdigit()));
     }
+
+    #[test]
+    fn api_key_override_falls_back_to_builtin() {
+        let api = RadioApi::new();
+        assert_eq!(api.current_api_key(), API_KEY);
+
+        api.set_api_key_override(Some("0123456789abcdef0123456789abcdef".to_string()));
+        assert_eq!(api.current_api_key(), "0123456789abcdef0123456789abcdef");
+
+        // 空白覆盖等同取消，恢复内置 key
+        api.set_api_key_override(Some("  ".to_string()));
+        assert_eq!(api.current_api_key(), API_KEY);
+    }
+
+    #[test]
+    fn mirror_urls_normalize_and_keep_builtin_fallback() {
+        let api = RadioApi::new();
+        api.set_mirror_urls(&[
+            "https://a.example/".to_string(),
+            "  ".to_string(),
+            "https://b.example".to_string(),
+        ]);
+
+        assert_eq!(
+            api.host_candidates(),
+            vec![
+                "https://a.example".to_string(),
+                "https://b.example".to_string(),
+                BASE_URL.to_string(),
+            ]
+        );
+
+        // 清空镜像列表后只剩内置官方地址
+        api.set_mirror_urls(&[]);
+        assert_eq!(api.host_candidates(), vec![BASE_URL.to_string()]);
+    }
+
+    #[test]
+    fn successful_host_is_preferred_on_next_request() {
+        let api = RadioApi::new();
+        api.set_mirror_urls(&["https://a.example".to_string(), "https://b.example".to_string()]);
+
+        api.note_host_success("https://b.example");
+        assert_eq!(
+            api.host_candidates(),
+            vec![
+                "https://b.example".to_string(),
+                BASE_URL.to_string(),
+                "https://a.example".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn is_valid_api_key_checks_length_and_charset() {
+        assert!(is_valid_api_key(API_KEY));
+        assert!(!is_valid_api_key("abc"));
+        assert!(!is_valid_api_key("zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz"));
+    }
+
+    /// 录制自 /web/appBroadcast/list 的响应样本
+    const BROADCAST_LIST_FIXTURE: &str = include_str!("fixtures/yunting_broadcast_list.json");
+    /// 录制自 /web/appProvince/list/all 的响应样本
+    const PROVINCE_LIST_FIXTURE: &str = include_str!("fixtures/yunting_province_list.json");
+
+    #[test]
+    fn parse_broadcast_list_fixture() {
+        let stations: Vec<RawStation> = RadioApi::parse_response(BROADCAST_LIST_FIXTURE).unwrap();
+
+        assert_eq!(stations.len(), 2);
+        assert_eq!(stations[0].content_id, "568");
+        assert_eq!(stations[0].title, "中国之声");
+        assert!(stations[0].mp3_play_url_high.is_some());
+        // 可选字段缺失时应容忍而不是解析失败
+        assert!(stations[1].subtitle.is_none());
+        assert!(stations[1].mp3_play_url_low.is_none());
+    }
+
+    #[test]
+    fn parse_province_list_fixture_accepts_int_and_string_codes() {
+        let provinces: Vec<Province> = RadioApi::parse_response(PROVINCE_LIST_FIXTURE).unwrap();
+
+        assert_eq!(provinces[0].province_code, "110000");
+        assert_eq!(provinces[0].province_name, "北京");
+        assert_eq!(provinces[1].province_code, "540000");
+    }
+
+    #[test]
+    fn parse_error_response_is_err() {
+        let result: anyhow::Result<Vec<RawStation>> =
+            RadioApi::parse_response(r#"{"code":10086,"message":"签名错误","data":null}"#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn breaker_opens_after_threshold_and_half_opens() {
+        let api = RadioApi::new();
+        assert!(api.breaker_allows_request());
+
+        for _ in 0..BREAKER_FAILURE_THRESHOLD {
+            api.record_failure();
+        }
+        assert!(!api.breaker_allows_request());
+
+        // 模拟熔断期结束：半开放行探测，成功后完全恢复
+        api.breaker.lock().unwrap().open_until =
+            Some(std::time::Instant::now() - Duration::from_secs(1));
+        assert!(api.breaker_allows_request());
+        api.record_success();
+        assert!(api.breaker_allows_request());
+    }
+
+    #[test]
+    fn raw_station_into_station_maps_fields() {
+        let stations: Vec<RawStation> = RadioApi::parse_response(BROADCAST_LIST_FIXTURE).unwrap();
+        let station = stations
+            .into_iter()
+            .nth(1)
+            .unwrap()
+            .into_station("西藏");
+
+        assert_eq!(station.province, "西藏");
+        assert_eq!(station.language.as_deref(), Some("BO"));
+        assert!(!station.is_custom);
+    }
 }
@@ -0,0 +1,1407 @@
+//! B 站音频源客户端
+//!
+//! 通过 playurl 接口获取 DASH 音频流地址，供 `bili:` 前缀的电台播放；
+//! 音频区（au）条目走专门的 song url 接口，绕开 DASH 视频的复杂度。
+//! 音质按设置选择，auto 模式根据最近一次实测吞吐自动降档。
+
+use anyhow::{anyhow, bail, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::settings::{BilibiliAudioQuality, BilibiliCdnSettings};
+
+/// playurl 接口地址
+const PLAYURL_API: &str = "https://api.bilibili.com/x/player/playurl";
+/// 音频区 song url 接口地址
+const SONG_URL_API: &str = "https://www.bilibili.com/audio/music-service-c/web/url";
+/// 请求必须带站内 Referer，否则会被拒绝
+const REFERER: &str = "https://www.bilibili.com";
+/// auto 模式吞吐探测的下载量（字节）
+const PROBE_BYTES: usize = 128 * 1024;
+/// auto 模式选流时的吞吐安全余量（只用八成带宽）
+const AUTO_HEADROOM: f64 = 0.8;
+/// 探测单个 base/backup 地址可用性的超时（秒）
+const URL_PROBE_TIMEOUT_SECS: u64 = 4;
+/// 关键词搜索接口地址
+const SEARCH_API: &str = "https://api.bilibili.com/x/web-interface/search/type";
+/// 搜索结果缓存的有效期（秒）
+const SEARCH_CACHE_TTL_SECS: i64 = 30 * 60;
+/// 每个关键词最多抓取的结果页数
+const SEARCH_MAX_PAGES: u32 = 3;
+/// 风控拦截的返回码（request intercepted / 风控校验失败）
+const RATE_LIMIT_CODES: [i32; 2] = [-412, -352];
+/// 风控退避的起始时长（秒）
+const BACKOFF_BASE_SECS: u64 = 30;
+/// 风控退避的上限（秒）
+const BACKOFF_MAX_SECS: u64 = 900;
+/// 浏览器指纹 spi 接口，下发正式的 buvid3/buvid4
+const SPI_API: &str = "https://api.bilibili.com/x/frontend/finger/spi";
+/// bili_ticket 签发接口
+const TICKET_API: &str =
+    "https://api.bilibili.com/bapis/bilibili.api.ticket.v1.Ticket/GenWebTicket";
+/// bili_ticket 签名用的固定 HMAC 密钥（与 web 端一致）
+const TICKET_HMAC_KEY: &[u8] = b"XgwSnGZ1p";
+/// 匿名身份持久化文件名
+const IDENTITY_FILE: &str = "bilibili_identity.json";
+
+/// 弹幕列表接口地址（返回 XML）
+const DANMAKU_API: &str = "https://api.bilibili.com/x/v1/dm/list.so";
+/// 弹幕密度统计的窗口大小（秒）
+const DANMAKU_WINDOW_SECS: u64 = 30;
+/// 低于这个弹幕总数时不做高能起播，密度曲线没有统计意义
+const DANMAKU_MIN_COUNT: usize = 20;
+
+/// 选台偏好评分文件：记录各 UP 主被听完 / 跳过的次数
+const PREFERENCE_FILE: &str = "bilibili_preferences.json";
+
+/// 搜索池缓存临近过期多少秒以内就值得后台预刷新
+const SEARCH_REFRESH_LEAD_SECS: i64 = 10 * 60;
+/// ticket 提前刷新的余量（秒）
+const TICKET_REFRESH_MARGIN_SECS: i64 = 3600;
+/// ticket 获取失败后的重试间隔（秒）
+const TICKET_RETRY_SECS: i64 = 600;
+/// 被风控后轮换的 User-Agent 池
+const USER_AGENTS: [&str; 3] = [
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64)",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/123.0.0.0 Safari/537.36",
+];
+
+/// 风控退避状态
+#[derive(Debug, Default)]
+struct RateLimitState {
+    /// 连续被拦截的次数
+    consecutive_intercepts: u32,
+    /// 退避截止时间戳（秒）
+    backoff_until: i64,
+}
+
+/// 匿名请求身份，被风控后轮换
+#[derive(Debug)]
+struct RequestIdentity {
+    ua_index: usize,
+    persisted: PersistedIdentity,
+}
+
+/// 持久化的匿名身份（buvid 和 bili_ticket）
+///
+/// 像浏览器一样固定携带同一套指纹，比每次随机生成更不容易
+/// 触发风控；落盘后重启应用也继续使用。
+#[derive(Debug, Clone, Default, serde::Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct PersistedIdentity {
+    buvid3: String,
+    buvid4: String,
+    bili_ticket: String,
+    /// ticket 过期时间戳（秒）
+    ticket_expires_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpiResponse {
+    code: i32,
+    data: Option<SpiData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpiData {
+    b_3: String,
+    b_4: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TicketResponse {
+    code: i32,
+    data: Option<TicketData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TicketData {
+    ticket: String,
+    #[serde(default)]
+    created_at: i64,
+    #[serde(default)]
+    ttl: i64,
+}
+
+/// 按连续拦截次数算指数退避时长（秒）
+fn backoff_secs(consecutive_intercepts: u32) -> u64 {
+    let shift = consecutive_intercepts.saturating_sub(1).min(5);
+    (BACKOFF_BASE_SECS << shift).min(BACKOFF_MAX_SECS)
+}
+
+/// 生成一个随机 buvid3（35 位十六进制 + infoc 后缀，模拟浏览器格式）
+fn generate_buvid() -> String {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+    let hex: String = (0..35)
+        .map(|_| {
+            let digit = rng.gen_range(0..16u32);
+            char::from_digit(digit, 16).unwrap().to_ascii_uppercase()
+        })
+        .collect();
+    format!("{}infoc", hex)
+}
+
+/// 搜索结果条目
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchItem {
+    #[serde(default)]
+    pub bvid: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+    /// 时长，形如 "12:34"
+    #[serde(default)]
+    pub duration: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    code: i32,
+    message: Option<String>,
+    data: Option<SearchData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchData {
+    /// 真实的结果页数，稀疏关键词可能只有一两页
+    #[serde(default, rename = "numPages")]
+    num_pages: u32,
+    #[serde(default)]
+    result: Vec<SearchItem>,
+}
+
+/// 单个关键词的搜索结果缓存
+#[derive(Debug, Clone)]
+struct SearchCacheEntry {
+    fetched_at: i64,
+    items: Vec<SearchItem>,
+}
+
+impl SearchCacheEntry {
+    /// 缓存是否仍在有效期内
+    fn is_fresh(&self, now: i64) -> bool {
+        now - self.fetched_at < SEARCH_CACHE_TTL_SECS
+    }
+}
+
+/// 单个 UP 主在某关键词下的收听记录
+#[derive(Debug, Clone, Default, serde::Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct SelectionScore {
+    /// 完整听完的次数
+    completes: u32,
+    /// 中途跳过的次数
+    skips: u32,
+}
+
+impl SelectionScore {
+    /// 拉普拉斯平滑后的完成率，作为随机选台权重。
+    /// 没有记录时按 0.5 对待，单次跳过不会把 UP 主打入冷宫。
+    fn weight(&self) -> f64 {
+        (f64::from(self.completes) + 1.0) / (f64::from(self.completes + self.skips) + 2.0)
+    }
+}
+
+/// 关键词 -> UP 主 -> 收听记录
+type PreferenceMap = std::collections::HashMap<String, std::collections::HashMap<String, SelectionScore>>;
+
+/// 按累积权重把 r ∈ [0,1) 映射到一个下标
+fn weighted_index(weights: &[f64], r: f64) -> usize {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return 0;
+    }
+    let target = r * total;
+    let mut acc = 0.0;
+    for (index, weight) in weights.iter().enumerate() {
+        acc += weight;
+        if target < acc {
+            return index;
+        }
+    }
+    weights.len() - 1
+}
+
+/// DASH 音频流条目
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DashAudio {
+    /// 音质 ID，数值越大音质越高
+    pub id: u32,
+    /// 码率（bps）
+    #[serde(default)]
+    pub bandwidth: u64,
+    pub base_url: String,
+    /// 备用地址，主地址 403 或超时后按顺序尝试
+    #[serde(default, alias = "backup_url")]
+    pub backup_url: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayUrlResponse {
+    code: i32,
+    message: Option<String>,
+    data: Option<PlayUrlData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayUrlData {
+    dash: Option<Dash>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Dash {
+    #[serde(default)]
+    audio: Vec<DashAudio>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SongUrlResponse {
+    code: i32,
+    msg: Option<String>,
+    data: Option<SongUrlData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SongUrlData {
+    #[serde(default)]
+    cdns: Vec<String>,
+}
+
+/// B 站 API 客户端
+pub struct BilibiliApi {
+    client: Client,
+    /// 身份文件所在的数据目录
+    data_dir: std::path::PathBuf,
+    /// 最近一次实测下载吞吐（kbps），auto 模式据此选流
+    recent_throughput_kbps: std::sync::Mutex<Option<u64>>,
+    /// 关键词 -> 搜索结果缓存，带 TTL
+    search_cache: std::sync::Mutex<std::collections::HashMap<String, SearchCacheEntry>>,
+    /// 风控退避状态
+    rate_limit: std::sync::Mutex<RateLimitState>,
+    /// 匿名请求身份（UA + buvid3），被风控后轮换
+    identity: std::sync::Mutex<RequestIdentity>,
+    /// 听完 / 跳过行为统计，用于偏向随机选台
+    preferences: std::sync::Mutex<PreferenceMap>,
+    /// 搜索限定的分区 tid，0 表示全站
+    search_tid: std::sync::atomic::AtomicU32,
+    /// 各接口结构漂移的累计计数，key 形如 "playurl: dash.audio 不是数组"
+    schema_drift: std::sync::Mutex<std::collections::HashMap<String, u64>>,
+}
+
+impl BilibiliApi {
+    /// 创建新的 API 客户端，匿名身份从数据目录加载
+    pub fn new(data_dir: std::path::PathBuf) -> Self {
+        // 搜索、取流地址都是小请求：开启压缩并复用连接，
+        // 刷新一批电台时不必每个请求都重新握手
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .connect_timeout(Duration::from_secs(10))
+            .gzip(true)
+            .brotli(true)
+            .pool_max_idle_per_host(8)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .http2_keep_alive_interval(Duration::from_secs(30))
+            .http2_keep_alive_while_idle(true)
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        let persisted = load_persisted_identity(&data_dir);
+        let preferences = load_preferences(&data_dir);
+
+        Self {
+            client,
+            data_dir,
+            recent_throughput_kbps: std::sync::Mutex::new(None),
+            search_cache: std::sync::Mutex::new(std::collections::HashMap::new()),
+            rate_limit: std::sync::Mutex::new(RateLimitState::default()),
+            identity: std::sync::Mutex::new(RequestIdentity {
+                ua_index: 0,
+                persisted,
+            }),
+            preferences: std::sync::Mutex::new(preferences),
+            search_tid: std::sync::atomic::AtomicU32::new(0),
+            schema_drift: std::sync::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    /// 记录一批结构漂移：计数并写警告日志
+    ///
+    /// 宽松解析保证播放继续工作，这里只负责把"字段没了 / 类型变了"
+    /// 尽早暴露出来，B 站改接口时维护者和用户都能第一时间察觉。
+    fn note_schema_drift(&self, api: &str, issues: &[&'static str]) {
+        if issues.is_empty() {
+            return;
+        }
+        if let Ok(mut counts) = self.schema_drift.lock() {
+            for issue in issues {
+                *counts.entry(format!("{}: {}", api, issue)).or_insert(0) += 1;
+            }
+        }
+        log::warn!("B 站 {} 接口结构疑似变更: {}", api, issues.join("；"));
+    }
+
+    /// 各接口累计的结构漂移计数，供 get_api_health 命令展示
+    pub fn schema_drift_counts(&self) -> std::collections::HashMap<String, u64> {
+        self.schema_drift
+            .lock()
+            .map(|counts| counts.clone())
+            .unwrap_or_default()
+    }
+
+    /// 设置搜索限定的分区 tid（0 表示全站）
+    ///
+    /// 限定到曲艺、音乐等分区能明显减少只是提到关键词的
+    /// vlog 之类跑题结果。分区变化时丢弃旧搜索池缓存。
+    pub fn set_search_tid(&self, tid: u32) {
+        let previous = self
+            .search_tid
+            .swap(tid, std::sync::atomic::Ordering::Relaxed);
+        if previous != tid {
+            if let Ok(mut cache) = self.search_cache.lock() {
+                cache.clear();
+            }
+        }
+    }
+
+    /// 确保匿名身份可用：没有 buvid 时向 spi 接口申请，ticket 过期时刷新
+    async fn ensure_identity(&self) {
+        let now = chrono::Utc::now().timestamp();
+        let (needs_buvid, needs_ticket) = match self.identity.lock() {
+            Ok(identity) => (
+                identity.persisted.buvid3.is_empty(),
+                identity.persisted.ticket_expires_at - TICKET_REFRESH_MARGIN_SECS <= now,
+            ),
+            Err(_) => return,
+        };
+        if !needs_buvid && !needs_ticket {
+            return;
+        }
+
+        if needs_buvid {
+            let (buvid3, buvid4) = match self.fetch_spi_buvid().await {
+                Ok(pair) => pair,
+                // spi 不可用时退回本地随机生成，之后不再反复重试
+                Err(e) => {
+                    log::debug!("获取正式 buvid 失败，使用本地随机值: {}", e);
+                    (generate_buvid(), generate_buvid())
+                }
+            };
+            if let Ok(mut identity) = self.identity.lock() {
+                identity.persisted.buvid3 = buvid3;
+                identity.persisted.buvid4 = buvid4;
+            }
+        }
+
+        if needs_ticket {
+            match self.fetch_bili_ticket().await {
+                Ok((ticket, expires_at)) => {
+                    if let Ok(mut identity) = self.identity.lock() {
+                        identity.persisted.bili_ticket = ticket;
+                        identity.persisted.ticket_expires_at = expires_at;
+                    }
+                }
+                Err(e) => {
+                    log::debug!("获取 bili_ticket 失败，{} 秒后再试: {}", TICKET_RETRY_SECS, e);
+                    if let Ok(mut identity) = self.identity.lock() {
+                        identity.persisted.ticket_expires_at =
+                            now + TICKET_REFRESH_MARGIN_SECS + TICKET_RETRY_SECS;
+                    }
+                }
+            }
+        }
+
+        self.persist_identity();
+    }
+
+    /// 向 spi 接口申请正式的 buvid3/buvid4
+    async fn fetch_spi_buvid(&self) -> Result<(String, String)> {
+        let text = self
+            .client
+            .get(SPI_API)
+            .header(reqwest::header::REFERER, REFERER)
+            .send()
+            .await?
+            .text()
+            .await?;
+        let response: SpiResponse = serde_json::from_str(&text)?;
+        if response.code != 0 {
+            bail!("spi 接口返回错误: {}", response.code);
+        }
+        let data = response.data.ok_or_else(|| anyhow!("spi 接口没有返回数据"))?;
+        Ok((data.b_3, data.b_4))
+    }
+
+    /// 像 web 端一样签名并申请 bili_ticket，返回（ticket，过期时间戳）
+    async fn fetch_bili_ticket(&self) -> Result<(String, i64)> {
+        let ts = chrono::Utc::now().timestamp();
+        let hexsign = hmac_sha256_hex(TICKET_HMAC_KEY, &format!("ts{}", ts));
+        let url = format!(
+            "{}?key_id=ec02&hexsign={}&context%5Bts%5D={}&csrf=",
+            TICKET_API, hexsign, ts
+        );
+        let text = self
+            .client
+            .post(&url)
+            .header(reqwest::header::REFERER, REFERER)
+            .send()
+            .await?
+            .text()
+            .await?;
+        let response: TicketResponse = serde_json::from_str(&text)?;
+        if response.code != 0 {
+            bail!("bili_ticket 接口返回错误: {}", response.code);
+        }
+        let data = response
+            .data
+            .ok_or_else(|| anyhow!("bili_ticket 接口没有返回数据"))?;
+        let expires_at = if data.ttl > 0 {
+            data.created_at.max(ts) + data.ttl
+        } else {
+            ts + 3 * 24 * 3600
+        };
+        Ok((data.ticket, expires_at))
+    }
+
+    /// 把当前身份落盘，重启后继续使用同一套指纹
+    fn persist_identity(&self) {
+        if let Ok(identity) = self.identity.lock() {
+            if let Ok(json) = serde_json::to_string_pretty(&identity.persisted) {
+                let _ =
+                    crate::utils::fs::write_atomic(&self.data_dir.join(IDENTITY_FILE), json);
+            }
+        }
+    }
+
+    /// 风控退避的剩余秒数，None 表示当前未被限制
+    pub fn rate_limited_secs(&self) -> Option<u64> {
+        let state = self.rate_limit.lock().ok()?;
+        let remaining = state.backoff_until - chrono::Utc::now().timestamp();
+        (remaining > 0).then_some(remaining as u64)
+    }
+
+    /// 风控守卫：仍在退避期内时直接报错，不再打接口
+    fn check_rate_limit(&self) -> Result<()> {
+        if let Some(secs) = self.rate_limited_secs() {
+            bail!("B 站暂时被风控限制，约 {} 秒后自动恢复", secs);
+        }
+        Ok(())
+    }
+
+    /// 记录一次接口返回码：风控码进入指数退避并轮换身份，正常响应清零
+    fn note_api_code(&self, code: i32) {
+        let Ok(mut state) = self.rate_limit.lock() else {
+            return;
+        };
+        if RATE_LIMIT_CODES.contains(&code) {
+            state.consecutive_intercepts += 1;
+            let secs = backoff_secs(state.consecutive_intercepts);
+            state.backoff_until = chrono::Utc::now().timestamp() + secs as i64;
+            log::warn!(
+                "B 站风控拦截（{}），第 {} 次，退避 {} 秒并轮换请求身份",
+                code,
+                state.consecutive_intercepts,
+                secs
+            );
+            drop(state);
+            self.rotate_identity();
+        } else {
+            state.consecutive_intercepts = 0;
+            state.backoff_until = 0;
+        }
+    }
+
+    /// 换一个 User-Agent 和新的 buvid，并落盘
+    fn rotate_identity(&self) {
+        if let Ok(mut identity) = self.identity.lock() {
+            identity.ua_index = (identity.ua_index + 1) % USER_AGENTS.len();
+            identity.persisted.buvid3 = generate_buvid();
+            identity.persisted.buvid4 = generate_buvid();
+        }
+        self.persist_identity();
+    }
+
+    /// 当前身份对应的（User-Agent，Cookie）请求头
+    fn identity_headers(&self) -> (&'static str, String) {
+        match self.identity.lock() {
+            Ok(identity) => (
+                USER_AGENTS[identity.ua_index % USER_AGENTS.len()],
+                identity.persisted.cookie_header(),
+            ),
+            Err(_) => (USER_AGENTS[0], String::new()),
+        }
+    }
+
+    /// 获取指定条目的音频流地址
+    ///
+    /// `raw_id` 形如 `BV1xx411c7mD/123456`（bvid/cid）；
+    /// 音频区条目形如 `au/12345`（song id）。
+    pub async fn get_audio_url(
+        &self,
+        raw_id: &str,
+        quality: BilibiliAudioQuality,
+        cdn: &BilibiliCdnSettings,
+    ) -> Result<String> {
+        if let Some(sid) = raw_id.strip_prefix("au/") {
+            return self.get_song_url(sid, quality, cdn).await;
+        }
+
+        let audio = self.fetch_dash_audio(raw_id).await?;
+        let recent_kbps = *self.recent_throughput_kbps.lock().unwrap();
+        let chosen = Self::select_audio(&audio, quality, recent_kbps)
+            .ok_or_else(|| anyhow!("playurl 响应中没有可用的音频流"))?
+            .clone();
+
+        // 主地址可能 403 或超时，按 CDN 偏好排序后找第一个真正可用的地址
+        let playable_url = self.first_reachable_url(&chosen, cdn).await?;
+
+        // auto 模式顺带实测一次吞吐，供下次选流参考
+        if quality == BilibiliAudioQuality::Auto {
+            if let Ok(kbps) = self.probe_throughput(&playable_url).await {
+                *self.recent_throughput_kbps.lock().unwrap() = Some(kbps);
+            }
+        }
+
+        Ok(playable_url)
+    }
+
+    /// 获取音频区条目的播放地址
+    ///
+    /// song url 接口直接返回各 CDN 的完整地址列表，
+    /// 按 CDN 偏好排序后取第一个可用的。
+    async fn get_song_url(
+        &self,
+        sid: &str,
+        quality: BilibiliAudioQuality,
+        cdn: &BilibiliCdnSettings,
+    ) -> Result<String> {
+        let url = format!(
+            "{}?sid={}&privilege=2&quality={}",
+            SONG_URL_API,
+            sid,
+            song_quality_param(quality)
+        );
+        let text = self.api_get(&url).await?;
+
+        let response: SongUrlResponse = serde_json::from_str(&text)?;
+        self.note_api_code(response.code);
+        if response.code != 0 {
+            return Err(api_error(
+                "song url",
+                response.code,
+                response.msg.unwrap_or_default(),
+            ));
+        }
+
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) {
+            self.note_schema_drift("song url", &song_url_drift(&value));
+        }
+
+        let mut candidates = response.data.map(|d| d.cdns).unwrap_or_default();
+        if candidates.is_empty() {
+            bail!("song url 响应中没有可用的 CDN 地址");
+        }
+        order_by_cdn_preference(&mut candidates, cdn);
+
+        for url in &candidates {
+            match self.probe_url(url).await {
+                Ok(()) => return Ok(url.clone()),
+                Err(e) => log::debug!("音频区 CDN 地址不可用，尝试下一个: {}", e),
+            }
+        }
+        bail!("音频区全部 {} 个 CDN 地址均不可用", candidates.len())
+    }
+
+    /// 发起一次带身份头的 API GET，退避期内直接报错
+    async fn api_get(&self, url: &str) -> Result<String> {
+        self.check_rate_limit()?;
+        self.ensure_identity().await;
+        let (user_agent, cookie) = self.identity_headers();
+        let text = self
+            .client
+            .get(url)
+            .header(reqwest::header::REFERER, REFERER)
+            .header(reqwest::header::USER_AGENT, user_agent)
+            .header(reqwest::header::COOKIE, cookie)
+            .send()
+            .await?
+            .text()
+            .await?;
+        Ok(text)
+    }
+
+    /// 获取并解析 DASH 音频流列表
+    async fn fetch_dash_audio(&self, raw_id: &str) -> Result<Vec<DashAudio>> {
+        let (bvid, cid) = raw_id
+            .split_once('/')
+            .ok_or_else(|| anyhow!("无效的 B 站电台 ID: {}", raw_id))?;
+
+        let url = format!("{}?bvid={}&cid={}&fnval=16", PLAYURL_API, bvid, cid);
+        let text = self.api_get(&url).await?;
+
+        let response: PlayUrlResponse = serde_json::from_str(&text)?;
+        self.note_api_code(response.code);
+        if response.code != 0 {
+            return Err(api_error(
+                "playurl",
+                response.code,
+                response.message.unwrap_or_default(),
+            ));
+        }
+
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) {
+            self.note_schema_drift("playurl", &playurl_drift(&value));
+        }
+
+        Ok(response
+            .data
+            .and_then(|d| d.dash)
+            .map(|d| d.audio)
+            .unwrap_or_default())
+    }
+
+    /// 按弹幕密度计算高能起播点（秒），实验性
+    ///
+    /// 抓取视频的弹幕列表，按固定窗口统计密度，返回最密集窗口的
+    /// 起点。长综艺上传常有几分钟的空场开头，从高能段起播可以
+    /// 直接跳过。弹幕太少时返回 0，按正常方式从头播放。
+    pub async fn densest_segment_start(&self, raw_id: &str) -> Result<u64> {
+        let (_, cid) = raw_id
+            .split_once('/')
+            .ok_or_else(|| anyhow!("该条目不支持弹幕高能起播: {}", raw_id))?;
+
+        let url = format!("{}?oid={}", DANMAKU_API, cid);
+        let xml = self.api_get(&url).await?;
+        let times = parse_danmaku_times(&xml);
+        if times.len() < DANMAKU_MIN_COUNT {
+            return Ok(0);
+        }
+        Ok(densest_window_start(&times, DANMAKU_WINDOW_SECS))
+    }
+
+    /// 从关键词的搜索池里随机挑一个条目
+    ///
+    /// 盲选随机页码在稀疏关键词下经常撞到空页；这里先抓到结果池
+    /// （带 TTL 缓存），再从池内随机采样，池空才算真的没有结果。
+    pub async fn get_random_audio(&self, keyword: &str) -> Result<SearchItem> {
+        let pool = self.search_pool(keyword).await?;
+        if pool.is_empty() {
+            bail!("关键词没有搜索结果: {}", keyword);
+        }
+        // 按各 UP 主的历史完成率加权，听完得多的被选中概率更高
+        use rand::Rng;
+        let weights: Vec<f64> = pool
+            .iter()
+            .map(|item| self.selection_weight(keyword, &item.author))
+            .collect();
+        let index = weighted_index(&weights, rand::thread_rng().gen::<f64>());
+        Ok(pool[index].clone())
+    }
+
+    /// 记录一次播放结果（听完或跳过），自动调校后续随机选台
+    pub fn note_playback_result(&self, keyword: &str, author: &str, completed: bool) {
+        if let Ok(mut prefs) = self.preferences.lock() {
+            let score = prefs
+                .entry(keyword.to_string())
+                .or_default()
+                .entry(author.to_string())
+                .or_default();
+            if completed {
+                score.completes += 1;
+            } else {
+                score.skips += 1;
+            }
+            if let Ok(json) = serde_json::to_string_pretty(&*prefs) {
+                let _ = crate::utils::fs::write_atomic(&self.data_dir.join(PREFERENCE_FILE), json);
+            }
+        }
+    }
+
+    /// 某关键词下某 UP 主的选台权重，没有记录时为 0.5
+    fn selection_weight(&self, keyword: &str, author: &str) -> f64 {
+        self.preferences
+            .lock()
+            .ok()
+            .and_then(|prefs| {
+                prefs
+                    .get(keyword)
+                    .and_then(|scores| scores.get(author))
+                    .map(SelectionScore::weight)
+            })
+            .unwrap_or_else(|| SelectionScore::default().weight())
+    }
+
+    /// 获取关键词的搜索结果池，命中有效缓存时不发请求
+    pub async fn search_pool(&self, keyword: &str) -> Result<Vec<SearchItem>> {
+        let now = chrono::Utc::now().timestamp();
+        if let Ok(cache) = self.search_cache.lock() {
+            if let Some(entry) = cache.get(keyword) {
+                if entry.is_fresh(now) {
+                    return Ok(entry.items.clone());
+                }
+            }
+        }
+
+        // 先抓第一页拿到真实页数，再顺序补抓后续页
+        let first = self.fetch_search_page(keyword, 1).await?;
+        let total_pages = first.num_pages.max(1);
+        let mut items = first.result;
+        for page in 2..=total_pages.min(SEARCH_MAX_PAGES) {
+            match self.fetch_search_page(keyword, page).await {
+                Ok(data) => items.extend(data.result),
+                // 后续页失败不致命，用已有的池
+                Err(e) => {
+                    log::debug!("搜索第 {} 页失败，使用已有结果: {}", page, e);
+                    break;
+                }
+            }
+        }
+
+        for item in &mut items {
+            item.title = strip_em_tags(&item.title);
+        }
+        items.retain(|item| !item.bvid.is_empty());
+        log::debug!(
+            "搜索池已更新: {} -> {} 条（共 {} 页）",
+            keyword,
+            items.len(),
+            total_pages
+        );
+
+        if let Ok(mut cache) = self.search_cache.lock() {
+            cache.insert(
+                keyword.to_string(),
+                SearchCacheEntry {
+                    fetched_at: now,
+                    items: items.clone(),
+                },
+            );
+        }
+        Ok(items)
+    }
+
+    /// 需要后台预刷新的关键词：搜索池缓存已过期或临近过期
+    fn keywords_needing_refresh(&self) -> Vec<String> {
+        let now = chrono::Utc::now().timestamp();
+        self.search_cache
+            .lock()
+            .map(|cache| {
+                cache
+                    .iter()
+                    .filter(|(_, entry)| !entry.is_fresh(now + SEARCH_REFRESH_LEAD_SECS))
+                    .map(|(keyword, _)| keyword.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 后台预刷新所有临近过期的搜索池，返回刷新的关键词数
+    pub async fn refresh_search_pools(&self) -> usize {
+        let keywords = self.keywords_needing_refresh();
+        let mut refreshed = 0;
+        for keyword in &keywords {
+            // 丢弃旧缓存，search_pool 会重新抓取
+            if let Ok(mut cache) = self.search_cache.lock() {
+                cache.remove(keyword);
+            }
+            match self.search_pool(keyword).await {
+                Ok(_) => refreshed += 1,
+                // 风控或网络问题时本轮到此为止，下个周期再试
+                Err(e) => {
+                    log::debug!("后台刷新搜索池失败: {} ({})", keyword, e);
+                    break;
+                }
+            }
+        }
+        refreshed
+    }
+
+    /// 抓取一页搜索结果
+    async fn fetch_search_page(&self, keyword: &str, page: u32) -> Result<SearchData> {
+        let mut url = format!(
+            "{}?search_type=video&keyword={}&page={}",
+            SEARCH_API,
+            urlencoding::encode(keyword),
+            page
+        );
+        // 配置了分区时带上 tids，把结果限定在该分区内
+        let tid = self.search_tid.load(std::sync::atomic::Ordering::Relaxed);
+        if tid > 0 {
+            url.push_str(&format!("&tids={}", tid));
+        }
+        let text = self.api_get(&url).await?;
+
+        let response: SearchResponse = serde_json::from_str(&text)?;
+        self.note_api_code(response.code);
+        if response.code != 0 {
+            return Err(api_error(
+                "搜索",
+                response.code,
+                response.message.unwrap_or_default(),
+            ));
+        }
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) {
+            self.note_schema_drift("搜索", &search_drift(&value));
+        }
+
+        response.data.ok_or_else(|| anyhow!("搜索接口没有返回数据"))
+    }
+
+    /// 依次探测主地址和备用地址，返回第一个可用的
+    async fn first_reachable_url(
+        &self,
+        audio: &DashAudio,
+        cdn: &BilibiliCdnSettings,
+    ) -> Result<String> {
+        let mut candidates = vec![audio.base_url.clone()];
+        candidates.extend(audio.backup_url.iter().cloned());
+        order_by_cdn_preference(&mut candidates, cdn);
+
+        for url in &candidates {
+            match self.probe_url(url).await {
+                Ok(()) => return Ok(url.clone()),
+                Err(e) => log::debug!("B 站音频地址不可用，尝试下一个: {}", e),
+            }
+        }
+        bail!("主地址和全部 {} 个备用地址均不可用", audio.backup_url.len())
+    }
+
+    /// 用短超时的小范围请求探测地址是否可用
+    async fn probe_url(&self, url: &str) -> Result<()> {
+        let response = tokio::time::timeout(
+            Duration::from_secs(URL_PROBE_TIMEOUT_SECS),
+            self.client
+                .get(url)
+                .header(reqwest::header::REFERER, REFERER)
+                .header(reqwest::header::RANGE, "bytes=0-1")
+                .send(),
+        )
+        .await??;
+        if !response.status().is_success() {
+            bail!("HTTP {}", response.status());
+        }
+        Ok(())
+    }
+
+    /// 按音质偏好从 DASH 音频流中选择一条
+    ///
+    /// auto 模式下在实测吞吐（留出安全余量）内选最高档，
+    /// 没有吞吐样本时取中间档。
+    fn select_audio(
+        streams: &[DashAudio],
+        quality: BilibiliAudioQuality,
+        recent_kbps: Option<u64>,
+    ) -> Option<&DashAudio> {
+        if streams.is_empty() {
+            return None;
+        }
+
+        let mut sorted: Vec<&DashAudio> = streams.iter().collect();
+        sorted.sort_by_key(|s| (s.bandwidth, s.id));
+
+        match quality {
+            BilibiliAudioQuality::High => sorted.last().copied(),
+            BilibiliAudioQuality::Low => sorted.first().copied(),
+            BilibiliAudioQuality::Medium => sorted.get(sorted.len() / 2).copied(),
+            BilibiliAudioQuality::Auto => match recent_kbps {
+                Some(kbps) => {
+                    let budget_bps = (kbps as f64 * 1000.0 * AUTO_HEADROOM) as u64;
+                    sorted
+                        .iter()
+                        .rev()
+                        .find(|s| s.bandwidth <= budget_bps)
+                        .copied()
+                        .or_else(|| sorted.first().copied())
+                }
+                None => sorted.get(sorted.len() / 2).copied(),
+            },
+        }
+    }
+
+    /// 下载所选音频流的开头一小段，实测下载吞吐（kbps）
+    async fn probe_throughput(&self, url: &str) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let bytes = self
+            .client
+            .get(url)
+            .header(reqwest::header::REFERER, REFERER)
+            .header(
+                reqwest::header::RANGE,
+                format!("bytes=0-{}", PROBE_BYTES - 1),
+            )
+            .send()
+            .await?
+            .bytes()
+            .await?;
+        let elapsed_ms = start.elapsed().as_millis().max(1) as u64;
+        Ok(bytes.len() as u64 * 8 / elapsed_ms)
+    }
+
+    /// 对电台的所有 base/backup 地址按 CDN 主机分组测速
+    pub async fn benchmark_cdn(&self, raw_id: &str) -> Result<Vec<CdnBenchmark>> {
+        let audio = self.fetch_dash_audio(raw_id).await?;
+
+        // 每个主机只测第一个出现的地址
+        let mut seen_hosts = Vec::new();
+        let mut candidates = Vec::new();
+        for stream in &audio {
+            for url in std::iter::once(&stream.base_url).chain(stream.backup_url.iter()) {
+                let host = url_host(url).to_string();
+                if host.is_empty() || seen_hosts.contains(&host) {
+                    continue;
+                }
+                seen_hosts.push(host.clone());
+                candidates.push((host, url.clone()));
+            }
+        }
+
+        let mut results = Vec::new();
+        for (host, url) in candidates {
+            let throughput_kbps = self.probe_throughput(&url).await.ok();
+            results.push(CdnBenchmark {
+                host,
+                throughput_kbps,
+            });
+        }
+        results.sort_by_key(|r| std::cmp::Reverse(r.throughput_kbps));
+        Ok(results)
+    }
+}
+
+/// 单个 CDN 主机的测速结果
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CdnBenchmark {
+    /// CDN 主机名
+    pub host: String,
+    /// 实测吞吐（kbps），None 表示探测失败
+    pub throughput_kbps: Option<u64>,
+}
+
+/// 把音质偏好映射为音频区接口的 quality 参数
+///
+/// 0=128k、1=192k、2=320k、3=无损；auto 取 320k，
+/// 音频区多为音乐内容，带宽压力远小于直播流。
+fn song_quality_param(quality: BilibiliAudioQuality) -> u8 {
+    match quality {
+        BilibiliAudioQuality::Low => 0,
+        BilibiliAudioQuality::Medium => 1,
+        BilibiliAudioQuality::Auto => 2,
+        BilibiliAudioQuality::High => 3,
+    }
+}
+
+/// 提取 URL 的主机名部分
+fn url_host(url: &str) -> &str {
+    url.split('/').nth(2).unwrap_or("").split(':').next().unwrap_or("")
+}
+
+/// 按 CDN 偏好重排候选地址：偏好的在前，避开的在后，其余保持原顺序
+fn order_by_cdn_preference(urls: &mut [String], cdn: &BilibiliCdnSettings) {
+    let rank = |url: &str| {
+        let host = url_host(url);
+        if cdn
+            .avoided_patterns
+            .iter()
+            .any(|p| !p.is_empty() && host.contains(p))
+        {
+            2
+        } else if cdn
+            .preferred_patterns
+            .iter()
+            .any(|p| !p.is_empty() && host.contains(p))
+        {
+            0
+        } else {
+            1
+        }
+    };
+    urls.sort_by_key(|url| rank(url));
+}
+
+/// 严格校验 playurl 成功响应的结构，返回与预期不符的地方
+///
+/// 宽松的 serde 定义会把缺失字段静默回落默认值，这里按原始 JSON
+/// 检查关键路径是否还在，作为结构漂移的报警依据。
+fn playurl_drift(value: &serde_json::Value) -> Vec<&'static str> {
+    let Some(data) = value.get("data") else {
+        return vec!["data 字段缺失"];
+    };
+    let Some(dash) = data.get("dash") else {
+        return vec!["data.dash 字段缺失"];
+    };
+    let mut issues = Vec::new();
+    match dash.get("audio").and_then(|audio| audio.as_array()) {
+        None => issues.push("dash.audio 不是数组"),
+        Some(streams) => {
+            let has_url = |s: &serde_json::Value| {
+                ["baseUrl", "base_url"]
+                    .iter()
+                    .any(|key| s.get(*key).and_then(|u| u.as_str()).is_some_and(|u| !u.is_empty()))
+            };
+            if streams.iter().any(|s| !has_url(s)) {
+                issues.push("audio 条目缺少 baseUrl");
+            }
+            if streams.iter().any(|s| s.get("id").and_then(|v| v.as_u64()).is_none()) {
+                issues.push("audio 条目缺少 id");
+            }
+        }
+    }
+    issues
+}
+
+/// 严格校验 song url 成功响应的结构
+fn song_url_drift(value: &serde_json::Value) -> Vec<&'static str> {
+    let Some(data) = value.get("data") else {
+        return vec!["data 字段缺失"];
+    };
+    match data.get("cdns").and_then(|cdns| cdns.as_array()) {
+        None => vec!["data.cdns 不是数组"],
+        Some(cdns) if cdns.iter().any(|c| c.as_str().is_none()) => {
+            vec!["cdns 条目不是字符串"]
+        }
+        Some(_) => Vec::new(),
+    }
+}
+
+/// 严格校验搜索成功响应的结构
+fn search_drift(value: &serde_json::Value) -> Vec<&'static str> {
+    let Some(data) = value.get("data") else {
+        return vec!["data 字段缺失"];
+    };
+    match data.get("result").and_then(|result| result.as_array()) {
+        None => vec!["data.result 不是数组"],
+        Some(items)
+            if !items.is_empty()
+                && items
+                    .iter()
+                    .all(|item| item.get("bvid").and_then(|v| v.as_str()).unwrap_or("").is_empty()) =>
+        {
+            vec!["result 条目全部缺少 bvid"]
+        }
+        Some(_) => Vec::new(),
+    }
+}
+
+/// 按返回码构造接口错误，风控码给出面向用户的提示
+fn api_error(api: &str, code: i32, message: String) -> anyhow::Error {
+    if RATE_LIMIT_CODES.contains(&code) {
+        anyhow!("B 站暂时被风控限制（{}），已进入退避，稍后自动恢复", code)
+    } else {
+        anyhow!("{} 接口返回错误: {} ({})", api, code, message)
+    }
+}
+
+/// 去掉搜索接口在标题里插入的关键词高亮标签
+fn strip_em_tags(title: &str) -> String {
+    title
+        .replace("<em class=\"keyword\">", "")
+        .replace("</em>", "")
+}
+
+/// 从弹幕 XML 中提取各弹幕的出现时间（秒）
+///
+/// 每条弹幕形如 `<d p="12.34,1,25,...">文本</d>`，p 属性的
+/// 第一段就是出现时间；只做轻量字符串切分，不引入 XML 解析器。
+fn parse_danmaku_times(xml: &str) -> Vec<f64> {
+    xml.split("<d p=\"")
+        .skip(1)
+        .filter_map(|chunk| chunk.split(',').next()?.parse::<f64>().ok())
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .collect()
+}
+
+/// 按固定窗口统计弹幕密度，返回最密集窗口的起点（秒）
+///
+/// 密度相同的窗口取最靠前的，避免随机跳到片尾。
+fn densest_window_start(times: &[f64], window_secs: u64) -> u64 {
+    let window = window_secs.max(1);
+    let mut counts: std::collections::HashMap<u64, usize> = std::collections::HashMap::new();
+    for secs in times {
+        *counts.entry(*secs as u64 / window).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map(|(bucket, _)| bucket * window)
+        .unwrap_or(0)
+}
+
+impl PersistedIdentity {
+    /// 拼出请求用的 Cookie 头，只携带已有的字段
+    fn cookie_header(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.buvid3.is_empty() {
+            parts.push(format!("buvid3={}", self.buvid3));
+        }
+        if !self.buvid4.is_empty() {
+            parts.push(format!("buvid4={}", self.buvid4));
+        }
+        if !self.bili_ticket.is_empty() {
+            parts.push(format!("bili_ticket={}", self.bili_ticket));
+        }
+        parts.join("; ")
+    }
+}
+
+/// 从数据目录加载持久化身份，文件不存在或损坏时返回空身份
+fn load_persisted_identity(data_dir: &std::path::Path) -> PersistedIdentity {
+    std::fs::read_to_string(data_dir.join(IDENTITY_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// 从数据目录加载选台偏好评分，文件不存在或损坏时从零开始
+fn load_preferences(data_dir: &std::path::Path) -> PreferenceMap {
+    std::fs::read_to_string(data_dir.join(PREFERENCE_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// HMAC-SHA256 签名的十六进制表示
+fn hmac_sha256_hex(key: &[u8], message: &str) -> String {
+    use hmac::Mac;
+    let mut mac = hmac::Hmac::<sha2::Sha256>::new_from_slice(key).expect("HMAC 接受任意长度密钥");
+    mac.update(message.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(id: u32, bandwidth: u64) -> DashAudio {
+        DashAudio {
+            id,
+            bandwidth,
+            base_url: format!("http://example.com/{}", id),
+            backup_url: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn selection_score_weight_biases_toward_completed() {
+        let fresh = SelectionScore::default();
+        let finished = SelectionScore {
+            completes: 8,
+            skips: 0,
+        };
+        let skipped = SelectionScore {
+            completes: 0,
+            skips: 8,
+        };
+        assert!((fresh.weight() - 0.5).abs() < f64::EPSILON);
+        assert!(finished.weight() > fresh.weight());
+        assert!(skipped.weight() < fresh.weight());
+    }
+
+    #[test]
+    fn weighted_index_picks_by_cumulative_weight() {
+        let weights = [0.1, 0.8, 0.1];
+        assert_eq!(weighted_index(&weights, 0.05), 0);
+        assert_eq!(weighted_index(&weights, 0.5), 1);
+        assert_eq!(weighted_index(&weights, 0.95), 2);
+        // 权重全为 0 时退化为第一个
+        assert_eq!(weighted_index(&[0.0, 0.0], 0.5), 0);
+    }
+
+    #[test]
+    fn dash_audio_parses_backup_urls() {
+        let json = r#"{
+            "id": 30232,
+            "bandwidth": 128000,
+            "baseUrl": "http://upos.example.com/a.m4s",
+            "backupUrl": ["http://mcdn.example.com/a.m4s"]
+        }"#;
+        let audio: DashAudio = serde_json::from_str(json).unwrap();
+        assert_eq!(audio.backup_url.len(), 1);
+    }
+
+    #[test]
+    fn select_audio_respects_quality_preference() {
+        let streams = vec![
+            stream(30216, 64_000),
+            stream(30280, 192_000),
+            stream(30232, 128_000),
+        ];
+
+        let pick = |q, kbps| BilibiliApi::select_audio(&streams, q, kbps).map(|s| s.id);
+        assert_eq!(pick(BilibiliAudioQuality::High, None), Some(30280));
+        assert_eq!(pick(BilibiliAudioQuality::Medium, None), Some(30232));
+        assert_eq!(pick(BilibiliAudioQuality::Low, None), Some(30216));
+    }
+
+    #[test]
+    fn select_audio_auto_uses_recent_throughput() {
+        let streams = vec![
+            stream(30216, 64_000),
+            stream(30232, 128_000),
+            stream(30280, 192_000),
+        ];
+
+        let pick = |kbps| {
+            BilibiliApi::select_audio(&streams, BilibiliAudioQuality::Auto, kbps).map(|s| s.id)
+        };
+        // 无样本取中间档
+        assert_eq!(pick(None), Some(30232));
+        // 吞吐充足选最高档（留两成余量）
+        assert_eq!(pick(Some(1000)), Some(30280));
+        // 吞吐紧张自动降档
+        assert_eq!(pick(Some(120)), Some(30216));
+        // 吞吐低于所有档位时兜底取最低档
+        assert_eq!(pick(Some(10)), Some(30216));
+    }
+
+    #[test]
+    fn select_audio_empty_returns_none() {
+        assert!(BilibiliApi::select_audio(&[], BilibiliAudioQuality::High, None).is_none());
+    }
+
+    #[test]
+    fn strip_em_tags_removes_highlight_markers() {
+        assert_eq!(
+            strip_em_tags("晚安<em class=\"keyword\">电台</em>精选"),
+            "晚安电台精选"
+        );
+        assert_eq!(strip_em_tags("普通标题"), "普通标题");
+    }
+
+    #[test]
+    fn parse_danmaku_times_reads_p_attribute() {
+        let xml = r#"<i><d p="12.34,1,25,16777215,0,0,abc,1">哈哈</d><d p="600.5,4,25,0,0,0,x,2">高能</d></i>"#;
+        assert_eq!(parse_danmaku_times(xml), vec![12.34, 600.5]);
+        assert!(parse_danmaku_times("<i></i>").is_empty());
+    }
+
+    #[test]
+    fn densest_window_prefers_earliest_on_tie() {
+        // 60-90 秒窗口 3 条最密集；0-30 与 120-150 各 2 条打平时取靠前的
+        let times = [65.0, 70.0, 80.0, 1.0, 2.0, 125.0, 130.0];
+        assert_eq!(densest_window_start(&times, 30), 60);
+
+        let tied = [1.0, 2.0, 125.0, 130.0];
+        assert_eq!(densest_window_start(&tied, 30), 0);
+    }
+
+    #[test]
+    fn playurl_drift_flags_missing_paths() {
+        let good = serde_json::json!({
+            "code": 0,
+            "data": { "dash": { "audio": [
+                { "id": 30232, "baseUrl": "http://example.com/a.m4s" }
+            ] } }
+        });
+        assert!(playurl_drift(&good).is_empty());
+
+        let renamed = serde_json::json!({
+            "code": 0,
+            "data": { "dash": { "audio": [
+                { "id": 30232, "playUrl": "http://example.com/a.m4s" }
+            ] } }
+        });
+        assert_eq!(playurl_drift(&renamed), vec!["audio 条目缺少 baseUrl"]);
+
+        let gone = serde_json::json!({ "code": 0, "data": {} });
+        assert_eq!(playurl_drift(&gone), vec!["data.dash 字段缺失"]);
+    }
+
+    #[test]
+    fn search_drift_flags_renamed_result_field() {
+        let good = serde_json::json!({
+            "data": { "result": [{ "bvid": "BV1xx411c7mD" }] }
+        });
+        assert!(search_drift(&good).is_empty());
+
+        let renamed = serde_json::json!({
+            "data": { "items": [{ "bvid": "BV1xx411c7mD" }] }
+        });
+        assert_eq!(search_drift(&renamed), vec!["data.result 不是数组"]);
+
+        let no_bvid = serde_json::json!({
+            "data": { "result": [{ "aid": 123 }] }
+        });
+        assert_eq!(search_drift(&no_bvid), vec!["result 条目全部缺少 bvid"]);
+    }
+
+    #[test]
+    fn song_url_drift_flags_non_string_cdns() {
+        let good = serde_json::json!({ "data": { "cdns": ["http://example.com/a.mp3"] } });
+        assert!(song_url_drift(&good).is_empty());
+
+        let changed = serde_json::json!({ "data": { "cdns": [{ "url": "x" }] } });
+        assert_eq!(song_url_drift(&changed), vec!["cdns 条目不是字符串"]);
+    }
+
+    #[test]
+    fn hmac_sha256_hex_matches_known_vector() {
+        assert_eq!(
+            hmac_sha256_hex(b"key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn cookie_header_only_carries_present_fields() {
+        let mut identity = PersistedIdentity {
+            buvid3: "AAAinfoc".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(identity.cookie_header(), "buvid3=AAAinfoc");
+        identity.bili_ticket = "ticket".to_string();
+        assert_eq!(
+            identity.cookie_header(),
+            "buvid3=AAAinfoc; bili_ticket=ticket"
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff_secs(1), 30);
+        assert_eq!(backoff_secs(2), 60);
+        assert_eq!(backoff_secs(3), 120);
+        assert_eq!(backoff_secs(10), BACKOFF_MAX_SECS);
+    }
+
+    #[test]
+    fn generated_buvid_has_browser_format() {
+        let buvid = generate_buvid();
+        assert_eq!(buvid.len(), 40);
+        assert!(buvid.ends_with("infoc"));
+    }
+
+    #[test]
+    fn search_cache_entry_expires_after_ttl() {
+        let entry = SearchCacheEntry {
+            fetched_at: 1_000,
+            items: Vec::new(),
+        };
+        assert!(entry.is_fresh(1_000 + SEARCH_CACHE_TTL_SECS - 1));
+        assert!(!entry.is_fresh(1_000 + SEARCH_CACHE_TTL_SECS));
+    }
+
+    #[test]
+    fn order_by_cdn_preference_moves_preferred_first_and_avoided_last() {
+        let mut urls = vec![
+            "http://mcdn.example.com/a.m4s".to_string(),
+            "http://other.example.com/a.m4s".to_string(),
+            "http://upos-hz.example.com/a.m4s".to_string(),
+        ];
+        let cdn = BilibiliCdnSettings {
+            preferred_patterns: vec!["upos".to_string()],
+            avoided_patterns: vec!["mcdn".to_string()],
+        };
+        order_by_cdn_preference(&mut urls, &cdn);
+        assert!(urls[0].contains("upos"));
+        assert!(urls[2].contains("mcdn"));
+    }
+}
@@ -9,6 +9,7 @@ use tokio::sync::RwLock;
 
 use crate::radio::api::RadioApi;
 use crate::radio::models::{CrawlProgress, Station};
+use crate::radio::province::ProvinceNormalizer;
 
 /// 电台爬虫
 pub struct Crawler {
@@ -20,8 +21,13 @@ pub struct Crawler {
 impl Crawler {
     /// 创建新的爬虫实例
     pub fn new(data_dir: PathBuf) -> Self {
+        let api = RadioApi::new();
+        // 设置里配置了云听 key 覆盖 / 镜像地址时立即生效
+        let settings = crate::settings::load_settings_from_file(&data_dir);
+        api.set_api_key_override(Some(settings.yunting_api_key));
+        api.set_mirror_urls(&settings.yunting_mirror_urls);
         Self {
-            api: RadioApi::new(),
+            api,
             data_dir,
             stations: Arc::new(RwLock::new(Vec::new())),
         }
@@ -95,6 +101,12 @@ impl Crawler {
 
         log::debug!("crawl completed: {}", all_stations.len());
 
+        // 归并"新疆兵团"这类伪省份，统计和 SII 分组不再出现重复分组
+        let changed = ProvinceNormalizer::load(&self.data_dir).apply(&mut all_stations);
+        if changed > 0 {
+            log::info!("省份归一化：{} 个电台已并入规范省份", changed);
+        }
+
         // 保存到缓存
         {
             let mut stations = self.stations.write().await;
@@ -117,12 +129,74 @@ impl Crawler {
         }
 
         let json = serde_json::to_string_pretty(stations)?;
-        std::fs::write(&path, json)?;
+        crate::utils::fs::write_atomic(&path, json)?;
 
         log::debug!("stations saved: {:?}", path);
         Ok(())
     }
 
+    /// 启动时校验 stations.json 完整性
+    ///
+    /// 文件损坏时将其改名移到一边，尝试从 backups/ 中最新的备份恢复，
+    /// 并返回一条描述信息供前端提示，而不是静默地以零电台启动。
+    /// 数据完好（或文件不存在）时返回 None。
+    pub fn check_and_repair_data(&self) -> Option<String> {
+        let path = self.data_dir.join("stations.json");
+        if !path.exists() {
+            return None;
+        }
+
+        let valid = std::fs::read_to_string(&path)
+            .map(|json| serde_json::from_str::<Vec<Station>>(&json).is_ok())
+            .unwrap_or(false);
+        if valid {
+            return None;
+        }
+
+        // 移走损坏文件，保留现场便于排查
+        let timestamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+        let aside = self
+            .data_dir
+            .join(format!("stations.json.corrupt-{}", timestamp));
+        if let Err(e) = std::fs::rename(&path, &aside) {
+            log::error!("移动损坏的 stations.json 失败: {}", e);
+        } else {
+            log::warn!("stations.json 已损坏，移动到 {:?}", aside);
+        }
+
+        // 尝试从最新备份恢复
+        if let Some(backup) = Self::newest_backup_file(&self.data_dir, "stations.json") {
+            if std::fs::copy(&backup, &path).is_ok() {
+                log::info!("已从备份恢复 stations.json: {:?}", backup);
+                return Some(format!(
+                    "电台数据文件已损坏，已从备份 {} 恢复",
+                    backup
+                        .parent()
+                        .and_then(|p| p.file_name())
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                ));
+            }
+        }
+
+        Some("电台数据文件已损坏且无可用备份，请重新爬取电台".to_string())
+    }
+
+    /// 在 backups/ 下查找包含指定文件的最新备份
+    fn newest_backup_file(data_dir: &std::path::Path, file_name: &str) -> Option<PathBuf> {
+        let backups_dir = data_dir.join("backups");
+        let mut candidates: Vec<PathBuf> = std::fs::read_dir(backups_dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().join(file_name))
+            .filter(|path| path.exists())
+            .collect();
+
+        // 备份目录按时间戳命名，字典序即时间序
+        candidates.sort();
+        candidates.pop()
+    }
+
     /// 从文件加载电台数据
     pub fn load_stations(&self) -> anyhow::Result<Vec<Station>> {
         let path = self.data_dir.join("stations.json");
@@ -133,7 +207,10 @@ impl Crawler {
         }
 
         let json = std::fs::read_to_string(&path)?;
-        let stations: Vec<Station> = serde_json::from_str(&json)?;
+        let mut stations: Vec<Station> = serde_json::from_str(&json)?;
+
+        // 老数据和新补充的规则都在加载时生效，不依赖重新爬取
+        ProvinceNormalizer::load(&self.data_dir).apply(&mut stations);
 
         log::debug!("stations loaded: {}", stations.len());
         Ok(stations)
@@ -161,6 +238,102 @@ impl Crawler {
     }
 }
 
+/// 各省份近似几何中心（经度、纬度），用于地图热力视图
+///
+/// 取省会城市坐标作为省份锚点，精度对热力图足够。
+/// 央广等非地理"省份"不在表内，对应条目坐标为空。
+const PROVINCE_CENTROIDS: [(&str, f64, f64); 31] = [
+    ("北京", 116.40, 39.90),
+    ("天津", 117.20, 39.13),
+    ("河北", 114.53, 38.04),
+    ("山西", 112.55, 37.87),
+    ("内蒙古", 111.77, 40.82),
+    ("辽宁", 123.43, 41.80),
+    ("吉林", 125.32, 43.90),
+    ("黑龙江", 126.66, 45.74),
+    ("上海", 121.47, 31.23),
+    ("江苏", 118.76, 32.04),
+    ("浙江", 120.15, 30.27),
+    ("安徽", 117.28, 31.86),
+    ("福建", 119.30, 26.08),
+    ("江西", 115.91, 28.67),
+    ("山东", 117.00, 36.65),
+    ("河南", 113.65, 34.76),
+    ("湖北", 114.31, 30.59),
+    ("湖南", 112.98, 28.19),
+    ("广东", 113.26, 23.13),
+    ("广西", 108.33, 22.82),
+    ("海南", 110.35, 20.02),
+    ("四川", 104.07, 30.67),
+    ("重庆", 106.55, 29.56),
+    ("贵州", 106.71, 26.57),
+    ("云南", 102.71, 25.04),
+    ("西藏", 91.11, 29.65),
+    ("陕西", 108.95, 34.27),
+    ("甘肃", 103.82, 36.06),
+    ("青海", 101.78, 36.62),
+    ("宁夏", 106.27, 38.47),
+    ("新疆", 87.62, 43.82),
+];
+
+/// 省份地理统计条目：电台数量加省份锚点坐标
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvinceGeoEntry {
+    pub province: String,
+    pub count: usize,
+    /// 经度，非地理省份（如央广）为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lng: Option<f64>,
+    /// 纬度，非地理省份（如央广）为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lat: Option<f64>,
+}
+
+/// 省份地理统计缓存：电台列表指纹 -> 上次计算结果
+static GEO_STATS_CACHE: std::sync::Mutex<Option<(String, Vec<ProvinceGeoEntry>)>> =
+    std::sync::Mutex::new(None);
+
+/// 统计各省份电台数量并关联省份坐标
+///
+/// 指纹只看电台 ID 与省份，列表未变时直接复用上次计算结果。
+pub fn get_province_geo_stats(stations: &[Station]) -> Vec<ProvinceGeoEntry> {
+    let mut digest = md5::Context::new();
+    for station in stations {
+        digest.consume(station.id.as_bytes());
+        digest.consume(station.province.as_bytes());
+    }
+    let fingerprint = format!("{:x}", digest.compute());
+
+    if let Ok(cache) = GEO_STATS_CACHE.lock() {
+        if let Some((cached_fingerprint, entries)) = cache.as_ref() {
+            if *cached_fingerprint == fingerprint {
+                return entries.clone();
+            }
+        }
+    }
+
+    let entries: Vec<ProvinceGeoEntry> = get_province_stats(stations)
+        .into_iter()
+        .map(|(province, count)| {
+            let centroid = PROVINCE_CENTROIDS
+                .iter()
+                .find(|(name, _, _)| *name == province);
+            ProvinceGeoEntry {
+                province,
+                count,
+                lng: centroid.map(|(_, lng, _)| *lng),
+                lat: centroid.map(|(_, _, lat)| *lat),
+            }
+        })
+        .collect();
+
+    if let Ok(mut cache) = GEO_STATS_CACHE.lock() {
+        *cache = Some((fingerprint, entries.clone()));
+    }
+    entries
+}
+
 /// 统计各省份电台数量
 pub fn get_province_stats(stations: &[Station]) -> Vec<(String, usize)> {
     use std::collections::HashMap;
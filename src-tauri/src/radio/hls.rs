@@ -0,0 +1,473 @@
+//! 纯 Rust 直通转发
+//!
+//! FFmpeg 不可用时的回退路径：
+//! - HLS 流用 reqwest 拉取分片，TS 分片用内置的最小解复用器
+//!   解出 ADTS-AAC 负载，ADTS 分片原样透传；
+//! - 上游已经是 MP3 icecast 流时整段代理。
+//!
+//! 不做任何转码；需要真正转码的电台仍依赖 FFmpeg。
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::diagnostics::DiagnosticLogger;
+
+/// 音频数据块发送端，与流媒体服务器的响应通道一致
+type StreamSender = tokio::sync::mpsc::Sender<Result<Vec<u8>, std::io::Error>>;
+
+/// 连续拉取播放列表失败多少次后放弃
+const MAX_PLAYLIST_FAILURES: u32 = 5;
+
+/// 已播放分片地址的记忆上限，防止直播列表滚动时重复播放
+const PLAYED_SEGMENT_MEMORY: usize = 64;
+
+/// 判断流地址是否可以走纯 Rust 直通（HLS 或裸 ADTS 流）
+pub fn can_relay_natively(stream_url: &str) -> bool {
+    let url = stream_url.split('?').next().unwrap_or(stream_url);
+    url.contains(".m3u8") || url.ends_with(".aac")
+}
+
+/// 判断流地址是否为可直通代理的 MP3 流
+pub fn is_mp3_url(stream_url: &str) -> bool {
+    let url = stream_url.split('?').next().unwrap_or(stream_url);
+    url.ends_with(".mp3")
+}
+
+/// 直通代理 MP3 icecast 流
+///
+/// 上游已经是 MP3 时完全不经过 FFmpeg，连接后用 Content-Type
+/// 再次确认，不是 MPEG 音频就放弃并记录诊断日志。
+pub async fn relay_mp3_passthrough(
+    stream_url: String,
+    tx: StreamSender,
+    logger: DiagnosticLogger,
+    station_id: String,
+    station_name: String,
+) {
+    use tokio_stream::StreamExt;
+
+    // 长连接流，只限制连接超时，不限制整体超时
+    let client = match reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(10))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            logger.push(
+                "error",
+                "hls",
+                "创建 HTTP 客户端失败",
+                Some(station_id),
+                Some(station_name),
+                Some(e.to_string()),
+            );
+            return;
+        }
+    };
+
+    let resp = match client
+        .get(&stream_url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+    {
+        Ok(resp) => resp,
+        Err(e) => {
+            logger.push(
+                "error",
+                "hls",
+                "连接 MP3 上游失败",
+                Some(station_id),
+                Some(station_name),
+                Some(e.to_string()),
+            );
+            return;
+        }
+    };
+
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    if !content_type.contains("mpeg") && !content_type.contains("mp3") && !is_mp3_url(&stream_url)
+    {
+        logger.push(
+            "error",
+            "hls",
+            "上游不是 MPEG 音频流，放弃直通",
+            Some(station_id),
+            Some(station_name),
+            Some(format!("Content-Type: {}", content_type)),
+        );
+        return;
+    }
+
+    // 直通也过一遍帧过滤，确保输出从 MP3 帧边界开始且不含残帧
+    let mut frame_filter = crate::radio::mp3::FrameFilter::new();
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(bytes) => {
+                let frames = frame_filter.push(&bytes);
+                if frames.is_empty() {
+                    continue;
+                }
+                if tx.send(Ok(frames)).await.is_err() {
+                    return; // 客户端已断开
+                }
+            }
+            Err(e) => {
+                logger.push(
+                    "warn",
+                    "hls",
+                    "MP3 上游连接中断",
+                    Some(station_id),
+                    Some(station_name),
+                    Some(e.to_string()),
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// 从 FFmpeg 缺席的环境里直通转发 HLS 流
+///
+/// 持续轮询媒体播放列表，按顺序下载新分片并把音频负载写入 `tx`，
+/// 客户端断开或上游持续失败时结束。
+pub async fn relay_hls_native(
+    stream_url: String,
+    tx: StreamSender,
+    logger: DiagnosticLogger,
+    station_id: String,
+    station_name: String,
+) {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            logger.push(
+                "error",
+                "hls",
+                "创建 HTTP 客户端失败",
+                Some(station_id),
+                Some(station_name),
+                Some(e.to_string()),
+            );
+            return;
+        }
+    };
+
+    let mut playlist_url = stream_url;
+    let mut played: VecDeque<String> = VecDeque::new();
+    let mut demuxer = TsAacDemuxer::new();
+    let mut failures = 0u32;
+
+    loop {
+        if tx.is_closed() {
+            return;
+        }
+
+        let text = match fetch_text(&client, &playlist_url).await {
+            Ok(text) => {
+                failures = 0;
+                text
+            }
+            Err(e) => {
+                failures += 1;
+                logger.push(
+                    "warn",
+                    "hls",
+                    format!("拉取播放列表失败（第 {} 次）", failures),
+                    Some(station_id.clone()),
+                    Some(station_name.clone()),
+                    Some(e),
+                );
+                if failures >= MAX_PLAYLIST_FAILURES {
+                    logger.push(
+                        "error",
+                        "hls",
+                        "播放列表连续拉取失败，结束直通转发",
+                        Some(station_id),
+                        Some(station_name),
+                        None::<String>,
+                    );
+                    return;
+                }
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        };
+
+        // 主播放列表：取第一个变体继续
+        if text.contains("#EXT-X-STREAM-INF") {
+            match parse_segment_urls(&playlist_url, &text).into_iter().next() {
+                Some(variant) => {
+                    playlist_url = variant;
+                    continue;
+                }
+                None => {
+                    logger.push(
+                        "error",
+                        "hls",
+                        "主播放列表中没有可用变体",
+                        Some(station_id),
+                        Some(station_name),
+                        None::<String>,
+                    );
+                    return;
+                }
+            }
+        }
+
+        let target_duration = parse_target_duration(&text);
+        let mut fetched_any = false;
+
+        for segment_url in parse_segment_urls(&playlist_url, &text) {
+            if played.contains(&segment_url) {
+                continue;
+            }
+
+            match fetch_bytes(&client, &segment_url).await {
+                Ok(bytes) => {
+                    let mut out = Vec::new();
+                    if bytes.first() == Some(&0x47) {
+                        demuxer.demux(&bytes, &mut out);
+                    } else {
+                        // ADTS 分片原样透传
+                        out.extend_from_slice(&bytes);
+                    }
+                    if !out.is_empty() && tx.send(Ok(out)).await.is_err() {
+                        return; // 客户端已断开
+                    }
+                }
+                Err(e) => {
+                    logger.push(
+                        "warn",
+                        "hls",
+                        "拉取分片失败，跳过",
+                        Some(station_id.clone()),
+                        Some(station_name.clone()),
+                        Some(e),
+                    );
+                }
+            }
+
+            played.push_back(segment_url);
+            if played.len() > PLAYED_SEGMENT_MEMORY {
+                played.pop_front();
+            }
+            fetched_any = true;
+        }
+
+        // 没有新分片时快速重试，否则按分片时长的一半轮询
+        let sleep_secs = if fetched_any {
+            (target_duration / 2).max(1)
+        } else {
+            1
+        };
+        tokio::time::sleep(Duration::from_secs(sleep_secs)).await;
+    }
+}
+
+/// 拉取文本资源
+async fn fetch_text(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 拉取二进制资源
+async fn fetch_bytes(client: &reqwest::Client, url: &str) -> Result<Vec<u8>, String> {
+    Ok(client
+        .get(url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|e| e.to_string())?
+        .bytes()
+        .await
+        .map_err(|e| e.to_string())?
+        .to_vec())
+}
+
+/// 解析播放列表中的分片地址，相对地址按播放列表地址补全
+fn parse_segment_urls(playlist_url: &str, text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| resolve_url(playlist_url, line))
+        .collect()
+}
+
+/// 解析 #EXT-X-TARGETDURATION，缺失时按 5 秒处理
+fn parse_target_duration(text: &str) -> u64 {
+    text.lines()
+        .find_map(|line| line.strip_prefix("#EXT-X-TARGETDURATION:"))
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(5)
+}
+
+/// 把相对地址解析为绝对地址
+fn resolve_url(base: &str, relative: &str) -> String {
+    if relative.starts_with("http://") || relative.starts_with("https://") {
+        return relative.to_string();
+    }
+
+    if let Some(stripped) = relative.strip_prefix('/') {
+        // 以 / 开头：相对站点根目录
+        if let Some(scheme_end) = base.find("://") {
+            let host_end = base[scheme_end + 3..]
+                .find('/')
+                .map(|pos| scheme_end + 3 + pos)
+                .unwrap_or(base.len());
+            return format!("{}/{}", &base[..host_end], stripped);
+        }
+    }
+
+    match base.rfind('/') {
+        Some(pos) => format!("{}/{}", &base[..pos], relative),
+        None => relative.to_string(),
+    }
+}
+
+/// 最小 MPEG-TS 音频解复用器
+///
+/// 只处理一个节目：从 PAT 找到 PMT，再从 PMT 找到 ADTS-AAC
+/// 音频流的 PID，把该 PID 的 PES 负载拼接成连续的 ADTS 流。
+struct TsAacDemuxer {
+    pmt_pid: Option<u16>,
+    audio_pid: Option<u16>,
+}
+
+impl TsAacDemuxer {
+    fn new() -> Self {
+        Self {
+            pmt_pid: None,
+            audio_pid: None,
+        }
+    }
+
+    /// 解出一段 TS 数据中的 ADTS 音频负载
+    fn demux(&mut self, data: &[u8], out: &mut Vec<u8>) {
+        for packet in data.chunks_exact(188) {
+            self.demux_packet(packet, out);
+        }
+    }
+
+    fn demux_packet(&mut self, packet: &[u8], out: &mut Vec<u8>) {
+        if packet[0] != 0x47 {
+            return; // 同步字节丢失，跳过
+        }
+        let payload_unit_start = packet[1] & 0x40 != 0;
+        let pid = ((packet[1] as u16 & 0x1F) << 8) | packet[2] as u16;
+        let adaptation_control = (packet[3] >> 4) & 0x03;
+
+        let mut offset = 4;
+        if adaptation_control == 0x02 {
+            return; // 只有适配字段，没有负载
+        }
+        if adaptation_control == 0x03 {
+            offset += 1 + packet[4] as usize;
+        }
+        if offset >= packet.len() {
+            return;
+        }
+        let payload = &packet[offset..];
+
+        if pid == 0 {
+            self.parse_pat(payload, payload_unit_start);
+        } else if Some(pid) == self.pmt_pid {
+            self.parse_pmt(payload, payload_unit_start);
+        } else if Some(pid) == self.audio_pid {
+            Self::append_pes_payload(payload, payload_unit_start, out);
+        }
+    }
+
+    /// 跳过 PSI 表前的 pointer_field，返回表体
+    fn table_body(payload: &[u8], payload_unit_start: bool) -> Option<&[u8]> {
+        if !payload_unit_start {
+            return None; // 假定表在单个 TS 包内完整
+        }
+        let pointer = *payload.first()? as usize;
+        payload.get(1 + pointer..)
+    }
+
+    fn parse_pat(&mut self, payload: &[u8], payload_unit_start: bool) {
+        let Some(table) = Self::table_body(payload, payload_unit_start) else {
+            return;
+        };
+        if table.len() < 12 || table[0] != 0x00 {
+            return;
+        }
+
+        let section_length = (((table[1] & 0x0F) as usize) << 8) | table[2] as usize;
+        let end = (3 + section_length).saturating_sub(4).min(table.len()); // 去掉 CRC
+
+        // 节目列表从表体第 8 字节开始，每项 4 字节
+        let mut pos = 8;
+        while pos + 4 <= end {
+            let program = ((table[pos] as u16) << 8) | table[pos + 1] as u16;
+            let pid = ((table[pos + 2] as u16 & 0x1F) << 8) | table[pos + 3] as u16;
+            if program != 0 {
+                self.pmt_pid = Some(pid);
+                return;
+            }
+            pos += 4;
+        }
+    }
+
+    fn parse_pmt(&mut self, payload: &[u8], payload_unit_start: bool) {
+        let Some(table) = Self::table_body(payload, payload_unit_start) else {
+            return;
+        };
+        if table.len() < 12 || table[0] != 0x02 {
+            return;
+        }
+
+        let section_length = (((table[1] & 0x0F) as usize) << 8) | table[2] as usize;
+        let end = (3 + section_length).saturating_sub(4).min(table.len());
+        let program_info_length = (((table[10] & 0x0F) as usize) << 8) | table[11] as usize;
+
+        let mut pos = 12 + program_info_length;
+        while pos + 5 <= end {
+            let stream_type = table[pos];
+            let pid = ((table[pos + 1] as u16 & 0x1F) << 8) | table[pos + 2] as u16;
+            let es_info_length = (((table[pos + 3] & 0x0F) as usize) << 8) | table[pos + 4] as usize;
+
+            // 0x0F = ISO/IEC 13818-7 音频（ADTS AAC）
+            if stream_type == 0x0F {
+                self.audio_pid = Some(pid);
+                return;
+            }
+            pos += 5 + es_info_length;
+        }
+    }
+
+    /// 追加 PES 负载，包起始处跳过 PES 头
+    fn append_pes_payload(payload: &[u8], payload_unit_start: bool, out: &mut Vec<u8>) {
+        if !payload_unit_start {
+            out.extend_from_slice(payload);
+            return;
+        }
+
+        // PES 头：起始码(3) + 流 ID(1) + 包长(2) + 标志(2) + 头长度(1)
+        if payload.len() < 9 || payload[0] != 0 || payload[1] != 0 || payload[2] != 1 {
+            return;
+        }
+        let header_length = payload[8] as usize;
+        if let Some(data) = payload.get(9 + header_length..) {
+            out.extend_from_slice(data);
+        }
+    }
+}
@@ -1,12 +1,16 @@
 //! 电台核心功能模块
 
 pub mod api;
+pub mod bilibili;
 pub mod crawler;
+pub mod hls;
 pub mod models;
+pub mod mp3;
+pub mod province;
 pub mod sii;
 pub mod stream;
 
-pub use crawler::{get_province_stats, Crawler};
+pub use crawler::{get_province_geo_stats, get_province_stats, Crawler};
 pub use models::*;
 pub use sii::SiiGenerator;
-pub use stream::StreamServer;
+pub use stream::{load_runtime_state, StreamServer};
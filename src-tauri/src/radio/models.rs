@@ -2,6 +2,15 @@
 
 use serde::{Deserialize, Serialize};
 
+/// 电台 ID 命名空间前缀
+///
+/// 历史原因，爬取的云听电台直接用裸 content_id 作为 ID；
+/// 云听以外的来源必须带前缀，保证永远不会与 content_id 冲突。
+pub const ID_PREFIX_YUNTING: &str = "yt:";
+pub const ID_PREFIX_BILIBILI: &str = "bili:";
+pub const ID_PREFIX_CUSTOM: &str = "custom:";
+pub const ID_PREFIX_AUDIOBOOK: &str = "book:";
+
 /// 电台信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Station {
@@ -29,6 +38,15 @@ pub struct Station {
     /// 是否为用户自定义电台
     #[serde(default)]
     pub is_custom: bool,
+    /// 码率（kbps），None 时使用全局转码码率
+    #[serde(default)]
+    pub bitrate: Option<u32>,
+    /// 广播语言代码（如 BO/UG/MN），None 表示普通话，SII 中记为 CN
+    #[serde(default)]
+    pub language: Option<String>,
+    /// 流地址中签名的过期时间（Unix 秒），无签名或无法识别时为 None
+    #[serde(default)]
+    pub url_expires_at: Option<i64>,
 }
 
 impl Station {
@@ -39,6 +57,69 @@ impl Station {
             .or(self.mp3_play_url_low.as_deref())
             .or(self.play_url_low.as_deref())
     }
+
+    /// 根据电台名称识别少数民族语言广播
+    ///
+    /// 识别不到时返回 None（按普通话处理）。
+    pub fn detect_language(name: &str) -> Option<String> {
+        let language_map = [
+            ("藏语", "BO"),
+            ("维吾尔语", "UG"),
+            ("维语", "UG"),
+            ("蒙古语", "MN"),
+            ("蒙语", "MN"),
+            ("哈萨克语", "KK"),
+            ("朝鲜语", "KO"),
+        ];
+
+        language_map
+            .iter()
+            .find(|(keyword, _)| name.contains(keyword))
+            .map(|(_, code)| code.to_string())
+    }
+
+    /// 从流地址的查询参数中解析签名过期时间
+    ///
+    /// 支持常见 CDN 风格：expires=/e=/exp=（十进制 Unix 秒）、
+    /// wsTime=（十六进制秒）、auth_key=时间戳-...（阿里云鉴权）。
+    /// 无法识别时返回 None，按不过期处理。
+    pub fn parse_url_expiry(url: &str) -> Option<i64> {
+        let query = url.split('?').nth(1)?;
+        for pair in query.split('&') {
+            let mut parts = pair.splitn(2, '=');
+            let key = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("");
+
+            let candidate = match key {
+                "expires" | "Expires" | "e" | "exp" => value.parse::<i64>().ok(),
+                "wsTime" | "wstime" => i64::from_str_radix(value, 16).ok(),
+                "auth_key" => value.split('-').next().and_then(|ts| ts.parse().ok()),
+                _ => None,
+            };
+            if let Some(ts) = candidate {
+                // 只接受看起来像 Unix 秒的时间戳，避免误判普通数字参数
+                if (1_000_000_000..4_000_000_000).contains(&ts) {
+                    return Some(ts);
+                }
+            }
+        }
+        None
+    }
+
+    /// 去掉命名空间前缀后的原始 ID（云听电台即 content_id）
+    pub fn raw_id(&self) -> &str {
+        for prefix in [
+            ID_PREFIX_YUNTING,
+            ID_PREFIX_BILIBILI,
+            ID_PREFIX_CUSTOM,
+            ID_PREFIX_AUDIOBOOK,
+        ] {
+            if let Some(raw) = self.id.strip_prefix(prefix) {
+                return raw;
+            }
+        }
+        &self.id
+    }
 }
 
 /// 省份信息
@@ -122,6 +203,15 @@ pub struct RawStation {
 impl RawStation {
     /// 转换为 Station 结构
     pub fn into_station(self, province: &str) -> Station {
+        let language = Station::detect_language(&self.title);
+        let url_expires_at = [
+            &self.mp3_play_url_high,
+            &self.mp3_play_url_low,
+            &self.play_url_low,
+        ]
+        .into_iter()
+        .flatten()
+        .find_map(|url| Station::parse_url_expiry(url));
         Station {
             id: self.content_id,
             name: self.title,
@@ -132,6 +222,9 @@ impl RawStation {
             mp3_play_url_low: self.mp3_play_url_low,
             mp3_play_url_high: self.mp3_play_url_high,
             is_custom: false,
+            bitrate: None,
+            language,
+            url_expires_at,
         }
     }
 }
@@ -143,6 +236,35 @@ pub struct ServerStatus {
     pub port: u16,
     pub active_streams: usize,
     pub total_stations: usize,
+    /// 流启动耗时中位数（毫秒），尚无播放记录时为 None
+    #[serde(default)]
+    pub startup_latency_p50_ms: Option<u64>,
+    /// 流启动耗时 95 分位（毫秒）
+    #[serde(default)]
+    pub startup_latency_p95_ms: Option<u64>,
+}
+
+/// 服务器事件，通过 `/api/events` SSE 推送给外部集成
+///
+/// 面向直播 overlay、家庭自动化等轻量订阅场景，字段刻意保持最小。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "event")]
+pub enum ServerEvent {
+    /// 有客户端开始播放某个电台
+    StreamStarted {
+        station_id: String,
+        station_name: String,
+    },
+    /// 某个电台的播放流已结束
+    StreamStopped {
+        station_id: String,
+        station_name: String,
+    },
+    /// 电台元数据已更新（如流地址签名刷新）
+    MetadataUpdated {
+        station_id: String,
+        station_name: String,
+    },
 }
 
 /// 爬虫进度
@@ -153,3 +275,27 @@ pub struct CrawlProgress {
     pub province: String,
     pub stations_found: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_url_expiry_recognizes_common_styles() {
+        assert_eq!(
+            Station::parse_url_expiry("http://a.cn/live.m3u8?expires=1735689600&sign=x"),
+            Some(1735689600)
+        );
+        assert_eq!(
+            Station::parse_url_expiry("http://a.cn/live.m3u8?auth_key=1735689600-0-0-abc"),
+            Some(1735689600)
+        );
+        assert_eq!(
+            Station::parse_url_expiry("http://a.cn/live.m3u8?wsTime=67748700"),
+            Some(0x67748700)
+        );
+        // 普通数字参数不应被误判
+        assert_eq!(Station::parse_url_expiry("http://a.cn/live.m3u8?bitrate=128"), None);
+        assert_eq!(Station::parse_url_expiry("http://a.cn/live.m3u8"), None);
+    }
+}
@@ -0,0 +1,261 @@
+//! MP3 帧工具
+//!
+//! 解析 MPEG 音频（Layer III）帧头，用于播放自检和损坏数据过滤。
+//! 只做帧边界和长度校验，不解码音频内容。
+
+/// 解析出的 MP3 帧信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameInfo {
+    /// 码率（kbps）
+    pub bitrate_kbps: u32,
+    /// 采样率（Hz）
+    pub sample_rate: u32,
+    /// 整帧长度（字节，含帧头）
+    pub frame_len: usize,
+}
+
+/// MPEG1 Layer III 码率表（kbps）
+const BITRATES_V1: [u32; 16] = [
+    0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    0,
+];
+/// MPEG2/2.5 Layer III 码率表（kbps）
+const BITRATES_V2: [u32; 16] = [
+    0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0,
+];
+/// MPEG1 采样率表（Hz），MPEG2 减半、MPEG2.5 取四分之一
+const SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+/// 解析缓冲开头的 MP3 帧头，无效时返回 None
+pub fn parse_frame_header(data: &[u8]) -> Option<FrameInfo> {
+    if data.len() < 4 {
+        return None;
+    }
+    // 11 位同步字
+    if data[0] != 0xFF || data[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+
+    let version_bits = (data[1] >> 3) & 0x03; // 0=2.5, 2=2, 3=1
+    let layer_bits = (data[1] >> 1) & 0x03; // 1=Layer III
+    if version_bits == 1 || layer_bits != 1 {
+        return None;
+    }
+
+    let bitrate_index = ((data[2] >> 4) & 0x0F) as usize;
+    let sample_rate_index = ((data[2] >> 2) & 0x03) as usize;
+    let padding = ((data[2] >> 1) & 0x01) as usize;
+    if sample_rate_index == 3 {
+        return None;
+    }
+
+    let is_v1 = version_bits == 3;
+    let bitrate_kbps = if is_v1 {
+        BITRATES_V1[bitrate_index]
+    } else {
+        BITRATES_V2[bitrate_index]
+    };
+    if bitrate_kbps == 0 {
+        return None;
+    }
+
+    let sample_rate = match version_bits {
+        3 => SAMPLE_RATES[sample_rate_index],
+        2 => SAMPLE_RATES[sample_rate_index] / 2,
+        _ => SAMPLE_RATES[sample_rate_index] / 4,
+    };
+
+    // Layer III 帧长公式：V1 每帧 1152 采样，V2/2.5 每帧 576 采样
+    let coefficient = if is_v1 { 144 } else { 72 };
+    let frame_len = (coefficient * bitrate_kbps as usize * 1000) / sample_rate as usize + padding;
+    if frame_len < 24 {
+        return None;
+    }
+
+    Some(FrameInfo {
+        bitrate_kbps,
+        sample_rate,
+        frame_len,
+    })
+}
+
+/// 在缓冲中寻找第一个可信的帧边界
+///
+/// 单字节 0xFF 在音频数据里很常见，要求当前帧之后紧跟
+/// 另一个有效帧头（或恰好到缓冲末尾）才算可信。
+pub fn find_frame_start(data: &[u8]) -> Option<usize> {
+    for offset in 0..data.len().saturating_sub(4) {
+        if let Some(frame) = parse_frame_header(&data[offset..]) {
+            let next = offset + frame.frame_len;
+            if next >= data.len() || parse_frame_header(&data[next..]).is_some() {
+                return Some(offset);
+            }
+        }
+    }
+    None
+}
+
+/// 统计缓冲内完整有效帧数（从第一个可信帧边界开始）
+pub fn count_valid_frames(data: &[u8]) -> usize {
+    let Some(start) = find_frame_start(data) else {
+        return 0;
+    };
+
+    let mut count = 0;
+    let mut offset = start;
+    while let Some(frame) = parse_frame_header(&data[offset..]) {
+        if offset + frame.frame_len > data.len() {
+            break;
+        }
+        count += 1;
+        offset += frame.frame_len;
+    }
+    count
+}
+
+/// 失步时缓冲的上限，超出后只保留尾部继续找边界
+const MAX_DESYNC_BUFFER: usize = 16 * 1024;
+/// 单帧长度上限（V1 320kbps/32kHz 约 1441 字节），留余量
+const MAX_FRAME_LEN: usize = 2048;
+
+/// 流式 MP3 帧过滤器
+///
+/// 累积输入字节，只放行完整有效帧：启动或失步后丢弃数据，
+/// 直到找到可信帧边界，保证客户端输出总是从帧边界开始、
+/// 且不含残帧和垃圾数据。
+pub struct FrameFilter {
+    buffer: Vec<u8>,
+    /// 当前是否已对上帧边界
+    synced: bool,
+    /// 累计丢弃的字节数
+    dropped_bytes: u64,
+}
+
+impl FrameFilter {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            synced: false,
+            dropped_bytes: 0,
+        }
+    }
+
+    /// 喂入一段数据，返回可以安全输出的完整帧字节
+    pub fn push(&mut self, data: &[u8]) -> Vec<u8> {
+        self.buffer.extend_from_slice(data);
+        let mut out = Vec::new();
+
+        loop {
+            if !self.synced {
+                match find_frame_start(&self.buffer) {
+                    Some(start) => {
+                        self.dropped_bytes += start as u64;
+                        self.buffer.drain(..start);
+                        self.synced = true;
+                    }
+                    None => {
+                        // 找不到边界时只保留尾部，防止损坏数据把缓冲撑大
+                        if self.buffer.len() > MAX_DESYNC_BUFFER {
+                            let drop = self.buffer.len() - MAX_FRAME_LEN;
+                            self.dropped_bytes += drop as u64;
+                            self.buffer.drain(..drop);
+                        }
+                        break;
+                    }
+                }
+            }
+
+            match parse_frame_header(&self.buffer) {
+                Some(frame) if self.buffer.len() >= frame.frame_len => {
+                    out.extend(self.buffer.drain(..frame.frame_len));
+                }
+                Some(_) => break, // 帧未到齐，等下一段数据
+                None => self.synced = false, // 失步，回到找边界
+            }
+        }
+        out
+    }
+
+    /// 累计丢弃的字节数
+    pub fn dropped_bytes(&self) -> u64 {
+        self.dropped_bytes
+    }
+}
+
+impl Default for FrameFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造一个 MPEG1 Layer III 128kbps/44.1kHz 的空帧
+    fn fake_frame() -> Vec<u8> {
+        // 0xFF 0xFB: V1 L3 无 CRC；0x90: 128kbps + 44100Hz 无 padding
+        let header = [0xFF, 0xFB, 0x90, 0x00];
+        let info = parse_frame_header(&header).unwrap();
+        let mut frame = vec![0u8; info.frame_len];
+        frame[..4].copy_from_slice(&header);
+        frame
+    }
+
+    #[test]
+    fn parse_frame_header_reads_v1_layer3() {
+        let info = parse_frame_header(&[0xFF, 0xFB, 0x90, 0x00]).unwrap();
+        assert_eq!(info.bitrate_kbps, 128);
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.frame_len, 417);
+    }
+
+    #[test]
+    fn parse_frame_header_rejects_garbage() {
+        assert!(parse_frame_header(&[0x00, 0x11, 0x22, 0x33]).is_none());
+        // 同步字对但码率索引无效
+        assert!(parse_frame_header(&[0xFF, 0xFB, 0xF0, 0x00]).is_none());
+    }
+
+    #[test]
+    fn find_frame_start_skips_leading_garbage() {
+        let mut data = vec![0x12, 0x34, 0xFF, 0x00];
+        data.extend(fake_frame());
+        data.extend(fake_frame());
+        assert_eq!(find_frame_start(&data), Some(4));
+    }
+
+    #[test]
+    fn frame_filter_drops_garbage_and_outputs_whole_frames() {
+        let mut filter = FrameFilter::new();
+        let mut input = vec![0x00, 0xFF, 0x42]; // 开头垃圾
+        input.extend(fake_frame());
+        input.extend(fake_frame());
+
+        let out = filter.push(&input);
+        assert_eq!(out.len(), 417 * 2);
+        assert_eq!(&out[..4], &[0xFF, 0xFB, 0x90, 0x00]);
+        assert_eq!(filter.dropped_bytes(), 3);
+    }
+
+    #[test]
+    fn frame_filter_holds_partial_frame_until_complete() {
+        let mut filter = FrameFilter::new();
+        let frames: Vec<u8> = [fake_frame(), fake_frame()].concat();
+
+        // 分两段喂入，第一段在第二帧中间截断
+        let out1 = filter.push(&frames[..500]);
+        assert_eq!(out1.len(), 417);
+        let out2 = filter.push(&frames[500..]);
+        assert_eq!(out2.len(), 417);
+        assert_eq!(filter.dropped_bytes(), 0);
+    }
+
+    #[test]
+    fn count_valid_frames_counts_whole_frames_only() {
+        let mut data = fake_frame();
+        data.extend(fake_frame());
+        data.extend(&fake_frame()[..100]); // 尾部残帧不计
+        assert_eq!(count_valid_frames(&data), 2);
+    }
+}
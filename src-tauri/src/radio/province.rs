@@ -0,0 +1,116 @@
+//! 省份归一化
+//!
+//! 云听的省份列表里混有"新疆兵团"这类伪省份和个别简称，
+//! 统计、筛选和 SII 分组会因此出现重复分组。这里把它们
+//! 归并到规范省份；规则内置一份默认表，并可用数据目录下的
+//! JSON 文件补充或覆盖，修正规则不需要发新版本。
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::radio::models::Station;
+
+/// 归一化规则文件名，格式为 `{"伪省份": "规范省份"}` 的平面映射
+pub const PROVINCE_ALIASES_FILE: &str = "province_aliases.json";
+
+/// 内置默认规则：伪省份 / 简称 -> 规范省份
+const DEFAULT_ALIASES: &[(&str, &str)] = &[
+    ("新疆兵团", "新疆"),
+    ("兵团", "新疆"),
+    ("内蒙", "内蒙古"),
+    ("延边", "吉林"),
+];
+
+/// 省份归一化器
+pub struct ProvinceNormalizer {
+    /// 伪省份 -> 规范省份
+    aliases: HashMap<String, String>,
+}
+
+impl ProvinceNormalizer {
+    /// 加载归一化规则：内置默认表 + 数据目录 JSON 覆盖
+    ///
+    /// 文件不存在或损坏时只用内置规则；文件里的条目会覆盖同名默认规则。
+    pub fn load(data_dir: &Path) -> Self {
+        let mut aliases: HashMap<String, String> = DEFAULT_ALIASES
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect();
+
+        if let Ok(json) = std::fs::read_to_string(data_dir.join(PROVINCE_ALIASES_FILE)) {
+            match serde_json::from_str::<HashMap<String, String>>(&json) {
+                Ok(overrides) => aliases.extend(overrides),
+                Err(e) => log::warn!("省份归一化规则文件解析失败，使用内置规则: {}", e),
+            }
+        }
+
+        Self { aliases }
+    }
+
+    /// 省份的规范名称，没有规则时原样返回
+    pub fn canonical<'a>(&'a self, province: &'a str) -> &'a str {
+        self.aliases
+            .get(province)
+            .map(String::as_str)
+            .unwrap_or(province)
+    }
+
+    /// 就地归一化电台列表的省份，返回改动的电台数
+    pub fn apply(&self, stations: &mut [Station]) -> usize {
+        let mut changed = 0;
+        for station in stations {
+            let canonical = self.canonical(&station.province);
+            if canonical != station.province {
+                station.province = canonical.to_string();
+                changed += 1;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalizer(pairs: &[(&str, &str)]) -> ProvinceNormalizer {
+        ProvinceNormalizer {
+            aliases: pairs
+                .iter()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn canonical_maps_pseudo_provinces() {
+        let n = normalizer(&[("新疆兵团", "新疆")]);
+        assert_eq!(n.canonical("新疆兵团"), "新疆");
+        assert_eq!(n.canonical("广东"), "广东");
+    }
+
+    fn test_station(province: &str) -> Station {
+        Station {
+            id: "custom:test".to_string(),
+            name: "测试电台".to_string(),
+            subtitle: String::new(),
+            image: String::new(),
+            province: province.to_string(),
+            play_url_low: None,
+            mp3_play_url_low: None,
+            mp3_play_url_high: None,
+            is_custom: true,
+            bitrate: None,
+            language: None,
+            url_expires_at: None,
+        }
+    }
+
+    #[test]
+    fn apply_counts_only_changed_stations() {
+        let n = normalizer(&[("新疆兵团", "新疆")]);
+        let mut stations = vec![test_station("新疆兵团"), test_station("新疆")];
+        assert_eq!(n.apply(&mut stations), 1);
+        assert!(stations.iter().all(|s| s.province == "新疆"));
+    }
+}
@@ -3,12 +3,70 @@
 //! 生成欧卡2可用的 live_streams.sii 配置文件
 
 use crate::radio::models::Station;
+use crate::settings::SiiEncoding;
 use std::path::{Path, PathBuf};
 
+/// 固定置顶的央广主频率，按此顺序排列
+const PINNED_CENTRAL_STATIONS: [&str; 3] = ["中国之声", "经济之声", "音乐之声"];
+
+/// 机器可读元数据注释行的前缀
+pub const SII_METADATA_PREFIX: &str = "# ouka2-meta: ";
+
+/// 嵌在生成文件里的元数据块
+///
+/// 以单行 JSON 注释的形式写入 SII，应用据此判断游戏目录里的
+/// 文件是否出自本应用、端口或设置是否已经过期。
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SiiMetadata {
+    /// 生成时的应用版本
+    pub app_version: String,
+    /// 生成时间（Unix 秒）
+    pub generated_at: i64,
+    /// 文件中的电台条目数
+    pub station_count: usize,
+    /// 生成时指向的服务器主机
+    pub host: String,
+    /// 生成时指向的服务器端口
+    pub port: u16,
+    /// 生成时设置文件的指纹
+    pub settings_hash: String,
+}
+
+/// SII 中单个电台条目的预览信息
+///
+/// 与 `generate` 输出的 stream_data 行一一对应，字段即行内各段。
+/// 前端预览和外部工具读它就能拿到与生成文件完全一致的
+/// 排序、命名和下标，不必自己复刻生成器逻辑。
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiiEntry {
+    /// stream_data 数组下标
+    pub index: usize,
+    /// 电台 ID
+    pub id: String,
+    /// 写入 SII 的显示名（ASCII 严格模式下为转写名或序号兜底名）
+    pub name: String,
+    /// 指向本机转发服务器的流地址
+    pub stream_url: String,
+    /// 流派
+    pub genre: &'static str,
+    /// 语言代码
+    pub language: String,
+    /// 码率（kbps）
+    pub bitrate: u32,
+}
+
 /// SII 文件生成器
 pub struct SiiGenerator {
     server_host: String,
     server_port: u16,
+    pin_central_stations: bool,
+    default_bitrate: u32,
+    encoding: SiiEncoding,
+    settings_hash: String,
+    /// 各电台累计播放次数；Some 时按次数降序排列电台
+    play_counts: Option<std::collections::HashMap<String, u64>>,
 }
 
 impl SiiGenerator {
@@ -17,17 +75,204 @@ impl SiiGenerator {
         Self {
             server_host: host.to_string(),
             server_port: port,
+            pin_central_stations: true,
+            default_bitrate: 128,
+            encoding: SiiEncoding::default(),
+            settings_hash: String::new(),
+            play_counts: None,
+        }
+    }
+
+    /// 设置播放次数表，按"我最常听"降序排列电台
+    ///
+    /// 次数相同的电台保持原有相对顺序；央广置顶仍优先于此排序。
+    pub fn with_play_counts(mut self, counts: std::collections::HashMap<String, u64>) -> Self {
+        self.play_counts = Some(counts);
+        self
+    }
+
+    /// 设置写入元数据块的设置指纹
+    pub fn with_settings_hash(mut self, hash: String) -> Self {
+        self.settings_hash = hash;
+        self
+    }
+
+    /// 设置输出编码
+    pub fn with_encoding(mut self, encoding: SiiEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// 设置默认码率（kbps），电台没有自带码率时使用
+    pub fn with_default_bitrate(mut self, bitrate: u32) -> Self {
+        self.default_bitrate = bitrate;
+        self
+    }
+
+    /// 设置是否将央广主频率固定在列表顶部
+    pub fn with_pin_central_stations(mut self, pin: bool) -> Self {
+        self.pin_central_stations = pin;
+        self
+    }
+
+    /// 将央广主频率排到列表顶部，其余电台保持原有顺序
+    fn apply_central_pinning(stations: &[Station]) -> Vec<Station> {
+        let mut pinned: Vec<Station> = Vec::new();
+        let mut rest: Vec<Station> = Vec::new();
+
+        for name in PINNED_CENTRAL_STATIONS {
+            if let Some(station) = stations
+                .iter()
+                .find(|s| s.name == name || (s.province == "央广" && s.name.contains(name)))
+            {
+                if !pinned.iter().any(|p| p.id == station.id) {
+                    pinned.push(station.clone());
+                }
+            }
+        }
+
+        for station in stations {
+            if !pinned.iter().any(|p| p.id == station.id) {
+                rest.push(station.clone());
+            }
+        }
+
+        pinned.extend(rest);
+        pinned
+    }
+
+    /// 计算最终写入 SII 的条目列表（排序、命名、下标均为最终值）
+    ///
+    /// `generate` 据此渲染文件，外部预览也从这里取数，
+    /// 保证两边永远一致。
+    pub fn plan(&self, stations: &[Station]) -> Vec<SiiEntry> {
+        // 先按播放次数降序（如开启），再做央广置顶
+        let play_ordered;
+        let stations = if let Some(counts) = &self.play_counts {
+            let mut ordered = stations.to_vec();
+            ordered.sort_by_key(|s| std::cmp::Reverse(counts.get(&s.id).copied().unwrap_or(0)));
+            play_ordered = ordered;
+            &play_ordered[..]
+        } else {
+            stations
+        };
+
+        let pinned_order;
+        let stations = if self.pin_central_stations {
+            pinned_order = Self::apply_central_pinning(stations);
+            &pinned_order[..]
+        } else {
+            stations
+        };
+
+        let mut names: Vec<String> = stations
+            .iter()
+            .enumerate()
+            .map(|(index, station)| {
+                if self.encoding == SiiEncoding::AsciiStrict {
+                    // 转写结果仍可能带中文前缀（如省份名），兜底用序号名
+                    let english = Self::to_english_name(&station.name);
+                    if english.is_ascii() {
+                        english
+                    } else {
+                        format!("CN Radio {}", index + 1)
+                    }
+                } else {
+                    station.name.clone()
+                }
+            })
+            .collect();
+        Self::disambiguate_names(
+            &mut names,
+            stations,
+            self.encoding == SiiEncoding::AsciiStrict,
+        );
+
+        stations
+            .iter()
+            .zip(names)
+            .enumerate()
+            .map(|(index, (station, name))| SiiEntry {
+                index,
+                id: station.id.clone(),
+                name,
+                stream_url: format!(
+                    "http://{}:{}/stream/{}",
+                    self.server_host, self.server_port, station.id
+                ),
+                genre: Self::get_genre(station),
+                language: station.language.as_deref().unwrap_or("CN").to_string(),
+                bitrate: station.bitrate.unwrap_or(self.default_bitrate),
+            })
+            .collect()
+    }
+
+    /// 同名条目消歧
+    ///
+    /// 两个电台显示名相同时（如两省转写后都是"Traffic Radio"），
+    /// 先加省份前缀区分；省份相同、前缀会破坏 ASCII 约束或加完仍
+    /// 重复时，按出现顺序退回序号后缀，保证游戏内每个条目都能分辨。
+    fn disambiguate_names(names: &mut [String], stations: &[Station], ascii: bool) {
+        let mut counts: std::collections::HashMap<String, usize> =
+            std::collections::HashMap::new();
+        for name in names.iter() {
+            *counts.entry(name.clone()).or_insert(0) += 1;
+        }
+
+        for (name, station) in names.iter_mut().zip(stations) {
+            if counts.get(name.as_str()).copied().unwrap_or(0) < 2 || station.province.is_empty() {
+                continue;
+            }
+            let candidate = format!("{} {}", station.province, name);
+            if name.starts_with(&station.province) || (ascii && !candidate.is_ascii()) {
+                continue;
+            }
+            *name = candidate;
+        }
+
+        let mut seen: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        for name in names.iter_mut() {
+            let occurrence = seen.entry(name.clone()).or_insert(0);
+            *occurrence += 1;
+            if *occurrence > 1 {
+                let suffixed = format!("{} {}", name, occurrence);
+                *name = suffixed;
+            }
         }
     }
 
     /// 生成 SII 文件内容
     pub fn generate(&self, stations: &[Station]) -> String {
-        let mut content = format!(
-            r#"SiiNunit
+        let entries = self.plan(stations);
+        // 元数据行是纯 ASCII 的 JSON，ASCII 严格模式下也能安全嵌入
+        let meta_line = serde_json::to_string(&self.metadata(entries.len()))
+            .map(|json| format!("{}{}", SII_METADATA_PREFIX, json))
+            .unwrap_or_default();
+
+        // ASCII 严格模式下注释和电台名都不能含中文
+        let mut content = if self.encoding == SiiEncoding::AsciiStrict {
+            format!(
+                r#"SiiNunit
+{{
+# ETS2 China radio stations
+# Generated by ouka2-desktop at {}
+{}
+
+live_stream_def : .live_streams {{
+ stream_data: {}
+"#,
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                meta_line,
+                entries.len()
+            )
+        } else {
+            format!(
+                r#"SiiNunit
 {{
 # 欧卡2中国电台配置文件
 # 由 ouka2-desktop 自动生成
 # 生成时间: {}
+{}
 #
 # 使用说明:
 # 1. 确保本地转发服务器正在运行
@@ -38,23 +283,19 @@ impl SiiGenerator {
 live_stream_def : .live_streams {{
  stream_data: {}
 "#,
-            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-            stations.len()
-        );
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                meta_line,
+                entries.len()
+            )
+        };
 
         // 添加每个电台
-        for (index, station) in stations.iter().enumerate() {
-            let stream_url = format!(
-                "http://{}:{}/stream/{}",
-                self.server_host, self.server_port, station.id
-            );
-            let genre = self.get_genre(station);
-
-            // SII格式: stream_data[index]: "URL|Name|Genre|Language|Bitrate|Favorite"
-            // 欧卡2支持UTF-8编码的中文名称
+        // SII格式: stream_data[index]: "URL|Name|Genre|Language|Bitrate|Favorite"
+        // 欧卡2支持UTF-8编码的中文名称
+        for entry in &entries {
             content.push_str(&format!(
-                " stream_data[{}]: \"{}|{}|{}|CN|128|0\"\n",
-                index, stream_url, station.name, genre
+                " stream_data[{}]: \"{}|{}|{}|{}|{}|0\"\n",
+                entry.index, entry.stream_url, entry.name, entry.genre, entry.language, entry.bitrate
             ));
         }
 
@@ -62,14 +303,50 @@ live_stream_def : .live_streams {{
         content
     }
 
-    /// 保存到文件
+    /// 当前配置下的元数据块
+    fn metadata(&self, station_count: usize) -> SiiMetadata {
+        SiiMetadata {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: chrono::Utc::now().timestamp(),
+            station_count,
+            host: self.server_host.clone(),
+            port: self.server_port,
+            settings_hash: self.settings_hash.clone(),
+        }
+    }
+
+    /// 从 SII 文本解析元数据块；不是本应用生成的文件返回 None
+    pub fn parse_metadata(content: &str) -> Option<SiiMetadata> {
+        content
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .find_map(|line| line.strip_prefix(SII_METADATA_PREFIX))
+            .and_then(|json| serde_json::from_str(json).ok())
+    }
+
+    /// 保存到文件，按配置的输出编码写入
     pub fn save_to_file(&self, content: &str, path: &Path) -> anyhow::Result<()> {
         // 确保目录存在
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
-        std::fs::write(path, content)?;
+        match self.encoding {
+            SiiEncoding::Utf8 => crate::utils::fs::write_atomic(path, content)?,
+            SiiEncoding::Utf8Bom => {
+                let mut bytes = Vec::with_capacity(content.len() + 3);
+                bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+                bytes.extend_from_slice(content.as_bytes());
+                crate::utils::fs::write_atomic(path, bytes)?;
+            }
+            SiiEncoding::AsciiStrict => {
+                if let Some(bad) = content.chars().find(|c| !c.is_ascii()) {
+                    anyhow::bail!("ASCII 严格模式下内容仍含非 ASCII 字符: {:?}", bad);
+                }
+                crate::utils::fs::write_atomic(path, content)?;
+            }
+        }
+
         log::info!("配置文件已生成: {:?}", path);
         Ok(())
     }
@@ -105,6 +382,14 @@ live_stream_def : .live_streams {{
                     paths.push(ets2_dir);
                 }
             }
+
+            // 个别版本写在文档目录下
+            if let Some(docs_dir) = dirs::document_dir() {
+                let ets2_dir = docs_dir.join("Euro Truck Simulator 2");
+                if ets2_dir.exists() && !paths.contains(&ets2_dir) {
+                    paths.push(ets2_dir);
+                }
+            }
         }
 
         #[cfg(target_os = "windows")]
@@ -131,8 +416,8 @@ live_stream_def : .live_streams {{
 
         #[cfg(target_os = "linux")]
         {
-            // Linux: ~/.local/share/Euro Truck Simulator 2
             if let Some(home_dir) = dirs::home_dir() {
+                // Linux 原生版: ~/.local/share/Euro Truck Simulator 2
                 let ets2_dir = home_dir
                     .join(".local")
                     .join("share")
@@ -140,15 +425,44 @@ live_stream_def : .live_streams {{
                 if ets2_dir.exists() {
                     paths.push(ets2_dir);
                 }
+
+                // Proton 版：游戏文档在 compatdata 的 Wine 前缀里
+                // （227300 是 ETS2 的 Steam AppID），Steam 本身可能装在
+                // 几个不同位置，包括 Flatpak 沙箱内。
+                let steam_roots = [
+                    home_dir.join(".steam").join("steam"),
+                    home_dir.join(".local").join("share").join("Steam"),
+                    home_dir
+                        .join(".var")
+                        .join("app")
+                        .join("com.valvesoftware.Steam")
+                        .join(".local")
+                        .join("share")
+                        .join("Steam"),
+                ];
+                for root in steam_roots {
+                    let proton_dir = root
+                        .join("steamapps")
+                        .join("compatdata")
+                        .join("227300")
+                        .join("pfx")
+                        .join("drive_c")
+                        .join("users")
+                        .join("steamuser")
+                        .join("Documents")
+                        .join("Euro Truck Simulator 2");
+                    if proton_dir.exists() && !paths.contains(&proton_dir) {
+                        paths.push(proton_dir);
+                    }
+                }
             }
         }
 
         paths
     }
 
-    /// 将中文电台名称转换为英文（备用方法，当欧卡2不支持中文时使用）
-    #[allow(dead_code)]
-    fn to_english_name(&self, chinese_name: &str) -> String {
+    /// 将中文电台名称转换为英文（用于 ICY 元数据等不支持中文的场景）
+    pub fn to_english_name(chinese_name: &str) -> String {
         // 常见电台名称映射
         let name_map = [
             ("中国之声", "China Voice"),
@@ -221,8 +535,22 @@ live_stream_def : .live_streams {{
         }
     }
 
+    /// 流派的中文显示名
+    pub fn genre_display_name(genre: &str) -> &'static str {
+        match genre {
+            "news" => "新闻",
+            "music" => "音乐",
+            "traffic" => "交通",
+            "economy" => "财经",
+            "culture" => "文艺",
+            "sports" => "体育",
+            "entertainment" => "娱乐",
+            _ => "综合",
+        }
+    }
+
     /// 获取电台流派
-    fn get_genre(&self, station: &Station) -> &'static str {
+    pub fn get_genre(station: &Station) -> &'static str {
         let name = station.name.to_lowercase();
 
         if name.contains("新闻") || name.contains("之声") {
@@ -250,3 +578,104 @@ impl Default for SiiGenerator {
         Self::new("127.0.0.1", 3000)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_sii_carries_parseable_metadata() {
+        let generator =
+            SiiGenerator::new("127.0.0.1", 3456).with_settings_hash("abc123".to_string());
+        let content = generator.generate(&[]);
+
+        let meta = SiiGenerator::parse_metadata(&content).expect("应能解析出元数据块");
+        assert_eq!(meta.port, 3456);
+        assert_eq!(meta.settings_hash, "abc123");
+        assert_eq!(meta.station_count, 0);
+        assert_eq!(meta.app_version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn parse_metadata_rejects_foreign_files() {
+        assert!(SiiGenerator::parse_metadata("SiiNunit\n{\n}\n").is_none());
+    }
+
+    fn test_station(id: &str, name: &str) -> Station {
+        Station {
+            id: id.to_string(),
+            name: name.to_string(),
+            subtitle: String::new(),
+            image: String::new(),
+            province: "测试".to_string(),
+            play_url_low: None,
+            mp3_play_url_low: None,
+            mp3_play_url_high: None,
+            is_custom: false,
+            bitrate: None,
+            language: None,
+            url_expires_at: None,
+        }
+    }
+
+    #[test]
+    fn plan_matches_generated_lines() {
+        let generator = SiiGenerator::new("127.0.0.1", 3000).with_pin_central_stations(false);
+        let stations = [test_station("a", "Radio A"), test_station("b", "Radio B")];
+
+        let entries = generator.plan(&stations);
+        let content = generator.generate(&stations);
+
+        assert_eq!(entries.len(), 2);
+        for entry in &entries {
+            let line = format!(
+                " stream_data[{}]: \"{}|{}|{}|{}|{}|0\"",
+                entry.index, entry.stream_url, entry.name, entry.genre, entry.language,
+                entry.bitrate
+            );
+            assert!(content.contains(&line), "生成内容应包含条目行: {}", line);
+        }
+    }
+
+    #[test]
+    fn duplicate_names_get_province_prefix() {
+        let mut a = test_station("a", "交通广播");
+        a.province = "浙江".to_string();
+        let mut b = test_station("b", "交通广播");
+        b.province = "江苏".to_string();
+
+        let generator = SiiGenerator::new("127.0.0.1", 3000).with_pin_central_stations(false);
+        let entries = generator.plan(&[a, b]);
+
+        assert_eq!(entries[0].name, "浙江 交通广播");
+        assert_eq!(entries[1].name, "江苏 交通广播");
+    }
+
+    #[test]
+    fn duplicate_names_same_province_get_numeric_suffix() {
+        let a = test_station("a", "交通广播");
+        let b = test_station("b", "交通广播");
+
+        let generator = SiiGenerator::new("127.0.0.1", 3000).with_pin_central_stations(false);
+        let entries = generator.plan(&[a, b]);
+
+        assert_ne!(entries[0].name, entries[1].name);
+        assert!(entries[1].name.ends_with(" 2"));
+    }
+
+    #[test]
+    fn play_counts_order_most_played_first() {
+        let mut counts = std::collections::HashMap::new();
+        counts.insert("b".to_string(), 5u64);
+
+        let generator = SiiGenerator::new("127.0.0.1", 3000)
+            .with_pin_central_stations(false)
+            .with_play_counts(counts);
+        let content =
+            generator.generate(&[test_station("a", "Radio A"), test_station("b", "Radio B")]);
+
+        let pos_a = content.find("Radio A").expect("应包含 Radio A");
+        let pos_b = content.find("Radio B").expect("应包含 Radio B");
+        assert!(pos_b < pos_a, "播放次数多的电台应排在前面");
+    }
+}
@@ -6,11 +6,14 @@ use axum::{
     body::Body,
     extract::{Path, State},
     http::{header, StatusCode},
-    response::{IntoResponse, Response},
-    routing::get,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
     Router,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::{
@@ -25,16 +28,266 @@ use tower_http::cors::{Any, CorsLayer};
 
 use crate::diagnostics::DiagnosticLogger;
 use crate::radio::api::RadioApi;
-use crate::radio::models::{ServerStatus, Station};
+use crate::radio::bilibili::BilibiliApi;
+use crate::radio::models::{
+    CrawlProgress, ServerEvent, ServerStatus, Station, ID_PREFIX_AUDIOBOOK, ID_PREFIX_BILIBILI,
+};
+use crate::radio::sii::SiiGenerator;
+use crate::settings::{load_settings_from_file, AppSettings};
 
 static NEXT_STREAM_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
 
+/// 音频数据块的发送端，客户端断开后可以换成新客户端的发送端
+type StreamSender = tokio::sync::mpsc::Sender<Result<Vec<u8>, std::io::Error>>;
+
+/// 同时保温的转码流上限，超出时淘汰最早进入保温的电台
+const MAX_WARM_STREAMS: usize = 3;
+
+/// 流地址签名距过期不足该秒数时提前刷新
+const URL_REFRESH_LEAD_SECS: i64 = 120;
+
+/// B 站候选池空闲预刷新的周期（秒）
+const BILIBILI_PREFRESH_INTERVAL_SECS: u64 = 15 * 60;
+
+/// 每个预刷新周期最多解析多少个 B 站电台的音频地址
+const BILIBILI_PREFRESH_BATCH: usize = 3;
+
+/// 预解析出的 B 站音频地址在没有签名过期信息时按多久有效对待
+const BILIBILI_PREFRESH_URL_TTL_SECS: i64 = 1800;
+
+/// 每个电台保留的吞吐样本数（每秒一个，约两分钟）
+const STREAM_STATS_CAPACITY: usize = 120;
+
+/// 按 IP 限流的计数窗口长度
+const RATE_LIMIT_WINDOW: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// 共享输出发送给单个客户端的最长等待，超时视为客户端卡死
+const FANOUT_SEND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// 运行时状态文件名，记录最近一次的活动电台，供重启后恢复
+const RUNTIME_STATE_FILE: &str = "runtime_state.json";
+
+/// 各电台累计播放次数文件名，供"按播放次数排序"的 SII 生成使用
+const PLAY_COUNTS_FILE: &str = "play_counts.json";
+
+/// 定时插播虚拟频道的电台 ID
+pub const INTERRUPT_CHANNEL_ID: &str = "interrupt";
+
+/// 环游中国虚拟电台的电台 ID
+pub const TOUR_CHANNEL_ID: &str = "tour";
+
+/// 流派聚合虚拟电台的 ID 前缀（如 genre:traffic）
+pub const GENRE_CHANNEL_PREFIX: &str = "genre:";
+
+/// 环游中国的省份顺序（大致按东北到西南的地理路线）
+const TOUR_PROVINCE_ORDER: [&str; 31] = [
+    "黑龙江",
+    "吉林",
+    "辽宁",
+    "内蒙古",
+    "北京",
+    "河北",
+    "山西",
+    "山东",
+    "河南",
+    "江苏",
+    "上海",
+    "浙江",
+    "安徽",
+    "江西",
+    "福建",
+    "湖北",
+    "湖南",
+    "广东",
+    "广西",
+    "海南",
+    "贵州",
+    "云南",
+    "四川",
+    "重庆",
+    "陕西",
+    "宁夏",
+    "甘肃",
+    "青海",
+    "新疆",
+    "新疆兵团",
+    "西藏",
+];
+
+/// 持久化的运行时状态
+///
+/// 每次活动流变化时写盘；应用非正常退出后，
+/// 据此推断游戏端还在重试哪些电台地址。
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct RuntimeState {
+    /// 退出时仍在播放的电台 ID
+    active_station_ids: Vec<String>,
+}
+
+/// 读取上次退出时的活动电台 ID 列表，文件不存在或损坏时返回空
+pub fn load_runtime_state(data_dir: &std::path::Path) -> Vec<String> {
+    std::fs::read_to_string(data_dir.join(RUNTIME_STATE_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str::<RuntimeState>(&json).ok())
+        .map(|state| state.active_station_ids)
+        .unwrap_or_default()
+}
+
+/// 读取各电台的累计播放次数，文件不存在或损坏时返回空
+pub fn load_play_counts(data_dir: &std::path::Path) -> HashMap<String, u64> {
+    std::fs::read_to_string(data_dir.join(PLAY_COUNTS_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// 输出封装格式
+///
+/// 游戏走默认的 MP3；`/stream/:id.aac` 和 `/stream/:id.opus`
+/// 供外部播放器和低带宽远程收听使用。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputCodec {
+    Mp3,
+    AdtsAac,
+    OggOpus,
+}
+
+impl OutputCodec {
+    /// 从请求的电台 ID 后缀解析输出格式，返回（格式，去掉后缀的 ID）
+    fn from_request_id(id: &str) -> (Self, &str) {
+        if let Some(raw) = id.strip_suffix(".aac") {
+            (Self::AdtsAac, raw)
+        } else if let Some(raw) = id.strip_suffix(".opus") {
+            (Self::OggOpus, raw)
+        } else {
+            (Self::Mp3, id)
+        }
+    }
+
+    /// HTTP Content-Type
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Mp3 => "audio/mpeg",
+            Self::AdtsAac => "audio/aac",
+            Self::OggOpus => "audio/ogg",
+        }
+    }
+
+    /// 根据探测到的编码器能力选择实际可用的编码器
+    ///
+    /// MP3 优先 libmp3lame，缺失时回退到 libshine；
+    /// AAC/Opus 没有替代实现，缺失时返回 None，由调用方报错或回退。
+    fn preferred_encoder(
+        self,
+        caps: &crate::utils::ffmpeg::EncoderCapabilities,
+    ) -> Option<&'static str> {
+        match self {
+            Self::Mp3 => {
+                if caps.libmp3lame {
+                    Some("libmp3lame")
+                } else if caps.libshine {
+                    Some("libshine")
+                } else {
+                    None
+                }
+            }
+            Self::AdtsAac => caps.aac.then_some("aac"),
+            Self::OggOpus => caps.libopus.then_some("libopus"),
+        }
+    }
+
+    /// FFmpeg 输出封装格式名
+    fn ffmpeg_format(self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::AdtsAac => "adts",
+            Self::OggOpus => "ogg",
+        }
+    }
+
+    /// 输出采样率（Opus 只支持 48kHz 系列）
+    fn sample_rate(self) -> &'static str {
+        match self {
+            Self::OggOpus => "48000",
+            _ => "44100",
+        }
+    }
+}
+
+/// 单个电台的吞吐统计环
+///
+/// 按秒聚合输出字节数，固定容量，UI 据此画实时码率曲线。
+#[derive(Debug, Default)]
+struct ThroughputRing {
+    /// 每秒 kbps 样本，旧的在前
+    samples: std::collections::VecDeque<u32>,
+    /// 当前这一秒累计的字节数
+    current_bytes: u64,
+    /// 当前这一秒的 Unix 时间戳
+    current_second: i64,
+}
+
+impl ThroughputRing {
+    /// 记录一次输出，跨秒时把上一秒的样本落环
+    fn record(&mut self, bytes: usize, now_second: i64) {
+        if self.current_second == 0 {
+            self.current_second = now_second;
+        }
+        // 长时间没有输出时直接清空，避免逐秒补零
+        if now_second - self.current_second > STREAM_STATS_CAPACITY as i64 {
+            self.samples.clear();
+            self.current_bytes = 0;
+            self.current_second = now_second;
+        }
+        while self.current_second < now_second {
+            let kbps = (self.current_bytes * 8 / 1000) as u32;
+            self.samples.push_back(kbps);
+            while self.samples.len() > STREAM_STATS_CAPACITY {
+                self.samples.pop_front();
+            }
+            self.current_bytes = 0;
+            self.current_second += 1;
+        }
+        self.current_bytes += bytes as u64;
+    }
+}
+
 /// 单次播放请求对应的活动流信息。
 pub struct ActiveStream {
     pub station_id: String,
     pub process_id: u32,
 }
 
+/// 保温流的接入句柄
+///
+/// 新客户端通过 `adopt_tx` 把自己的发送端交给原读取任务，
+/// 免去重新解析地址和启动 FFmpeg，实现近乎瞬时的换台。
+struct WarmStream {
+    adopt_tx: tokio::sync::mpsc::Sender<StreamSender>,
+    /// 进入保温期的时间，用于容量淘汰
+    warmed_at: std::time::Instant,
+}
+
+/// 电台启动权守卫
+///
+/// 离开作用域时自动释放占用，保证任何提前返回的路径
+/// 都不会把该电台的后续播放请求卡死。
+struct SpawnClaim {
+    state: Arc<ServerState>,
+    station_id: String,
+}
+
+impl Drop for SpawnClaim {
+    fn drop(&mut self) {
+        let state = self.state.clone();
+        let station_id = std::mem::take(&mut self.station_id);
+        tokio::spawn(async move {
+            state.spawn_claims.write().await.remove(&station_id);
+        });
+    }
+}
+
 fn next_stream_request_id(station_id: &str) -> String {
     let id = NEXT_STREAM_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
     format!("{}-{}", station_id, id)
@@ -67,6 +320,42 @@ fn is_ffmpeg_noise_line(line: &str) -> bool {
         || lower.starts_with("muxing overhead")
 }
 
+/// 电平表事件名
+const AUDIO_LEVEL_EVENT: &str = "audio-level";
+/// 电平事件的最小发送间隔（ebur128 每 100ms 打一行，原样转发太密）
+const AUDIO_LEVEL_EMIT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// 推送给前端电平表的响度数据
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioLevel {
+    station_id: String,
+    station_name: String,
+    /// 瞬时响度（LUFS）
+    momentary_lufs: f32,
+    /// 短期响度（LUFS）
+    short_term_lufs: f32,
+}
+
+/// 解析 FFmpeg ebur128 滤镜写到 stderr 的电平行
+///
+/// 形如 `[Parsed_ebur128_0 @ ...] t: 2.1 TARGET:-23 LUFS M: -17.9 S: -18.4 ...`，
+/// 返回（瞬时响度 M，短期响度 S）。
+fn parse_ebur128_levels(line: &str) -> Option<(f32, f32)> {
+    if !line.contains("Parsed_ebur128") {
+        return None;
+    }
+    let momentary = extract_ebur128_value(line, "M:")?;
+    let short_term = extract_ebur128_value(line, "S:")?;
+    Some((momentary, short_term))
+}
+
+/// 取出 ebur128 行里指定键后面的数值
+fn extract_ebur128_value(line: &str, key: &str) -> Option<f32> {
+    let rest = &line[line.find(key)? + key.len()..];
+    rest.split_whitespace().next()?.parse().ok()
+}
+
 fn ffmpeg_diagnostic_level(line: &str) -> Option<&'static str> {
     if is_ffmpeg_noise_line(line) {
         return None;
@@ -124,31 +413,419 @@ fn kill_stream_process(process_id: u32) {
     }
 }
 
+/// 挂起 FFmpeg 进程（不终止）
+///
+/// Windows 没有现成的挂起命令，这里留空；各读取任务暂停取数据后
+/// 输出管道填满，FFmpeg 会自行阻塞，效果等同挂起。
+fn suspend_stream_process(process_id: u32) {
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = std::process::Command::new("kill")
+            .args(["-STOP", &process_id.to_string()])
+            .output();
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let _ = process_id;
+    }
+}
+
+/// 恢复被挂起的 FFmpeg 进程
+fn resume_stream_process(process_id: u32) {
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = std::process::Command::new("kill")
+            .args(["-CONT", &process_id.to_string()])
+            .output();
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let _ = process_id;
+    }
+}
+
 /// 服务器共享状态
 pub struct ServerState {
     /// 电台列表
     pub stations: RwLock<HashMap<String, Station>>,
     /// 活动的 FFmpeg 进程
     pub active_streams: RwLock<HashMap<String, ActiveStream>>, // request_id -> stream
+    /// 各电台最近一次开始播放的时间
+    pub last_played: RwLock<HashMap<String, String>>, // station_id -> time
+    /// 正在启动 FFmpeg 的电台，防止并发请求重复拉起进程
+    pub spawn_claims: RwLock<HashSet<String>>,
+    /// 最近若干次流启动耗时（请求到首个音频字节，毫秒）
+    pub startup_latencies_ms: RwLock<Vec<u64>>,
+    /// 保温中的转码流：客户端已断开但 FFmpeg 仍在运行，可被新客户端接入
+    warm_streams: RwLock<HashMap<String, WarmStream>>, // station_id -> warm
+    /// 正在输出的转码流的共享接入口：同一电台的新客户端把自己的
+    /// 发送端交给已有读取任务，共享一个 FFmpeg 的输出
+    live_joins: RwLock<HashMap<String, tokio::sync::mpsc::Sender<StreamSender>>>, // station_id -> join
     /// 服务器端口（可动态更新）
     pub port: RwLock<u16>,
     /// FFmpeg 路径
     pub ffmpeg_path: PathBuf,
+    /// 应用数据目录（用于读取设置文件）
+    pub data_dir: PathBuf,
+    /// 本实例的转码码率覆盖（kbps），额外实例可与主实例用不同档位
+    pub bitrate_override: Option<u32>,
     /// API 客户端（用于刷新流地址）
     pub api: RadioApi,
+    /// B 站 API 客户端
+    pub bilibili: BilibiliApi,
     /// 诊断日志
     pub logger: DiagnosticLogger,
+    /// 流地址过期刷新任务是否已启动
+    url_refresh_task_started: AtomicBool,
+    /// B 站候选池空闲预刷新任务是否已启动
+    bilibili_prefresh_task_started: AtomicBool,
+    /// 爬取进度广播，供 SSE 端点订阅
+    pub crawl_progress_tx: tokio::sync::broadcast::Sender<CrawlProgress>,
+    /// HTTP 触发的爬取是否正在进行
+    crawl_in_progress: AtomicBool,
+    /// 服务器事件广播，供 `/api/events` SSE 订阅
+    server_events_tx: tokio::sync::broadcast::Sender<ServerEvent>,
+    /// MQTT 转发任务是否已启动
+    mqtt_task_started: AtomicBool,
+    /// Discord Rich Presence 任务是否已启动
+    discord_task_started: AtomicBool,
+    /// OBS 标题文件输出任务是否已启动
+    obs_title_task_started: AtomicBool,
+    /// 各电台的吞吐统计环，key 为电台 ID
+    stream_stats: RwLock<HashMap<String, ThroughputRing>>,
+    /// 各电台的累计播放次数，落盘供 SII 排序使用
+    play_counts: std::sync::Mutex<HashMap<String, u64>>,
+    /// 最新播报音频（已转码为 MP3），供所有活动流插入
+    announcement_clip: RwLock<Option<Arc<Vec<u8>>>>,
+    /// 播报序号，活动流据此判断是否有未播的新播报
+    announcement_seq: std::sync::atomic::AtomicU64,
+    /// FFmpeg 编码器能力，首次启动流时异步探测一次
+    encoder_caps: tokio::sync::OnceCell<crate::utils::ffmpeg::EncoderCapabilities>,
+    /// 全局暂停标志，置位时各读取任务停止从 FFmpeg 取数据
+    streams_paused: AtomicBool,
+    /// 恢复播放时唤醒被暂停的读取任务
+    resume_notify: tokio::sync::Notify,
+    /// 各客户端 IP 的限流计数（窗口起点，窗口内请求数）
+    rate_counters: std::sync::Mutex<HashMap<std::net::IpAddr, (std::time::Instant, u64)>>,
 }
 
 impl ServerState {
-    pub fn new(port: u16, ffmpeg_path: PathBuf, logger: DiagnosticLogger) -> Self {
+    pub fn new(
+        port: u16,
+        ffmpeg_path: PathBuf,
+        data_dir: PathBuf,
+        logger: DiagnosticLogger,
+        bitrate_override: Option<u32>,
+    ) -> Self {
+        let settings = load_settings_from_file(&data_dir);
+        let bilibili = BilibiliApi::new(data_dir.clone());
+        bilibili.set_search_tid(settings.bilibili_search_tid);
+        let api = RadioApi::new();
+        api.set_api_key_override(Some(settings.yunting_api_key));
+        api.set_mirror_urls(&settings.yunting_mirror_urls);
+        let play_counts = load_play_counts(&data_dir);
         Self {
+            bitrate_override,
             stations: RwLock::new(HashMap::new()),
             active_streams: RwLock::new(HashMap::new()),
+            last_played: RwLock::new(HashMap::new()),
+            spawn_claims: RwLock::new(HashSet::new()),
+            startup_latencies_ms: RwLock::new(Vec::new()),
+            warm_streams: RwLock::new(HashMap::new()),
+            live_joins: RwLock::new(HashMap::new()),
             port: RwLock::new(port),
             ffmpeg_path,
-            api: RadioApi::new(),
+            data_dir,
+            api,
+            bilibili,
             logger,
+            url_refresh_task_started: AtomicBool::new(false),
+            bilibili_prefresh_task_started: AtomicBool::new(false),
+            crawl_progress_tx: tokio::sync::broadcast::channel(32).0,
+            crawl_in_progress: AtomicBool::new(false),
+            server_events_tx: tokio::sync::broadcast::channel(64).0,
+            mqtt_task_started: AtomicBool::new(false),
+            discord_task_started: AtomicBool::new(false),
+            obs_title_task_started: AtomicBool::new(false),
+            stream_stats: RwLock::new(HashMap::new()),
+            play_counts: std::sync::Mutex::new(play_counts),
+            announcement_clip: RwLock::new(None),
+            announcement_seq: std::sync::atomic::AtomicU64::new(0),
+            encoder_caps: tokio::sync::OnceCell::new(),
+            streams_paused: AtomicBool::new(false),
+            resume_notify: tokio::sync::Notify::new(),
+            rate_counters: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 获取 FFmpeg 编码器能力，首次调用时探测并缓存
+    ///
+    /// 探测是惰性的：放到第一个流请求时才跑，避免拖慢启动；
+    /// 探测失败时保守假定全部可用，行为与探测功能加入前一致。
+    pub async fn encoder_caps(&self) -> crate::utils::ffmpeg::EncoderCapabilities {
+        *self
+            .encoder_caps
+            .get_or_init(|| async {
+                match crate::utils::ffmpeg::probe_encoders(&self.ffmpeg_path).await {
+                    Some(caps) => {
+                        if !caps.libmp3lame {
+                            self.logger.warn(
+                                "ffmpeg",
+                                "当前 FFmpeg 缺少 libmp3lame 编码器，将自动选择替代方案",
+                                None::<String>,
+                            );
+                        }
+                        caps
+                    }
+                    None => {
+                        log::debug!("FFmpeg 编码器探测失败，假定全部可用");
+                        crate::utils::ffmpeg::EncoderCapabilities::assume_all()
+                    }
+                }
+            })
+            .await
+    }
+
+    /// 记录一次电台播放并落盘计数
+    ///
+    /// 只在播放真正建立（收到首个音频字节，或接入保温 / 共享管线）
+    /// 时计一次：WebView 对同一 audio src 的重复 GET、启动失败和
+    /// 游戏对故障电台的自动重连都不计入，避免把打不开的电台
+    /// 刷到"最常听"排序的顶部。虚拟频道不参与 SII 排序，不计入。
+    fn note_station_play(&self, station_id: &str) {
+        let snapshot = {
+            let Ok(mut counts) = self.play_counts.lock() else {
+                return;
+            };
+            *counts.entry(station_id.to_string()).or_insert(0) += 1;
+            counts.clone()
+        };
+        if let Ok(json) = serde_json::to_string_pretty(&snapshot) {
+            let _ = crate::utils::fs::write_atomic(&self.data_dir.join(PLAY_COUNTS_FILE), json);
+        }
+    }
+
+    /// 本实例的转码码率：实例覆盖优先于全局设置
+    pub fn transcode_bitrate(&self, settings: &AppSettings) -> u32 {
+        self.bitrate_override
+            .unwrap_or(settings.transcode_bitrate_kbps)
+    }
+
+    /// 当前占用 CPU 的 FFmpeg 进程数：活动流加保温流
+    async fn transcode_process_count(&self) -> usize {
+        let active = self.active_streams.read().await.len();
+        let warm = self.warm_streams.read().await.len();
+        active + warm
+    }
+
+    /// 发布服务器事件；没有订阅者时静默丢弃
+    fn publish_event(&self, event: ServerEvent) {
+        let _ = self.server_events_tx.send(event);
+    }
+
+    /// 记录一次流输出，按秒聚合进该电台的吞吐统计环
+    pub async fn record_stream_throughput(&self, station_id: &str, bytes: usize) {
+        let now_second = chrono::Local::now().timestamp();
+        let mut stats = self.stream_stats.write().await;
+        stats
+            .entry(station_id.to_string())
+            .or_default()
+            .record(bytes, now_second);
+    }
+
+    /// 读取电台的吞吐样本（kbps，每秒一个，旧的在前）
+    pub async fn get_stream_stats(&self, station_id: &str) -> Vec<u32> {
+        let stats = self.stream_stats.read().await;
+        stats
+            .get(station_id)
+            .map(|ring| ring.samples.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// 清除电台的吞吐统计（流关闭时调用）
+    async fn clear_stream_stats(&self, station_id: &str) {
+        self.stream_stats.write().await.remove(station_id);
+    }
+
+    /// 把当前活动电台写入运行时状态文件，供重启后恢复
+    async fn persist_runtime_state(&self) {
+        let mut station_ids: Vec<String> = {
+            let active_streams = self.active_streams.read().await;
+            active_streams
+                .values()
+                .map(|stream| stream.station_id.clone())
+                .collect()
+        };
+        station_ids.sort();
+        station_ids.dedup();
+
+        let state = RuntimeState {
+            active_station_ids: station_ids,
+        };
+        if let Ok(json) = serde_json::to_string_pretty(&state) {
+            let _ =
+                crate::utils::fs::write_atomic(&self.data_dir.join(RUNTIME_STATE_FILE), json);
+        }
+    }
+
+    /// 预热指定电台：提前解析并刷新流地址，游戏重连首个请求免去等待
+    pub async fn prewarm_stations(self: &Arc<Self>, station_ids: &[String]) {
+        for station_id in station_ids {
+            let station = { self.stations.read().await.get(station_id).cloned() };
+            let Some(station) = station else {
+                continue;
+            };
+            self.logger.push(
+                "info",
+                "server",
+                "游戏可能会重新请求该电台，提前解析流地址",
+                Some(station.id.clone()),
+                Some(station.name.clone()),
+                None::<String>,
+            );
+            let _ = resolve_stream_url(self, &station).await;
+        }
+    }
+
+    /// 解析电台当前可用的流地址（诊断用，与播放路径走同一套逻辑）
+    pub async fn resolve_station_url(self: &Arc<Self>, station: &Station) -> Option<String> {
+        resolve_stream_url(self, station).await
+    }
+
+    /// 刷新有活动流且签名临近过期的电台地址
+    ///
+    /// 云听部分流地址带签名 token，过期后 FFmpeg 重连会失败；
+    /// 提前刷新让保温重连和下一次播放都能拿到有效地址。
+    async fn refresh_expiring_urls(&self) {
+        let active_ids: HashSet<String> = self
+            .active_streams
+            .read()
+            .await
+            .values()
+            .map(|stream| stream.station_id.clone())
+            .collect();
+        if active_ids.is_empty() {
+            return;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let expiring: Vec<Station> = {
+            let stations = self.stations.read().await;
+            active_ids
+                .iter()
+                .filter_map(|id| stations.get(id))
+                .filter(|station| !station.is_custom)
+                .filter(|station| {
+                    station
+                        .url_expires_at
+                        .is_some_and(|expires| expires - now < URL_REFRESH_LEAD_SECS)
+                })
+                .cloned()
+                .collect()
+        };
+
+        for station in expiring {
+            if let Ok(Some(url)) = self
+                .api
+                .refresh_stream_url(station.raw_id(), &station.province)
+                .await
+            {
+                let expires = Station::parse_url_expiry(&url);
+                let mut stations = self.stations.write().await;
+                if let Some(entry) = stations.get_mut(&station.id) {
+                    entry.mp3_play_url_high = Some(url);
+                    entry.url_expires_at = expires;
+                }
+                self.logger.push(
+                    "info",
+                    "stream",
+                    "流地址签名临近过期，已提前刷新",
+                    Some(station.id.clone()),
+                    Some(station.name.clone()),
+                    None::<String>,
+                );
+                self.publish_event(ServerEvent::MetadataUpdated {
+                    station_id: station.id.clone(),
+                    station_name: station.name.clone(),
+                });
+            }
+        }
+    }
+
+    /// 空闲时预刷新 B 站候选池
+    ///
+    /// 游戏中切到 B 站电台要连打好几个接口才能出声；趁没有活动流的
+    /// 空闲时段把搜索池和音频地址提前备好，出声只差拉流一步。
+    async fn refresh_bilibili_pools(&self) {
+        if !self.active_streams.read().await.is_empty() {
+            return;
+        }
+        // 风控退避期内不主动打接口
+        if self.bilibili.rate_limited_secs().is_some() {
+            return;
+        }
+
+        let refreshed_pools = self.bilibili.refresh_search_pools().await;
+
+        // 预解析 B 站电台的音频地址，结果作为缓存回退地址
+        let now = chrono::Utc::now().timestamp();
+        let stale: Vec<Station> = {
+            let stations = self.stations.read().await;
+            stations
+                .values()
+                .filter(|station| station.id.starts_with(ID_PREFIX_BILIBILI))
+                .filter(|station| {
+                    station
+                        .url_expires_at
+                        .is_none_or(|expires| expires - now < URL_REFRESH_LEAD_SECS)
+                })
+                .take(BILIBILI_PREFRESH_BATCH)
+                .cloned()
+                .collect()
+        };
+        if refreshed_pools == 0 && stale.is_empty() {
+            return;
+        }
+
+        let settings = load_settings_from_file(&self.data_dir);
+        let mut resolved = 0;
+        for station in &stale {
+            match self
+                .bilibili
+                .get_audio_url(
+                    station.raw_id(),
+                    settings.bilibili_audio_quality,
+                    &settings.bilibili_cdn,
+                )
+                .await
+            {
+                Ok(url) => {
+                    // 没有签名过期信息时按固定时长有效，避免每个周期都重复解析
+                    let expires = Station::parse_url_expiry(&url)
+                        .or(Some(now + BILIBILI_PREFRESH_URL_TTL_SECS));
+                    let mut stations = self.stations.write().await;
+                    if let Some(entry) = stations.get_mut(&station.id) {
+                        entry.mp3_play_url_high = Some(url);
+                        entry.url_expires_at = expires;
+                    }
+                    resolved += 1;
+                }
+                // 接口不可用时本轮到此为止，下个周期再试
+                Err(e) => {
+                    log::debug!("预解析 B 站音频地址失败: {} ({})", station.name, e);
+                    break;
+                }
+            }
+        }
+
+        if refreshed_pools > 0 || resolved > 0 {
+            self.logger.info(
+                "bilibili",
+                format!(
+                    "空闲预刷新完成：{} 个搜索池，{} 个电台音频地址",
+                    refreshed_pools, resolved
+                ),
+            );
         }
     }
 
@@ -163,14 +840,125 @@ impl ServerState {
 
     /// 获取服务器状态
     pub async fn get_status(&self) -> ServerStatus {
+        let (p50, p95) = self.startup_latency_percentiles().await;
         ServerStatus {
             running: true,
             port: *self.port.read().await,
             active_streams: self.active_streams.read().await.len(),
             total_stations: self.stations.read().await.len(),
+            startup_latency_p50_ms: p50,
+            startup_latency_p95_ms: p95,
+        }
+    }
+
+    /// 记录一次流启动耗时，只保留最近的样本
+    pub async fn record_startup_latency(&self, elapsed_ms: u64) {
+        const MAX_SAMPLES: usize = 200;
+        let mut samples = self.startup_latencies_ms.write().await;
+        samples.push(elapsed_ms);
+        if samples.len() > MAX_SAMPLES {
+            let excess = samples.len() - MAX_SAMPLES;
+            samples.drain(..excess);
+        }
+    }
+
+    /// 计算启动耗时的 p50/p95，没有样本时返回 None
+    pub async fn startup_latency_percentiles(&self) -> (Option<u64>, Option<u64>) {
+        let samples = self.startup_latencies_ms.read().await;
+        if samples.is_empty() {
+            return (None, None);
+        }
+
+        let mut sorted = samples.clone();
+        sorted.sort_unstable();
+        let percentile = |p: usize| {
+            let index = (sorted.len().saturating_sub(1)) * p / 100;
+            sorted[index]
+        };
+        (Some(percentile(50)), Some(percentile(95)))
+    }
+
+    /// 注册保温流，超出容量时淘汰最早进入保温的电台
+    ///
+    /// 被淘汰电台的 adopt_tx 被丢弃后，其读取任务会立即退出并杀掉进程。
+    async fn register_warm_stream(
+        &self,
+        station_id: &str,
+        adopt_tx: tokio::sync::mpsc::Sender<StreamSender>,
+    ) {
+        let mut warm = self.warm_streams.write().await;
+        warm.insert(
+            station_id.to_string(),
+            WarmStream {
+                adopt_tx,
+                warmed_at: std::time::Instant::now(),
+            },
+        );
+
+        while warm.len() > MAX_WARM_STREAMS {
+            let oldest = warm
+                .iter()
+                .min_by_key(|(_, stream)| stream.warmed_at)
+                .map(|(id, _)| id.clone());
+            match oldest {
+                Some(id) => {
+                    warm.remove(&id);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// 取走指定电台的保温流接入句柄
+    async fn take_warm_stream(
+        &self,
+        station_id: &str,
+    ) -> Option<tokio::sync::mpsc::Sender<StreamSender>> {
+        self.warm_streams
+            .write()
+            .await
+            .remove(station_id)
+            .map(|stream| stream.adopt_tx)
+    }
+
+    /// 注册正在输出的转码流的共享接入口
+    async fn register_live_stream(
+        &self,
+        station_id: &str,
+        join_tx: tokio::sync::mpsc::Sender<StreamSender>,
+    ) {
+        self.live_joins
+            .write()
+            .await
+            .insert(station_id.to_string(), join_tx);
+    }
+
+    /// 注销共享接入口，仅当登记的仍是本会话的通道时移除
+    ///
+    /// 读取任务退出和同一电台新会话的注册之间存在竞争，
+    /// 比对通道避免误删新会话刚登记的接入口。
+    async fn unregister_live_stream(
+        &self,
+        station_id: &str,
+        join_tx: &tokio::sync::mpsc::Sender<StreamSender>,
+    ) {
+        let mut joins = self.live_joins.write().await;
+        if joins
+            .get(station_id)
+            .is_some_and(|tx| tx.same_channel(join_tx))
+        {
+            joins.remove(station_id);
         }
     }
 
+    /// 获取指定电台共享接入口的克隆（如有）
+    async fn join_live_stream(
+        &self,
+        station_id: &str,
+    ) -> Option<tokio::sync::mpsc::Sender<StreamSender>> {
+        self.live_joins.read().await.get(station_id).cloned()
+    }
+
     /// 停止当前所有活动流，但不关闭 HTTP 服务器。
     pub async fn stop_active_streams(&self) {
         let active_streams: Vec<_> = {
@@ -235,6 +1023,79 @@ impl ServerState {
 
         stopped_any
     }
+
+    /// 暂停全部活动流，但不杀掉 FFmpeg 进程。
+    ///
+    /// Unix 下给进程发 SIGSTOP；所有平台同时让读取任务停止取数据，
+    /// 管道填满后进程自行阻塞。快速锁屏/解锁不再触发完整的重连周期。
+    /// 返回受影响的流数量。
+    pub async fn pause_all_streams(&self) -> usize {
+        let streams = self.active_streams.read().await;
+        let count = streams.len();
+        if self.streams_paused.swap(true, Ordering::Relaxed) {
+            return count; // 已处于暂停状态
+        }
+        for stream in streams.values() {
+            suspend_stream_process(stream.process_id);
+        }
+        drop(streams);
+
+        if count > 0 {
+            self.logger.info(
+                "stream",
+                format!("已暂停 {} 个活动流（进程挂起，未终止）", count),
+            );
+        }
+        count
+    }
+
+    /// 恢复全部被暂停的流，返回受影响的流数量
+    pub async fn resume_all_streams(&self) -> usize {
+        if !self.streams_paused.swap(false, Ordering::Relaxed) {
+            return 0; // 本就没有暂停
+        }
+        let streams = self.active_streams.read().await;
+        let count = streams.len();
+        for stream in streams.values() {
+            resume_stream_process(stream.process_id);
+        }
+        drop(streams);
+        self.resume_notify.notify_waiters();
+
+        if count > 0 {
+            self.logger
+                .info("stream", format!("已恢复 {} 个活动流", count));
+        }
+        count
+    }
+
+    /// 记一次限流计数，返回是否放行
+    ///
+    /// 固定一分钟窗口；顺带清理过期条目，防止计数表无限增长。
+    fn register_rate_hit(&self, ip: std::net::IpAddr, limit: u64) -> bool {
+        let now = std::time::Instant::now();
+        let mut counters = self.rate_counters.lock().unwrap();
+        counters.retain(|_, (start, _)| now.duration_since(*start) < RATE_LIMIT_WINDOW);
+
+        let (_, count) = counters.entry(ip).or_insert((now, 0));
+        *count += 1;
+        *count <= limit
+    }
+
+    /// 全局暂停期间挂起当前任务，恢复后立即返回
+    async fn wait_while_paused(&self) {
+        loop {
+            if !self.streams_paused.load(Ordering::Relaxed) {
+                return;
+            }
+            let notified = self.resume_notify.notified();
+            // 注册等待后再确认一次，避免错过恢复通知
+            if !self.streams_paused.load(Ordering::Relaxed) {
+                return;
+            }
+            notified.await;
+        }
+    }
 }
 
 /// 流媒体服务器
@@ -247,10 +1108,27 @@ pub struct StreamServer {
 
 impl StreamServer {
     /// 创建新的服务器实例
-    pub fn new(port: u16, ffmpeg_path: PathBuf, logger: DiagnosticLogger) -> Self {
+    pub fn new(port: u16, ffmpeg_path: PathBuf, data_dir: PathBuf, logger: DiagnosticLogger) -> Self {
+        Self::with_profile(port, ffmpeg_path, data_dir, logger, None)
+    }
+
+    /// 创建带独立转码档位的服务器实例（额外实例用）
+    pub fn with_profile(
+        port: u16,
+        ffmpeg_path: PathBuf,
+        data_dir: PathBuf,
+        logger: DiagnosticLogger,
+        bitrate_override: Option<u32>,
+    ) -> Self {
         Self {
             port,
-            state: Arc::new(ServerState::new(port, ffmpeg_path, logger)),
+            state: Arc::new(ServerState::new(
+                port,
+                ffmpeg_path,
+                data_dir,
+                logger,
+                bitrate_override,
+            )),
             shutdown_tx: None,
             is_running: false,
         }
@@ -266,6 +1144,17 @@ impl StreamServer {
         self.state.clone()
     }
 
+    /// 设置首选监听端口
+    ///
+    /// 运行中只更新下次启动的基准端口（重启后生效）；
+    /// 未运行时同步更新状态里的端口，生成 SII 立即使用新值。
+    pub async fn set_port(&mut self, port: u16) {
+        self.port = port;
+        if !self.is_running {
+            *self.state.port.write().await = port;
+        }
+    }
+
     /// 停止当前所有活动流，但保持服务器运行。
     pub async fn stop_active_streams(&self) {
         self.state.stop_active_streams().await;
@@ -283,13 +1172,22 @@ impl StreamServer {
         let state = self.state.clone();
 
         // 尝试绑定端口，如果被占用就自动切换
+        let settings = load_settings_from_file(&self.state.data_dir);
+        let tuning = settings.stream_tuning;
+        // 默认只监听回环地址；开启局域网监听后绑定到所有网卡，
+        // 手机、音箱等设备才能访问网页播放器和流端点
+        let bind_ip: std::net::IpAddr = if settings.listen_on_lan {
+            std::net::Ipv4Addr::UNSPECIFIED.into()
+        } else {
+            std::net::Ipv4Addr::LOCALHOST.into()
+        };
         let mut port = self.port;
         let max_attempts = 10; // 最多尝试 10 个端口
         let mut listener = None;
 
         for attempt in 0..max_attempts {
-            let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
-            match tokio::net::TcpListener::bind(addr).await {
+            let addr = std::net::SocketAddr::from((bind_ip, port));
+            match bind_listener(addr, &tuning) {
                 Ok(l) => {
                     if attempt > 0 {
                         log::info!("端口 {} 被占用，自动切换到 {}", self.port, port);
@@ -335,28 +1233,219 @@ impl StreamServer {
             "server",
             format!("流媒体服务器已启动: http://127.0.0.1:{}", port),
         );
+        if settings.listen_on_lan {
+            self.state.logger.info(
+                "server",
+                format!("已开启局域网监听，本机以外的设备可访问端口 {}", port),
+            );
+        }
 
-        // 构建路由
+        // 构建路由。控制类端点单独成组，可选 Basic 认证保护：
+        // 把音频服务开放到局域网时，别人能听但不能操作。
+        let admin_routes = Router::new()
+            .route("/api/sii.json", get(handle_sii_json))
+            .route("/api/announce", post(handle_announce))
+            .route("/api/crawl/start", post(handle_crawl_start))
+            .route("/api/streams/stop", post(handle_stop_streams))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                require_admin_auth,
+            ));
         let app = Router::new()
+            .route("/", get(handle_index))
             .route("/stream/:id", get(handle_stream))
+            .route("/playlist.xspf", get(handle_playlist_xspf))
+            .route("/playlist/province/:name", get(handle_playlist_province))
+            .route("/playlist/genre/:genre", get(handle_playlist_genre))
+            .route("/images/:id", get(handle_channel_image))
             .route("/health", get(handle_health))
+            .route("/metrics", get(handle_metrics))
             .route("/api/stations", get(handle_stations_api))
+            .route("/api/stations/voice_search", post(handle_voice_search))
+            .route("/api/provinces/geo", get(handle_provinces_geo))
+            .route("/api/events", get(handle_events_sse))
+            .route("/api/crawl/progress", get(handle_crawl_progress_sse))
+            .merge(admin_routes)
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                enforce_rate_limit,
+            ))
+            // 访问日志放最外层，被限流 / 认证拒绝的请求也会被记到
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                log_requests,
+            ))
             .layer(CorsLayer::new().allow_origin(Any).allow_methods(Any))
             .with_state(state);
 
-        // 在后台运行服务器
-        tokio::spawn(async move {
-            axum::serve(listener, app)
-                .with_graceful_shutdown(async {
-                    rx.await.ok();
-                })
-                .await
-                .ok();
-        });
+        // 定期检查活动电台的流地址签名是否临近过期
+        if !self
+            .state
+            .url_refresh_task_started
+            .swap(true, Ordering::Relaxed)
+        {
+            let refresh_state = self.state.clone();
+            tokio::spawn(async move {
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_secs(60));
+                loop {
+                    interval.tick().await;
+                    refresh_state.refresh_expiring_urls().await;
+                }
+            });
+        }
 
-        self.is_running = true;
-        Ok(())
-    }
+        // 空闲时段预刷新 B 站候选池，进游戏切台免去串行接口等待
+        if !self
+            .state
+            .bilibili_prefresh_task_started
+            .swap(true, Ordering::Relaxed)
+        {
+            let prefresh_state = self.state.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+                    BILIBILI_PREFRESH_INTERVAL_SECS,
+                ));
+                // 第一个 tick 立即触发，跳过它让刷新从一个周期后开始
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    prefresh_state.refresh_bilibili_pools().await;
+                }
+            });
+        }
+
+        // 把播放事件和服务器状态转发到 MQTT broker（可选集成）
+        if !self.state.mqtt_task_started.swap(true, Ordering::Relaxed) {
+            let mqtt_state = self.state.clone();
+            tokio::spawn(async move {
+                let mut rx = mqtt_state.server_events_tx.subscribe();
+                loop {
+                    let event = match rx.recv().await {
+                        Ok(event) => event,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    };
+                    let settings = load_settings_from_file(&mqtt_state.data_dir);
+                    if !settings.mqtt.enabled {
+                        continue;
+                    }
+                    if let Err(e) = publish_mqtt_event(&mqtt_state, &settings.mqtt, &event).await {
+                        mqtt_state.logger.warn(
+                            "mqtt",
+                            "发布 MQTT 消息失败",
+                            Some(e.to_string()),
+                        );
+                    }
+                }
+            });
+        }
+
+        // 把正在收听的电台同步到 Discord Rich Presence（可选集成）
+        if !self.state.discord_task_started.swap(true, Ordering::Relaxed) {
+            let discord_state = self.state.clone();
+            tokio::spawn(async move {
+                let mut rx = discord_state.server_events_tx.subscribe();
+                loop {
+                    let event = match rx.recv().await {
+                        Ok(event) => event,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    };
+                    if !load_settings_from_file(&discord_state.data_dir).discord_rich_presence {
+                        continue;
+                    }
+                    // Discord 未运行属于常态，失败只记 debug 日志
+                    let result = match &event {
+                        ServerEvent::StreamStarted { station_name, .. } => {
+                            crate::utils::discord::set_activity(station_name).await
+                        }
+                        ServerEvent::StreamStopped { .. } => {
+                            if discord_state.active_streams.read().await.is_empty() {
+                                crate::utils::discord::clear_activity().await
+                            } else {
+                                Ok(())
+                            }
+                        }
+                        ServerEvent::MetadataUpdated { .. } => Ok(()),
+                    };
+                    if let Err(e) = result {
+                        log::debug!("更新 Discord Rich Presence 失败: {}", e);
+                    }
+                }
+            });
+        }
+
+        // 把正在播放的电台写到 OBS 标题文件（可选集成）
+        if !self.state.obs_title_task_started.swap(true, Ordering::Relaxed) {
+            let obs_state = self.state.clone();
+            tokio::spawn(async move {
+                let mut rx = obs_state.server_events_tx.subscribe();
+                loop {
+                    let event = match rx.recv().await {
+                        Ok(event) => event,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    };
+                    let path = load_settings_from_file(&obs_state.data_dir).obs_title_file;
+                    if path.trim().is_empty() {
+                        continue;
+                    }
+                    let line = match &event {
+                        ServerEvent::StreamStarted {
+                            station_id,
+                            station_name,
+                        } => {
+                            // 有副标题时拼成"电台 – 副标题"
+                            let subtitle = obs_state
+                                .stations
+                                .read()
+                                .await
+                                .get(station_id)
+                                .map(|s| s.subtitle.clone())
+                                .unwrap_or_default();
+                            if subtitle.is_empty() {
+                                station_name.clone()
+                            } else {
+                                format!("{} – {}", station_name, subtitle)
+                            }
+                        }
+                        ServerEvent::StreamStopped { .. } => {
+                            // 还有其他活动流时保持现有标题，全部停止才清空
+                            if obs_state.active_streams.read().await.is_empty() {
+                                String::new()
+                            } else {
+                                continue;
+                            }
+                        }
+                        ServerEvent::MetadataUpdated { .. } => continue,
+                    };
+                    if let Err(e) =
+                        crate::utils::fs::write_atomic(std::path::Path::new(&path), line)
+                    {
+                        log::debug!("OBS 标题文件写入失败: {}", e);
+                    }
+                }
+            });
+        }
+
+        // 在后台运行服务器
+        tokio::spawn(async move {
+            // 带上连接信息，限流中间件才能拿到客户端 IP
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+            )
+                .with_graceful_shutdown(async {
+                    rx.await.ok();
+                })
+                .await
+                .ok();
+        });
+
+        self.is_running = true;
+        Ok(())
+    }
 
     /// 停止服务器
     pub async fn stop(&mut self) {
@@ -381,6 +1470,23 @@ async fn handle_stream(
     Path(station_id): Path<String>,
     State(state): State<Arc<ServerState>>,
 ) -> Response {
+    let request_start = std::time::Instant::now();
+
+    // 虚拟频道单独处理
+    if station_id == INTERRUPT_CHANNEL_ID {
+        return handle_interrupt_stream(state).await;
+    }
+    if station_id == TOUR_CHANNEL_ID {
+        return handle_tour_stream(state).await;
+    }
+    if let Some(genre) = station_id.strip_prefix(GENRE_CHANNEL_PREFIX) {
+        return handle_genre_stream(state, genre.to_string()).await;
+    }
+
+    // 按后缀确定输出格式（.aac / .opus），默认 MP3
+    let (codec, real_id) = OutputCodec::from_request_id(&station_id);
+    let station_id = real_id.to_string();
+
     // 查找电台
     let station = {
         let stations = state.stations.read().await;
@@ -410,124 +1516,265 @@ async fn handle_stream(
         Some(format!("省份: {}", station.province)),
     );
 
-    // WebView 可能会对同一个 audio src 发起两次 GET。
-    // 新请求到来时先关闭该电台已有流，确保同一电台最终只保留一个 FFmpeg。
-    let replaced_existing_stream = state.stop_streams_for_station(&station_id).await;
-
-    // 获取流地址：自定义电台直接用缓存地址，普通电台刷新
-    let stream_url = if station.is_custom {
-        log::debug!("custom station stream url");
+    // 原子占用该电台的启动权：active_streams 在 spawn 之后才写入，
+    // 仅靠它无法阻止并发请求同时拉起两个 FFmpeg。
+    let mut claimed = false;
+    for _ in 0..10 {
+        if state.spawn_claims.write().await.insert(station_id.clone()) {
+            claimed = true;
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+    }
+    if !claimed {
         state.logger.push(
-            "info",
+            "warn",
             "stream",
-            "使用自定义电台流地址",
+            "电台正在被其他请求启动，放弃本次请求",
             Some(station_id.clone()),
             Some(station.name.clone()),
             None::<String>,
         );
-        match station.get_best_stream_url() {
-            Some(url) => url.to_string(),
-            None => {
+        return (StatusCode::TOO_MANY_REQUESTS, "该电台正在启动中").into_response();
+    }
+    let _claim = SpawnClaim {
+        state: state.clone(),
+        station_id: station_id.clone(),
+    };
+
+    // 优先接入保温中的 FFmpeg：跳过地址解析和进程启动，换台几乎瞬时完成。
+    // 保温管线固定输出 MP3，其他格式不参与。
+    if codec == OutputCodec::Mp3 {
+        if let Some(adopt_tx) = state.take_warm_stream(&station_id).await {
+            let settings = load_settings_from_file(&state.data_dir);
+            let bitrate = station.bitrate.unwrap_or(state.transcode_bitrate(&settings));
+            let (tx, rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(
+                output_channel_capacity(&settings, bitrate),
+            );
+            if adopt_tx.send(tx).await.is_ok() {
+                let elapsed_ms = request_start.elapsed().as_millis() as u64;
+                state.record_startup_latency(elapsed_ms).await;
+                state.note_station_play(&station_id);
                 state.logger.push(
-                    "error",
+                    "info",
                     "stream",
-                    "自定义电台无流地址",
+                    format!("接入保温中的 FFmpeg 进程，启动耗时 {}ms", elapsed_ms),
                     Some(station_id.clone()),
                     Some(station.name.clone()),
                     None::<String>,
                 );
-                return (StatusCode::INTERNAL_SERVER_ERROR, "自定义电台无流地址").into_response();
+                state.last_played.write().await.insert(
+                    station_id.clone(),
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                );
+                state.publish_event(ServerEvent::StreamStarted {
+                    station_id: station_id.clone(),
+                    station_name: station.name.clone(),
+                });
+
+                let body = Body::from_stream(ReceiverStream::new(rx));
+                return stream_response(&station, &settings, bitrate, OutputCodec::Mp3, body);
             }
+            // 保温流恰好在此刻过期，走正常启动流程
         }
-    } else {
-        // 刷新流地址
-        state.logger.push(
-            "info",
-            "api",
-            "正在刷新真实播放地址",
-            Some(station_id.clone()),
-            Some(station.name.clone()),
-            None::<String>,
-        );
-        match state
-            .api
-            .refresh_stream_url(&station_id, &station.province)
-            .await
-        {
-            Ok(Some(url)) => {
-                log::debug!("refreshed stream url");
+
+        // 该电台已有客户端在播时共享同一个 FFmpeg 的输出：
+        // 游戏和浏览器同时打开同一电台不再重复转码。
+        if let Some(join_tx) = state.join_live_stream(&station_id).await {
+            let settings = load_settings_from_file(&state.data_dir);
+            let bitrate = station.bitrate.unwrap_or(state.transcode_bitrate(&settings));
+            let (tx, rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(
+                output_channel_capacity(&settings, bitrate),
+            );
+            if join_tx.send(tx).await.is_ok() {
+                let elapsed_ms = request_start.elapsed().as_millis() as u64;
+                state.record_startup_latency(elapsed_ms).await;
+                state.note_station_play(&station_id);
                 state.logger.push(
                     "info",
-                    "api",
-                    "真实播放地址刷新成功",
+                    "stream",
+                    format!("接入已有转码进程共享输出，启动耗时 {}ms", elapsed_ms),
                     Some(station_id.clone()),
                     Some(station.name.clone()),
                     None::<String>,
                 );
-                url
+                state.last_played.write().await.insert(
+                    station_id.clone(),
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                );
+                state.publish_event(ServerEvent::StreamStarted {
+                    station_id: station_id.clone(),
+                    station_name: station.name.clone(),
+                });
+
+                let body = Body::from_stream(ReceiverStream::new(rx));
+                return stream_response(&station, &settings, bitrate, OutputCodec::Mp3, body);
             }
-            Ok(None) => {
-                // 使用缓存的地址
-                log::warn!("刷新流地址失败，使用缓存地址");
+            // 读取任务恰好在此刻退出，走正常启动流程
+        }
+    }
+
+    // WebView 可能会对同一个 audio src 发起两次 GET（共享接入已在上面
+    // 处理）。仍在运行但无法接入的旧流先关闭，确保同一电台最终只保留
+    // 一个 FFmpeg。
+    let replaced_existing_stream = state.stop_streams_for_station(&station_id).await;
+
+    // 转码预算：并发 FFmpeg 数量超过 CPU 核数上限时拒绝新请求，
+    // 避免局域网多设备收听把整机拖垮。替换已有流不占新额度。
+    let budget = max_concurrent_transcodes();
+    let running = state.transcode_process_count().await;
+    if running >= budget {
+        state.logger.push(
+            "warn",
+            "stream",
+            format!("转码进程已达上限（{}/{}），拒绝新播放请求", running, budget),
+            Some(station_id.clone()),
+            Some(station.name.clone()),
+            None::<String>,
+        );
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!(
+                "转码进程已达上限（{} 个，按 CPU 核数限制），请先停止其他电台再试",
+                budget
+            ),
+        )
+            .into_response();
+    }
+
+    // 读取设置：解析超时和音频滤镜链都要用
+    let settings = load_settings_from_file(&state.data_dir);
+
+    // 获取流地址：自定义电台直接用缓存地址，普通电台带超时刷新
+    let stream_url = match resolve_stream_url_with_timeout(
+        &state,
+        &station,
+        settings.resolve_timeout_secs,
+    )
+    .await
+    {
+        Some(url) => url,
+        None => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "无可用流地址").into_response();
+        }
+    };
+
+    log::debug!("stream url: {}...", &stream_url[..stream_url.len().min(80)]);
+
+    // 按需构建音频滤镜链
+    let mut audio_filters: Vec<String> = Vec::new();
+    if let Some(db) = settings.station_gains.get(&station_id) {
+        if *db != 0.0 {
+            audio_filters.push(format!("volume={}dB", db));
+            state.logger.push(
+                "info",
+                "ffmpeg",
+                format!("应用音量增益: {}dB", db),
+                Some(station_id.clone()),
+                Some(station.name.clone()),
+                None::<String>,
+            );
+        }
+    }
+
+    if settings.enable_limiter {
+        // 限幅到约 -1dB，放在增益之后，保证增益过大的电台也不会削波
+        audio_filters.push("alimiter=limit=0.891".to_string());
+    }
+
+    if settings.level_meter {
+        // 音频原样通过，响度值打到 stderr，由诊断输出任务解析后推给前端
+        audio_filters.push("ebur128".to_string());
+    }
+
+    // 弹幕高能起播（实验性）：按弹幕密度曲线跳到最密集的片段
+    let start_offset_secs = if station.id.starts_with(ID_PREFIX_BILIBILI)
+        && settings.bilibili_highlight_stations.contains(&station.id)
+    {
+        match tokio::time::timeout(
+            tokio::time::Duration::from_secs(5),
+            state.bilibili.densest_segment_start(station.raw_id()),
+        )
+        .await
+        {
+            Ok(Ok(secs)) if secs > 0 => {
                 state.logger.push(
-                    "warn",
-                    "api",
-                    "刷新真实播放地址失败，尝试使用缓存地址",
+                    "info",
+                    "stream",
+                    format!("按弹幕密度从 {} 秒处起播", secs),
                     Some(station_id.clone()),
                     Some(station.name.clone()),
                     None::<String>,
                 );
-                match station.get_best_stream_url() {
-                    Some(url) => url.to_string(),
-                    None => {
-                        state.logger.push(
-                            "error",
-                            "stream",
-                            "无可用流地址",
-                            Some(station_id.clone()),
-                            Some(station.name.clone()),
-                            None::<String>,
-                        );
-                        return (StatusCode::INTERNAL_SERVER_ERROR, "无可用流地址").into_response();
-                    }
-                }
+                Some(secs)
             }
-            Err(e) => {
-                log::error!("刷新流地址失败: {}", e);
-                state.logger.push(
-                    "warn",
-                    "api",
-                    "刷新真实播放地址异常，尝试使用缓存地址",
-                    Some(station_id.clone()),
-                    Some(station.name.clone()),
-                    Some(e.to_string()),
-                );
-                match station.get_best_stream_url() {
-                    Some(url) => url.to_string(),
-                    None => {
-                        state.logger.push(
-                            "error",
-                            "stream",
-                            "无可用流地址",
-                            Some(station_id.clone()),
-                            Some(station.name.clone()),
-                            Some(e.to_string()),
-                        );
-                        return (StatusCode::INTERNAL_SERVER_ERROR, "无可用流地址").into_response();
-                    }
-                }
+            Ok(Err(e)) => {
+                // 拿不到弹幕不影响播放，从头开始
+                log::debug!("弹幕密度获取失败，从头播放: {}", e);
+                None
             }
+            _ => None,
+        }
+    } else if station.id.starts_with(ID_PREFIX_AUDIOBOOK) {
+        // 有声书从书签的确切位置继续，换集由合并逻辑切换流地址
+        let offset = crate::commands::audiobook::bookmark_offset(&state.data_dir, &station.id);
+        if let Some(secs) = offset {
+            state.logger.push(
+                "info",
+                "stream",
+                format!("从书签位置 {} 秒处继续播放", secs),
+                Some(station_id.clone()),
+                Some(station.name.clone()),
+                None::<String>,
+            );
         }
+        offset
+    } else {
+        None
     };
 
-    log::debug!("stream url: {}...", &stream_url[..stream_url.len().min(80)]);
-
     // 启动 FFmpeg 进程
     let ffmpeg_path = &state.ffmpeg_path;
+    let bitrate = station.bitrate.unwrap_or(state.transcode_bitrate(&settings));
+    let watermark = prepare_watermark(&state, &station_id, &settings).await;
+    let caps = state.encoder_caps().await;
 
-    let mut child = match spawn_ffmpeg(ffmpeg_path, &stream_url) {
+    // 缺少目标编码器时走与启动失败相同的回退 / 报错路径
+    let mut child = match codec
+        .preferred_encoder(&caps)
+        .ok_or_else(|| anyhow::anyhow!("FFmpeg 缺少 {} 输出所需的编码器", codec.content_type()))
+        .and_then(|encoder| {
+            spawn_ffmpeg(
+                ffmpeg_path,
+                &TranscodeSpec {
+                    stream_url: &stream_url,
+                    start_offset_secs,
+                    audio_filters: &audio_filters,
+                    bitrate_kbps: bitrate,
+                    codec,
+                    encoder,
+                    watermark: watermark.as_ref(),
+                },
+            )
+        }) {
         Ok(child) => child,
         Err(e) => {
+            // FFmpeg 不可用时，HLS/AAC/MP3 源回退到纯 Rust 直通
+            // （无转码、无增益和限幅，也无法转成 AAC/Opus 输出）
+            if codec == OutputCodec::Mp3
+                && (crate::radio::hls::can_relay_natively(&stream_url)
+                    || crate::radio::hls::is_mp3_url(&stream_url))
+            {
+                state.logger.push(
+                    "warn",
+                    "ffmpeg",
+                    "FFmpeg 不可用，回退到纯 Rust 直通（无转码）",
+                    Some(station_id.clone()),
+                    Some(station.name.clone()),
+                    Some(e.to_string()),
+                );
+                return native_passthrough_response(&state, &station, &settings, stream_url).await;
+            }
             log::error!("启动 FFmpeg 失败: {}", e);
             state.logger.push(
                 "error",
@@ -563,18 +1810,42 @@ async fn handle_stream(
                 process_id,
             },
         );
+        state.persist_runtime_state().await;
     }
     if !replaced_existing_stream {
         log::info!("正在播放: {} ({})", station.name, station.province);
     }
 
+    // 记录最近播放时间
+    state.last_played.write().await.insert(
+        station_id.clone(),
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    );
+    state.publish_event(ServerEvent::StreamStarted {
+        station_id: station_id.clone(),
+        station_name: station.name.clone(),
+    });
+
     // 获取输出流
     let stdout = child.stdout.take().expect("无法获取 stdout");
     let stderr = child.stderr.take();
 
     // 创建流式响应
-    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(32);
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(
+        output_channel_capacity(&settings, bitrate),
+    );
     let first_audio_packet = Arc::new(AtomicBool::new(false));
+    // 预填充的输出缓冲大小（字节），吸收上游分片抖动
+    let prebuffer_bytes = settings.output_buffer_secs * bitrate as u64 * 1000 / 8;
+
+    // 登记共享接入口：同一电台的后续请求把发送端交给本读取任务，
+    // 共享这一个 FFmpeg 的输出。共享管线与保温一样只支持 MP3。
+    let (join_tx, mut join_rx) = tokio::sync::mpsc::channel::<StreamSender>(4);
+    if codec == OutputCodec::Mp3 {
+        state
+            .register_live_stream(&station_id, join_tx.clone())
+            .await;
+    }
 
     // 在后台读取 FFmpeg 输出
     let station_id_clone = station_id.clone();
@@ -582,52 +1853,213 @@ async fn handle_stream(
     let request_id_clone = request_id.clone();
     let state_clone = state.clone();
     let first_audio_packet_clone = first_audio_packet.clone();
+    let read_chunk_bytes = settings.stream_tuning.read_chunk_bytes.clamp(1024, 65536);
+    let output_is_mp3 = codec == OutputCodec::Mp3;
     tokio::spawn(async move {
         let mut reader = tokio::io::BufReader::new(stdout);
-        let mut buffer = [0u8; 4096];
+        let mut buffer = vec![0u8; read_chunk_bytes];
+        // 共享本进程输出的所有客户端，断开的在发送时就地移除
+        let mut subscribers: Vec<StreamSender> = vec![tx];
+        // 输出缓冲：先积累若干秒音频再开始发送，之后管线始终保有这段储备
+        let mut prebuffer: Vec<Vec<u8>> = Vec::new();
+        let mut prebuffer_remaining = prebuffer_bytes;
+        // 帧过滤：丢弃损坏数据，保证输出始终从 MP3 帧边界开始
+        let mut frame_filter = crate::radio::mp3::FrameFilter::new();
+        // 已播过的播报序号，启动时对齐当前值避免重播旧播报
+        let mut last_announcement_seq = state_clone.announcement_seq.load(Ordering::Relaxed);
 
-        loop {
-            match reader.read(&mut buffer).await {
-                Ok(0) => break, // EOF
-                Ok(n) => {
-                    if !first_audio_packet_clone.swap(true, Ordering::Relaxed) {
+        // 外层循环：一个客户端会话，加上断开后的保温期
+        'session: loop {
+            loop {
+                // 接收共享接入的新客户端，下一个数据块起一并发送
+                while let Ok(new_tx) = join_rx.try_recv() {
+                    subscribers.push(new_tx);
+                }
+                // 全局暂停时停读，让管道背压把 FFmpeg 也堵住
+                state_clone.wait_while_paused().await;
+                match reader.read(&mut buffer).await {
+                    Ok(0) => break 'session, // EOF
+                    Ok(n) => {
+                        if !first_audio_packet_clone.swap(true, Ordering::Relaxed) {
+                            let elapsed_ms = request_start.elapsed().as_millis() as u64;
+                            state_clone.record_startup_latency(elapsed_ms).await;
+                            // 首个音频字节到达才算播放成功，此时才计入播放次数
+                            state_clone.note_station_play(&station_id_clone);
+                            state_clone.logger.push(
+                                "info",
+                                "ffmpeg",
+                                format!("已收到首个音频数据包，启动耗时 {}ms", elapsed_ms),
+                                Some(station_id_clone.clone()),
+                                Some(station_name_clone.clone()),
+                                None::<String>,
+                            );
+                        }
+                        // 有新播报时先把播报音频插入输出，播完再继续电台内容。
+                        // 播报与电台输出同为 MP3，直接拼接即可；期间电台
+                        // 声音被完全压下，结束后自动恢复。
+                        let announce_seq = state_clone.announcement_seq.load(Ordering::Relaxed);
+                        if announce_seq != last_announcement_seq && output_is_mp3 {
+                            last_announcement_seq = announce_seq;
+                            let clip = state_clone.announcement_clip.read().await.clone();
+                            if let Some(clip) = clip {
+                                for part in clip.chunks(read_chunk_bytes) {
+                                    fan_out_chunk(&mut subscribers, part).await;
+                                    if subscribers.is_empty() {
+                                        break;
+                                    }
+                                }
+                                if subscribers.is_empty() {
+                                    break; // 所有接收端已关闭，进入保温期
+                                }
+                            }
+                        }
+
+                        // 帧过滤只理解 MP3，AAC/Opus 输出原样转发
+                        let chunk = if output_is_mp3 {
+                            frame_filter.push(&buffer[..n])
+                        } else {
+                            buffer[..n].to_vec()
+                        };
+                        if chunk.is_empty() {
+                            continue; // 残帧未到齐或正在重新对齐帧边界
+                        }
+                        state_clone
+                            .record_stream_throughput(&station_id_clone, chunk.len())
+                            .await;
+                        if prebuffer_remaining > 0 {
+                            prebuffer_remaining =
+                                prebuffer_remaining.saturating_sub(chunk.len() as u64);
+                            prebuffer.push(chunk);
+                            if prebuffer_remaining == 0 {
+                                // 预填充完成，一次性送入响应通道
+                                for buffered in prebuffer.drain(..) {
+                                    fan_out_chunk(&mut subscribers, &buffered).await;
+                                }
+                                if subscribers.is_empty() {
+                                    break;
+                                }
+                            }
+                            continue;
+                        }
+                        fan_out_chunk(&mut subscribers, &chunk).await;
+                        if subscribers.is_empty() {
+                            break; // 所有接收端已关闭，进入保温期
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("读取 FFmpeg 输出失败: {}", e);
                         state_clone.logger.push(
-                            "info",
+                            "error",
                             "ffmpeg",
-                            "已收到首个音频数据包",
+                            "读取 FFmpeg 输出失败",
                             Some(station_id_clone.clone()),
                             Some(station_name_clone.clone()),
-                            None::<String>,
+                            Some(e.to_string()),
                         );
+                        for subscriber in &subscribers {
+                            let _ = subscriber
+                                .send(Err(std::io::Error::new(e.kind(), e.to_string())))
+                                .await;
+                        }
+                        break 'session;
                     }
-                    if tx.send(Ok(buffer[..n].to_vec())).await.is_err() {
-                        break; // 接收端已关闭
+                }
+            }
+
+            // 保温期：继续读走 FFmpeg 输出防止管道阻塞，等待新客户端接入。
+            // 接入的新客户端直接续播，不再重新预填充缓冲。
+            prebuffer.clear();
+            prebuffer_remaining = 0;
+            let grace_secs = load_settings_from_file(&state_clone.data_dir).keep_alive_grace_secs;
+            // 保温注册表里的流会被按 MP3 接入，非 MP3 输出不进入保温
+            if grace_secs == 0 || !output_is_mp3 {
+                break;
+            }
+            let (adopt_tx, mut adopt_rx) = tokio::sync::mpsc::channel::<StreamSender>(1);
+            state_clone
+                .register_warm_stream(&station_id_clone, adopt_tx)
+                .await;
+            state_clone.logger.push(
+                "info",
+                "stream",
+                format!("客户端断开，FFmpeg 保温 {} 秒等待重新接入", grace_secs),
+                Some(station_id_clone.clone()),
+                Some(station_name_clone.clone()),
+                None::<String>,
+            );
+
+            let deadline =
+                tokio::time::Instant::now() + tokio::time::Duration::from_secs(grace_secs);
+            let adopted = loop {
+                tokio::select! {
+                    read = reader.read(&mut buffer) => {
+                        match read {
+                            Ok(0) | Err(_) => break None, // 进程已结束，保温失败
+                            Ok(_) => {} // 丢弃数据
+                        }
+                    }
+                    new_client = adopt_rx.recv() => {
+                        break new_client; // None 表示被容量淘汰
+                    }
+                    new_client = join_rx.recv() => {
+                        // 共享接入口在保温期同样可用
+                        if let Some(new_tx) = new_client {
+                            break Some(new_tx);
+                        }
+                    }
+                    _ = tokio::time::sleep_until(deadline) => {
+                        // 接入请求可能与超时同时到达，最后再确认一次
+                        break adopt_rx.try_recv().ok().or_else(|| join_rx.try_recv().ok());
                     }
                 }
-                Err(e) => {
-                    log::error!("读取 FFmpeg 输出失败: {}", e);
-                    state_clone.logger.push(
-                        "error",
-                        "ffmpeg",
-                        "读取 FFmpeg 输出失败",
-                        Some(station_id_clone.clone()),
-                        Some(station_name_clone.clone()),
-                        Some(e.to_string()),
-                    );
-                    let _ = tx.send(Err(e)).await;
+            };
+
+            match adopted {
+                Some(new_tx) => {
+                    subscribers.push(new_tx);
+                    // 经共享接入口复活时保温登记还在，清掉过期句柄
+                    let _ = state_clone.take_warm_stream(&station_id_clone).await;
+                    // 保温期间丢弃的数据可能停在半帧处，重新对齐帧边界
+                    frame_filter = crate::radio::mp3::FrameFilter::new();
+                }
+                None => {
+                    let _ = state_clone.take_warm_stream(&station_id_clone).await;
                     break;
                 }
             }
         }
 
+        if frame_filter.dropped_bytes() > 0 {
+            state_clone.logger.push(
+                "warn",
+                "stream",
+                format!(
+                    "本次播放共丢弃损坏的 MP3 数据 {} 字节",
+                    frame_filter.dropped_bytes()
+                ),
+                Some(station_id_clone.clone()),
+                Some(station_name_clone.clone()),
+                None::<String>,
+            );
+        }
+
         // 清理
+        state_clone
+            .unregister_live_stream(&station_id_clone, &join_tx)
+            .await;
         let _ = child.kill().await;
         state_clone
             .active_streams
             .write()
             .await
             .remove(&request_id_clone);
+        state_clone.persist_runtime_state().await;
+        state_clone.clear_stream_stats(&station_id_clone).await;
         log::debug!("stream closed: {} / {}", request_id_clone, station_id_clone);
+        state_clone.publish_event(ServerEvent::StreamStopped {
+            station_id: station_id_clone.clone(),
+            station_name: station_name_clone.clone(),
+        });
         state_clone.logger.push(
             "info",
             "stream",
@@ -642,9 +2074,11 @@ async fn handle_stream(
         let station_id_clone = station_id.clone();
         let station_name_clone = station.name.clone();
         let state_clone = state.clone();
+        let level_meter = settings.level_meter;
         tokio::spawn(async move {
             let mut reader = tokio::io::BufReader::new(stderr);
             let mut buffer = [0u8; 1024];
+            let mut last_level_emit = std::time::Instant::now();
 
             loop {
                 match reader.read(&mut buffer).await {
@@ -652,6 +2086,24 @@ async fn handle_stream(
                     Ok(n) => {
                         let detail = String::from_utf8_lossy(&buffer[..n]).replace('\r', "\n");
                         for line in detail.lines() {
+                            if level_meter {
+                                if let Some((momentary, short_term)) = parse_ebur128_levels(line) {
+                                    // 电平行只走事件，不进诊断日志
+                                    if last_level_emit.elapsed() >= AUDIO_LEVEL_EMIT_INTERVAL {
+                                        last_level_emit = std::time::Instant::now();
+                                        state_clone.logger.emit_event(
+                                            AUDIO_LEVEL_EVENT,
+                                            AudioLevel {
+                                                station_id: station_id_clone.clone(),
+                                                station_name: station_name_clone.clone(),
+                                                momentary_lufs: momentary,
+                                                short_term_lufs: short_term,
+                                            },
+                                        );
+                                    }
+                                    continue;
+                                }
+                            }
                             if let Some(level) = ffmpeg_diagnostic_level(line) {
                                 state_clone.logger.push(
                                     level,
@@ -701,43 +2153,895 @@ async fn handle_stream(
     }
 
     // 构建响应
-    let stream = ReceiverStream::new(rx);
-    let body = Body::from_stream(stream);
+    let body = Body::from_stream(ReceiverStream::new(rx));
+    stream_response(&station, &settings, bitrate, codec, body)
+}
+
+/// 把一块音频数据发给所有共享输出的客户端
+///
+/// 断开的就地移除；个别客户端长时间收不动（网络停滞）时也将其
+/// 踢掉，避免一个卡死的连接拖停共享同一电台的其他客户端。
+async fn fan_out_chunk(subscribers: &mut Vec<StreamSender>, chunk: &[u8]) {
+    let mut index = 0;
+    while index < subscribers.len() {
+        let sent = tokio::time::timeout(
+            FANOUT_SEND_TIMEOUT,
+            subscribers[index].send(Ok(chunk.to_vec())),
+        )
+        .await;
+        match sent {
+            Ok(Ok(())) => index += 1,
+            _ => {
+                subscribers.swap_remove(index);
+            }
+        }
+    }
+}
+
+/// 按输出缓冲设置计算响应通道容量（以 4KB 数据块为单位）
+///
+/// 通道本身也是缓冲的一部分，容量放大后 FFmpeg 可以在上游抖动时
+/// 继续写入而不被 HTTP 发送端反压。
+fn output_channel_capacity(settings: &AppSettings, bitrate_kbps: u32) -> usize {
+    let buffer_bytes = settings.output_buffer_secs * bitrate_kbps as u64 * 1000 / 8;
+    32 + (buffer_bytes / 4096) as usize
+}
+
+/// 启动纯 Rust 直通并构建流响应
+///
+/// FFmpeg 缺席时的回退路径：MP3 上游整段代理，HLS 上游解出
+/// ADTS-AAC 负载。转发结束以客户端断开或上游失败为准，
+/// 不占用 active_streams（没有可管理的子进程）。
+async fn native_passthrough_response(
+    state: &Arc<ServerState>,
+    station: &Station,
+    settings: &AppSettings,
+    stream_url: String,
+) -> Response {
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(32);
+    // 直通转发在任务内部连上游，这里按"开始转发"计一次播放
+    state.note_station_play(&station.id);
+    let content_type = if crate::radio::hls::is_mp3_url(&stream_url) {
+        tokio::spawn(crate::radio::hls::relay_mp3_passthrough(
+            stream_url,
+            tx,
+            state.logger.clone(),
+            station.id.clone(),
+            station.name.clone(),
+        ));
+        "audio/mpeg"
+    } else {
+        tokio::spawn(crate::radio::hls::relay_hls_native(
+            stream_url,
+            tx,
+            state.logger.clone(),
+            station.id.clone(),
+            station.name.clone(),
+        ));
+        "audio/aac"
+    };
+
+    state.last_played.write().await.insert(
+        station.id.clone(),
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    );
+    state.publish_event(ServerEvent::StreamStarted {
+        station_id: station.id.clone(),
+        station_name: station.name.clone(),
+    });
+
+    let icy_name = if settings.icy_ascii_names {
+        SiiGenerator::to_english_name(&station.name)
+    } else {
+        urlencoding::encode(&station.name).to_string()
+    };
+    let icy_name = truncate_utf8(&icy_name, settings.icy_name_max_len).to_string();
 
     Response::builder()
-        .header(header::CONTENT_TYPE, "audio/mpeg")
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::TRANSFER_ENCODING, "chunked")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .header("icy-name", icy_name)
+        .header("icy-genre", SiiGenerator::get_genre(station))
+        .body(Body::from_stream(ReceiverStream::new(rx)))
+        .unwrap()
+}
+
+/// 构建带 ICY 元数据头的流响应，Content-Type 随输出格式变化
+fn stream_response(
+    station: &Station,
+    settings: &AppSettings,
+    bitrate: u32,
+    codec: OutputCodec,
+    body: Body,
+) -> Response {
+    // 部分播放器会把 URL 编码的中文 icy-name 原样显示成乱码，
+    // 默认改用转写后的英文名，并按字符边界安全截断。
+    let icy_name = if settings.icy_ascii_names {
+        SiiGenerator::to_english_name(&station.name)
+    } else {
+        urlencoding::encode(&station.name).to_string()
+    };
+    let icy_name = truncate_utf8(&icy_name, settings.icy_name_max_len).to_string();
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, codec.content_type())
         .header(header::TRANSFER_ENCODING, "chunked")
         .header(header::CACHE_CONTROL, "no-cache")
         .header(header::CONNECTION, "keep-alive")
-        .header("icy-name", urlencoding::encode(&station.name).to_string())
+        .header("icy-name", icy_name)
+        .header("icy-genre", SiiGenerator::get_genre(station))
+        .header("icy-br", bitrate.to_string())
         .body(body)
         .unwrap()
 }
 
-/// 启动 FFmpeg 转码进程
-fn spawn_ffmpeg(ffmpeg_path: &PathBuf, stream_url: &str) -> anyhow::Result<Child> {
-    let mut cmd = Command::new(ffmpeg_path);
+/// 按字符边界截断字符串，避免把多字节字符截成无效 UTF-8
+pub(crate) fn truncate_utf8(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
 
-    cmd.args([
-        "-reconnect",
-        "1",
-        "-reconnect_streamed",
-        "1",
-        "-reconnect_delay_max",
-        "5",
-        "-i",
-        stream_url,
-        "-vn",
-        "-acodec",
-        "libmp3lame",
-        "-ab",
-        "128k",
-        "-ar",
-        "44100",
-        "-ac",
+    let mut end = max_bytes;
+    while end > 0 && !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// 处理定时插播虚拟频道
+///
+/// 正常转发基础电台，每隔配置的间隔切换到插播电台播放数分钟后切回。
+/// 两路输入分别由各自的 FFmpeg 进程转码，HTTP 连接全程保持不断开。
+async fn handle_interrupt_stream(state: Arc<ServerState>) -> Response {
+    let settings = load_settings_from_file(&state.data_dir);
+    let cfg = settings.interrupt_channel;
+    if !cfg.enabled {
+        return (StatusCode::NOT_FOUND, "插播频道未启用").into_response();
+    }
+
+    let (base, cutin) = {
+        let stations = state.stations.read().await;
+        (
+            stations.get(&cfg.base_station_id).cloned(),
+            stations.get(&cfg.cutin_station_id).cloned(),
+        )
+    };
+    let (base, cutin) = match (base, cutin) {
+        (Some(base), Some(cutin)) => (base, cutin),
+        _ => {
+            state.logger.push(
+                "warn",
+                "stream",
+                "插播频道配置的电台未找到",
+                Some(INTERRUPT_CHANNEL_ID.to_string()),
+                None::<String>,
+                Some(format!(
+                    "base: {} / cutin: {}",
+                    cfg.base_station_id, cfg.cutin_station_id
+                )),
+            );
+            return (StatusCode::NOT_FOUND, "插播频道配置的电台未找到").into_response();
+        }
+    };
+
+    state.logger.push(
+        "info",
+        "stream",
+        format!(
+            "启动插播频道：{} 每 {} 分钟插播 {} {} 分钟",
+            base.name, cfg.interval_minutes, cutin.name, cfg.cutin_minutes
+        ),
+        Some(INTERRUPT_CHANNEL_ID.to_string()),
+        None::<String>,
+        None::<String>,
+    );
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(32);
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let mut play_base = true;
+
+        loop {
+            let station = if play_base { &base } else { &cutin };
+            let phase_minutes = if play_base {
+                cfg.interval_minutes
+            } else {
+                cfg.cutin_minutes
+            };
+            let deadline = tokio::time::Instant::now()
+                + tokio::time::Duration::from_secs(phase_minutes.max(1) * 60);
+
+            if !relay_station_until(&state_clone, station, INTERRUPT_CHANNEL_ID, deadline, &tx)
+                .await
+            {
+                break;
+            }
+
+            play_base = !play_base;
+            state_clone.logger.push(
+                "info",
+                "stream",
+                if play_base {
+                    "插播结束，切回基础电台"
+                } else {
+                    "开始插播"
+                },
+                Some(INTERRUPT_CHANNEL_ID.to_string()),
+                None::<String>,
+                None::<String>,
+            );
+        }
+
+        log::debug!("interrupt channel stream closed");
+        state_clone.logger.push(
+            "info",
+            "stream",
+            "插播频道播放流已关闭",
+            Some(INTERRUPT_CHANNEL_ID.to_string()),
+            None::<String>,
+            None::<String>,
+        );
+    });
+
+    let body = Body::from_stream(ReceiverStream::new(rx));
+    Response::builder()
+        .header(header::CONTENT_TYPE, "audio/mpeg")
+        .header(header::TRANSFER_ENCODING, "chunked")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .header("icy-name", "CN Traffic Interrupt")
+        .body(body)
+        .unwrap()
+}
+
+/// 处理环游中国虚拟电台
+///
+/// 按 TOUR_PROVINCE_ORDER 的地理顺序，每个省份挑一个电台轮播，
+/// 每个电台播放配置的分钟数后切到下一个省份，循环往复。
+/// 环游频道的播放列表：按地理顺序每省取一台（省内按 ID 排序保证确定性）
+pub(crate) fn tour_playlist(stations: &HashMap<String, Station>) -> Vec<Station> {
+    TOUR_PROVINCE_ORDER
+        .iter()
+        .filter_map(|province| {
+            let mut candidates: Vec<&Station> = stations
+                .values()
+                .filter(|s| s.province == *province)
+                .collect();
+            candidates.sort_by(|a, b| a.id.cmp(&b.id));
+            candidates.first().map(|s| (*s).clone())
+        })
+        .collect()
+}
+
+/// 流派聚合频道的播放列表（按 ID 排序保证确定性）
+pub(crate) fn genre_playlist(stations: &HashMap<String, Station>, genre: &str) -> Vec<Station> {
+    let mut list: Vec<Station> = stations
+        .values()
+        .filter(|s| SiiGenerator::get_genre(s) == genre)
+        .cloned()
+        .collect();
+    list.sort_by(|a, b| a.id.cmp(&b.id));
+    list
+}
+
+async fn handle_tour_stream(state: Arc<ServerState>) -> Response {
+    let settings = load_settings_from_file(&state.data_dir);
+    let cfg = settings.tour_channel;
+    if !cfg.enabled {
+        return (StatusCode::NOT_FOUND, "环游频道未启用").into_response();
+    }
+
+    // 每个省份按地理顺序取一个电台（省内按 ID 排序保证确定性）
+    let playlist: Vec<Station> = {
+        let stations = state.stations.read().await;
+        tour_playlist(&stations)
+    };
+
+    if playlist.is_empty() {
+        return (StatusCode::NOT_FOUND, "没有可用于环游频道的电台").into_response();
+    }
+
+    state.logger.push(
+        "info",
+        "stream",
+        format!(
+            "启动环游频道：{} 个省份，每站 {} 分钟",
+            playlist.len(),
+            cfg.minutes_per_station
+        ),
+        Some(TOUR_CHANNEL_ID.to_string()),
+        None::<String>,
+        None::<String>,
+    );
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(32);
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let mut index = 0usize;
+
+        loop {
+            let station = &playlist[index % playlist.len()];
+            let deadline = tokio::time::Instant::now()
+                + tokio::time::Duration::from_secs(cfg.minutes_per_station.max(1) * 60);
+
+            state_clone.logger.push(
+                "info",
+                "stream",
+                format!("环游频道到达 {}：{}", station.province, station.name),
+                Some(TOUR_CHANNEL_ID.to_string()),
+                Some(station.name.clone()),
+                None::<String>,
+            );
+
+            if !relay_station_until(&state_clone, station, TOUR_CHANNEL_ID, deadline, &tx).await {
+                break;
+            }
+
+            index += 1;
+        }
+
+        log::debug!("tour channel stream closed");
+        state_clone.logger.push(
+            "info",
+            "stream",
+            "环游频道播放流已关闭",
+            Some(TOUR_CHANNEL_ID.to_string()),
+            None::<String>,
+            None::<String>,
+        );
+    });
+
+    let body = Body::from_stream(ReceiverStream::new(rx));
+    Response::builder()
+        .header(header::CONTENT_TYPE, "audio/mpeg")
+        .header(header::TRANSFER_ENCODING, "chunked")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .header("icy-name", "Tour of China")
+        .body(body)
+        .unwrap()
+}
+
+/// 处理流派聚合虚拟电台
+///
+/// 在同一流派的全部电台中轮播，每个电台播放配置的分钟数，
+/// 让游戏里一个 SII 条目就能听遍全国同类电台。
+async fn handle_genre_stream(state: Arc<ServerState>, genre: String) -> Response {
+    let settings = load_settings_from_file(&state.data_dir);
+    let cfg = settings.genre_channels;
+    if !cfg.enabled {
+        return (StatusCode::NOT_FOUND, "流派聚合频道未启用").into_response();
+    }
+
+    let playlist: Vec<Station> = {
+        let stations = state.stations.read().await;
+        genre_playlist(&stations, &genre)
+    };
+
+    if playlist.is_empty() {
+        return (StatusCode::NOT_FOUND, "该流派没有可用电台").into_response();
+    }
+
+    let channel_id = format!("{}{}", GENRE_CHANNEL_PREFIX, genre);
+    state.logger.push(
+        "info",
+        "stream",
+        format!(
+            "启动流派聚合频道 {}：{} 个电台，每站 {} 分钟",
+            genre,
+            playlist.len(),
+            cfg.minutes_per_station
+        ),
+        Some(channel_id.clone()),
+        None::<String>,
+        None::<String>,
+    );
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(32);
+    let state_clone = state.clone();
+    let channel_id_clone = channel_id.clone();
+    tokio::spawn(async move {
+        let mut index = 0usize;
+
+        loop {
+            let station = &playlist[index % playlist.len()];
+            let deadline = tokio::time::Instant::now()
+                + tokio::time::Duration::from_secs(cfg.minutes_per_station.max(1) * 60);
+
+            if !relay_station_until(&state_clone, station, &channel_id_clone, deadline, &tx).await
+            {
+                break;
+            }
+
+            index += 1;
+        }
+
+        log::debug!("genre channel stream closed: {}", channel_id_clone);
+        state_clone.logger.push(
+            "info",
+            "stream",
+            "流派聚合频道播放流已关闭",
+            Some(channel_id_clone),
+            None::<String>,
+            None::<String>,
+        );
+    });
+
+    let body = Body::from_stream(ReceiverStream::new(rx));
+    Response::builder()
+        .header(header::CONTENT_TYPE, "audio/mpeg")
+        .header(header::TRANSFER_ENCODING, "chunked")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .header("icy-name", format!("CN {} Aggregate", genre))
+        .body(body)
+        .unwrap()
+}
+
+/// 播放单个电台直到截止时间或源结束
+///
+/// 返回 false 表示客户端已断开（或 FFmpeg 无法启动），调用方应结束轮换。
+async fn relay_station_until(
+    state: &Arc<ServerState>,
+    station: &Station,
+    channel_id: &str,
+    deadline: tokio::time::Instant,
+    tx: &tokio::sync::mpsc::Sender<Result<Vec<u8>, std::io::Error>>,
+) -> bool {
+    let url = match resolve_stream_url(state, station).await {
+        Some(url) => url,
+        None => {
+            // 源暂时不可用，稍等后让调用方切到下一阶段
+            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            return !tx.is_closed();
+        }
+    };
+
+    let settings = load_settings_from_file(&state.data_dir);
+    let bitrate = station.bitrate.unwrap_or(state.transcode_bitrate(&settings));
+    let encoder = OutputCodec::Mp3
+        .preferred_encoder(&state.encoder_caps().await)
+        .unwrap_or("libmp3lame");
+    let mut child = match spawn_ffmpeg(
+        &state.ffmpeg_path,
+        &TranscodeSpec {
+            stream_url: &url,
+            start_offset_secs: None,
+            audio_filters: &[],
+            bitrate_kbps: bitrate,
+            codec: OutputCodec::Mp3,
+            encoder,
+            watermark: None,
+        },
+    ) {
+        Ok(child) => child,
+        Err(e) => {
+            log::error!("虚拟频道启动 FFmpeg 失败: {}", e);
+            state.logger.push(
+                "error",
+                "ffmpeg",
+                "虚拟频道启动 FFmpeg 失败",
+                Some(channel_id.to_string()),
+                Some(station.name.clone()),
+                Some(e.to_string()),
+            );
+            return false;
+        }
+    };
+
+    let request_id = next_stream_request_id(channel_id);
+    if let Some(process_id) = child.id() {
+        state.active_streams.write().await.insert(
+            request_id.clone(),
+            ActiveStream {
+                station_id: channel_id.to_string(),
+                process_id,
+            },
+        );
+        state.persist_runtime_state().await;
+    }
+
+    let mut reader = tokio::io::BufReader::new(child.stdout.take().expect("无法获取 stdout"));
+    let chunk_bytes = load_settings_from_file(&state.data_dir)
+        .stream_tuning
+        .read_chunk_bytes
+        .clamp(1024, 65536);
+    let mut buffer = vec![0u8; chunk_bytes];
+    let mut client_gone = false;
+
+    loop {
+        // 全局暂停时停读，虚拟频道与普通流一起静止
+        state.wait_while_paused().await;
+        tokio::select! {
+            read = reader.read(&mut buffer) => match read {
+                Ok(0) => break,
+                Ok(n) => {
+                    state.record_stream_throughput(channel_id, n).await;
+                    if tx.send(Ok(buffer[..n].to_vec())).await.is_err() {
+                        client_gone = true;
+                        break;
+                    }
+                }
+                Err(_) => break,
+            },
+            _ = tokio::time::sleep_until(deadline) => break,
+        }
+    }
+
+    let _ = child.kill().await;
+    state.active_streams.write().await.remove(&request_id);
+    state.persist_runtime_state().await;
+    state.clear_stream_stats(channel_id).await;
+
+    !client_gone && !tx.is_closed()
+}
+
+/// 带超时的流地址解析
+///
+/// 云听接口偶尔慢到游戏先放弃连接；超过 `timeout_secs` 就立即回退
+/// 缓存地址先出声，同时在后台把解析跑完并回填，下次播放即用新地址。
+async fn resolve_stream_url_with_timeout(
+    state: &Arc<ServerState>,
+    station: &Station,
+    timeout_secs: u64,
+) -> Option<String> {
+    if timeout_secs == 0 {
+        return resolve_stream_url(state, station).await;
+    }
+
+    let limit = tokio::time::Duration::from_secs(timeout_secs);
+    match tokio::time::timeout(limit, resolve_stream_url(state, station)).await {
+        Ok(url) => url,
+        Err(_) => {
+            state.logger.push(
+                "warn",
+                "api",
+                format!("解析流地址超过 {} 秒，先用缓存地址", timeout_secs),
+                Some(station.id.clone()),
+                Some(station.name.clone()),
+                None::<String>,
+            );
+
+            // 后台继续解析并把结果回填到电台缓存
+            let bg_state = state.clone();
+            let bg_station = station.clone();
+            tokio::spawn(async move {
+                if let Some(url) = resolve_stream_url(&bg_state, &bg_station).await {
+                    let expires = Station::parse_url_expiry(&url);
+                    let mut stations = bg_state.stations.write().await;
+                    if let Some(entry) = stations.get_mut(&bg_station.id) {
+                        entry.mp3_play_url_high = Some(url);
+                        if expires.is_some() {
+                            entry.url_expires_at = expires;
+                        }
+                    }
+                }
+            });
+
+            station.get_best_stream_url().map(|url| url.to_string())
+        }
+    }
+}
+
+/// 解析电台实际可播放的流地址：自定义电台直接用缓存地址，普通电台先刷新
+async fn resolve_stream_url(state: &Arc<ServerState>, station: &Station) -> Option<String> {
+    if station.is_custom {
+        log::debug!("custom station stream url");
+        state.logger.push(
+            "info",
+            "stream",
+            "使用自定义电台流地址",
+            Some(station.id.clone()),
+            Some(station.name.clone()),
+            None::<String>,
+        );
+        return match station.get_best_stream_url() {
+            Some(url) => Some(url.to_string()),
+            None => {
+                state.logger.push(
+                    "error",
+                    "stream",
+                    "自定义电台无流地址",
+                    Some(station.id.clone()),
+                    Some(station.name.clone()),
+                    None::<String>,
+                );
+                None
+            }
+        };
+    }
+
+    // B 站电台通过 playurl 接口取 DASH 音频流，音质按设置选择
+    if station.id.starts_with(ID_PREFIX_BILIBILI) {
+        let settings = load_settings_from_file(&state.data_dir);
+        return match state
+            .bilibili
+            .get_audio_url(
+                station.raw_id(),
+                settings.bilibili_audio_quality,
+                &settings.bilibili_cdn,
+            )
+            .await
+        {
+            Ok(url) => Some(url),
+            Err(e) => {
+                state.logger.push(
+                    "error",
+                    "bilibili",
+                    "获取 B 站音频流地址失败",
+                    Some(station.id.clone()),
+                    Some(station.name.clone()),
+                    Some(e.to_string()),
+                );
+                // 回退到缓存的流地址（可能已过期）
+                station.get_best_stream_url().map(|url| url.to_string())
+            }
+        };
+    }
+
+    // 刷新流地址
+    state.logger.push(
+        "info",
+        "api",
+        "正在刷新真实播放地址",
+        Some(station.id.clone()),
+        Some(station.name.clone()),
+        None::<String>,
+    );
+    match state
+        .api
+        .refresh_stream_url(station.raw_id(), &station.province)
+        .await
+    {
+        Ok(Some(url)) => {
+            log::debug!("refreshed stream url");
+            state.logger.push(
+                "info",
+                "api",
+                "真实播放地址刷新成功",
+                Some(station.id.clone()),
+                Some(station.name.clone()),
+                None::<String>,
+            );
+            // 记录新地址的签名过期时间，供过期刷新任务使用
+            let expires = Station::parse_url_expiry(&url);
+            if expires.is_some() {
+                let mut stations = state.stations.write().await;
+                if let Some(entry) = stations.get_mut(&station.id) {
+                    entry.url_expires_at = expires;
+                }
+            }
+            Some(url)
+        }
+        Ok(None) => {
+            // 使用缓存的地址
+            log::warn!("刷新流地址失败，使用缓存地址");
+            state.logger.push(
+                "warn",
+                "api",
+                "刷新真实播放地址失败，尝试使用缓存地址",
+                Some(station.id.clone()),
+                Some(station.name.clone()),
+                None::<String>,
+            );
+            match station.get_best_stream_url() {
+                Some(url) => Some(url.to_string()),
+                None => {
+                    state.logger.push(
+                        "error",
+                        "stream",
+                        "无可用流地址",
+                        Some(station.id.clone()),
+                        Some(station.name.clone()),
+                        None::<String>,
+                    );
+                    None
+                }
+            }
+        }
+        Err(e) => {
+            log::error!("刷新流地址失败: {}", e);
+            state.logger.push(
+                "warn",
+                "api",
+                "刷新真实播放地址异常，尝试使用缓存地址",
+                Some(station.id.clone()),
+                Some(station.name.clone()),
+                Some(e.to_string()),
+            );
+            match station.get_best_stream_url() {
+                Some(url) => Some(url.to_string()),
+                None => {
+                    state.logger.push(
+                        "error",
+                        "stream",
+                        "无可用流地址",
+                        Some(station.id.clone()),
+                        Some(station.name.clone()),
+                        Some(e.to_string()),
+                    );
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// 水印混音输入：预处理好的循环音频文件及其音量
+struct WatermarkInput {
+    path: PathBuf,
+    volume: f32,
+}
+
+/// 按设置为电台准备水印循环输入
+///
+/// 把台名口播片段尾部补齐静音到播报间隔长度并缓存；主转码进程
+/// 用 `-stream_loop -1` 循环该文件即实现周期性混入。预处理结果
+/// 按"片段路径 + 间隔"缓存，设置不变时只做一次。
+async fn prepare_watermark(
+    state: &Arc<ServerState>,
+    station_id: &str,
+    settings: &AppSettings,
+) -> Option<WatermarkInput> {
+    let watermark = &settings.watermark;
+    if !watermark.enabled {
+        return None;
+    }
+    let clip = watermark
+        .station_clips
+        .get(station_id)
+        .cloned()
+        .unwrap_or_else(|| watermark.clip_path.clone());
+    if clip.trim().is_empty() {
+        return None;
+    }
+    if !std::path::Path::new(&clip).exists() {
+        state
+            .logger
+            .warn("watermark", "水印音频文件不存在", Some(clip));
+        return None;
+    }
+
+    let interval = watermark.interval_secs.max(10);
+    let cache_dir = state.data_dir.join("watermark_cache");
+    let key = format!("{:x}", md5::compute(format!("{}|{}", clip, interval)));
+    let padded = cache_dir.join(format!("{}.mp3", key));
+    if !padded.exists() {
+        std::fs::create_dir_all(&cache_dir).ok()?;
+        let mut cmd = tokio::process::Command::new(&state.ffmpeg_path);
+        cmd.args(["-hide_banner", "-i"])
+            .arg(&clip)
+            .args(["-vn", "-af", &format!("apad=whole_dur={}", interval)])
+            .args(["-acodec", "libmp3lame", "-b:a", "96k", "-ar", "44100", "-ac", "2"])
+            .arg(&padded)
+            .stdin(Stdio::null());
+
+        // Windows: 隐藏控制台窗口
+        #[cfg(target_os = "windows")]
+        {
+            #[allow(unused_imports)]
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let output = cmd.output().await.ok()?;
+        if !output.status.success() {
+            state.logger.warn(
+                "watermark",
+                "水印音频预处理失败",
+                Some(
+                    String::from_utf8_lossy(&output.stderr)
+                        .lines()
+                        .last()
+                        .unwrap_or("FFmpeg 退出异常")
+                        .to_string(),
+                ),
+            );
+            let _ = std::fs::remove_file(&padded);
+            return None;
+        }
+    }
+
+    Some(WatermarkInput {
+        path: padded,
+        volume: watermark.volume.clamp(0.0, 1.0),
+    })
+}
+
+/// 并发转码进程上限
+///
+/// 每个 FFmpeg 转码大约占满不到一个核，按逻辑核数封顶；
+/// 留一个核给界面和网络 IO，双核以下机器仍保底允许两路。
+fn max_concurrent_transcodes() -> usize {
+    let cores = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+    cores.saturating_sub(1).max(2)
+}
+
+/// 一次 FFmpeg 转码的全部参数
+struct TranscodeSpec<'a> {
+    /// 上游流地址
+    stream_url: &'a str,
+    /// 输入端起播偏移（秒），弹幕高能起播使用
+    start_offset_secs: Option<u64>,
+    /// 按顺序拼接为 `-af` 滤镜链（如音量增益），空则不加
+    audio_filters: &'a [String],
+    /// 输出码率（kbps）
+    bitrate_kbps: u32,
+    /// 输出封装格式与采样率
+    codec: OutputCodec,
+    /// 按能力探测选出的编码器名
+    encoder: &'static str,
+    /// 存在时改用 filter_complex，把循环的水印音频与电台声音 amix 混合
+    watermark: Option<&'a WatermarkInput>,
+}
+
+/// 按参数启动 FFmpeg 转码进程
+fn spawn_ffmpeg(ffmpeg_path: &PathBuf, spec: &TranscodeSpec) -> anyhow::Result<Child> {
+    let TranscodeSpec {
+        stream_url,
+        start_offset_secs,
+        audio_filters,
+        bitrate_kbps,
+        codec,
+        encoder,
+        watermark,
+    } = *spec;
+    let mut cmd = Command::new(ffmpeg_path);
+
+    cmd.args([
+        "-reconnect",
+        "1",
+        "-reconnect_streamed",
+        "1",
+        "-reconnect_delay_max",
+        "5",
+    ]);
+    if let Some(secs) = start_offset_secs {
+        cmd.args(["-ss", &secs.to_string()]);
+    }
+    cmd.args(["-i", stream_url]);
+
+    if let Some(watermark) = watermark {
+        // 第二路输入无限循环播放预处理好的水印文件
+        cmd.args(["-stream_loop", "-1", "-i"]).arg(&watermark.path);
+    }
+
+    cmd.args([
+        "-vn",
+        "-acodec",
+        encoder,
+        "-ab",
+        &format!("{}k", bitrate_kbps),
+        "-ar",
+        codec.sample_rate(),
+        "-ac",
         "2",
+    ]);
+
+    if let Some(watermark) = watermark {
+        // -af 与 filter_complex 互斥，原有滤镜链并入主通路
+        let main_chain = if audio_filters.is_empty() {
+            "anull".to_string()
+        } else {
+            audio_filters.join(",")
+        };
+        let graph = format!(
+            "[0:a]{}[main];[1:a]volume={}[wm];[main][wm]amix=inputs=2:duration=first:dropout_transition=0[out]",
+            main_chain, watermark.volume
+        );
+        cmd.args(["-filter_complex", &graph, "-map", "[out]"]);
+    } else if !audio_filters.is_empty() {
+        cmd.args(["-af", &audio_filters.join(",")]);
+    }
+
+    cmd.args([
         "-f",
-        "mp3",
+        codec.ffmpeg_format(),
         "-fflags",
         "+nobuffer+discardcorrupt",
         "-flags",
@@ -760,30 +3064,1285 @@ fn spawn_ffmpeg(ffmpeg_path: &PathBuf, stream_url: &str) -> anyhow::Result<Child
         cmd.creation_flags(CREATE_NO_WINDOW);
     }
 
-    let child = cmd.spawn()?;
-    Ok(child)
+    let child = cmd.spawn()?;
+    Ok(child)
+}
+
+/// 内嵌 Web 播放页
+///
+/// 列出全部电台并用 HTML5 audio 播放 `/stream/:id`，
+/// 局域网内任何设备用浏览器即可收听，无需桌面应用界面。
+async fn handle_index() -> Response {
+    const INDEX_HTML: &str = include_str!("portal.html");
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Body::from(INDEX_HTML))
+        .unwrap()
+}
+
+/// XSPF 播放列表端点
+///
+/// 生成包含全部电台的 .xspf 播放列表，VLC 等播放器可以直接打开，
+/// 让局域网内其他设备不装桌面应用也能收听。
+async fn handle_playlist_xspf(
+    State(state): State<Arc<ServerState>>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let base_url = external_base_url(&state, &headers).await;
+    let stations: Vec<Station> = {
+        let map = state.stations.read().await;
+        map.values().cloned().collect()
+    };
+
+    let mut content = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n\
+         \x20 <title>欧卡2中国电台</title>\n\
+         \x20 <trackList>\n",
+    );
+
+    for station in &stations {
+        content.push_str(&format!(
+            "    <track>\n      <title>{}</title>\n      <location>{}/stream/{}</location>\n    </track>\n",
+            escape_xml(&station.name),
+            escape_xml(&base_url),
+            escape_xml(&station.id),
+        ));
+    }
+
+    content.push_str("  </trackList>\n</playlist>\n");
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/xspf+xml; charset=utf-8")
+        .body(Body::from(content))
+        .unwrap()
+}
+
+/// 按省份生成 M3U 播放列表
+///
+/// `/playlist/province/江苏.m3u`，外部播放器一次加载整个省的电台。
+async fn handle_playlist_province(
+    State(state): State<Arc<ServerState>>,
+    Path(name): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let name = name.strip_suffix(".m3u").unwrap_or(&name).to_string();
+    let stations: Vec<Station> = {
+        let map = state.stations.read().await;
+        map.values()
+            .filter(|s| s.province == name)
+            .cloned()
+            .collect()
+    };
+    m3u_response(&state, &headers, &format!("省份 {}", name), stations).await
+}
+
+/// 按流派生成 M3U 播放列表
+///
+/// `/playlist/genre/traffic.m3u`，流派取值与 icy-genre 一致
+/// （news、music、traffic 等）。
+async fn handle_playlist_genre(
+    State(state): State<Arc<ServerState>>,
+    Path(genre): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let genre = genre.strip_suffix(".m3u").unwrap_or(&genre).to_string();
+    let stations: Vec<Station> = {
+        let map = state.stations.read().await;
+        map.values()
+            .filter(|s| SiiGenerator::get_genre(s) == genre)
+            .cloned()
+            .collect()
+    };
+    m3u_response(&state, &headers, &format!("流派 {}", genre), stations).await
+}
+
+/// 把一组电台渲染成 M3U 响应，组内为空时返回 404
+async fn m3u_response(
+    state: &Arc<ServerState>,
+    headers: &axum::http::HeaderMap,
+    group: &str,
+    mut stations: Vec<Station>,
+) -> Response {
+    if stations.is_empty() {
+        return (StatusCode::NOT_FOUND, format!("{} 下没有电台", group)).into_response();
+    }
+    stations.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let base_url = external_base_url(state, headers).await;
+    let mut content = String::from("#EXTM3U\n");
+    for station in &stations {
+        content.push_str(&format!(
+            "#EXTINF:-1,{}\n{}/stream/{}\n",
+            station.name, base_url, station.id
+        ));
+    }
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "audio/x-mpegurl; charset=utf-8")
+        .body(Body::from(content))
+        .unwrap()
+}
+
+/// 推断对外基础地址，供生成绝对播放地址使用
+///
+/// 优先级：设置中的对外地址 > 反向代理注入的 X-Forwarded-Host /
+/// X-Forwarded-Proto > 本机回环地址。返回值不带末尾斜杠，
+/// 保证经 nginx / Caddy 代理后播放列表里的地址对外仍然可达。
+async fn external_base_url(state: &Arc<ServerState>, headers: &axum::http::HeaderMap) -> String {
+    let settings = load_settings_from_file(&state.data_dir);
+    let configured = settings.external_url.trim().trim_end_matches('/');
+    if !configured.is_empty() {
+        return configured.to_string();
+    }
+
+    if let Some(host) = headers
+        .get("x-forwarded-host")
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|host| !host.is_empty())
+    {
+        // 多级代理会用逗号串联，取最靠近客户端的第一个
+        let host = host.split(',').next().unwrap_or(host).trim();
+        let proto = headers
+            .get("x-forwarded-proto")
+            .and_then(|value| value.to_str().ok())
+            .map(|proto| proto.split(',').next().unwrap_or(proto).trim())
+            .filter(|proto| !proto.is_empty())
+            .unwrap_or("http");
+        return format!("{}://{}", proto, host);
+    }
+
+    format!("http://127.0.0.1:{}", *state.port.read().await)
+}
+
+/// 转义 XML 特殊字符
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 频道图标端点
+///
+/// 提供 `set_channel_image` 上传到数据目录的图标，桌面端和
+/// 网页播放页统一从这里取图，不直接引用本地文件路径。
+async fn handle_channel_image(
+    Path(id): Path<String>,
+    State(state): State<Arc<ServerState>>,
+) -> Response {
+    let Some(path) = crate::commands::custom::find_channel_image(&state.data_dir, &id) else {
+        return (StatusCode::NOT_FOUND, "没有该频道的图标").into_response();
+    };
+    let Ok(bytes) = tokio::fs::read(&path).await else {
+        return (StatusCode::NOT_FOUND, "没有该频道的图标").into_response();
+    };
+
+    let content_type = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("gif") => "image/gif",
+        _ => "application/octet-stream",
+    };
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "max-age=86400")
+        .body(Body::from(bytes))
+        .unwrap()
+}
+
+/// 健康检查端点
+async fn handle_health(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    state.logger.info("server", "收到健康检查请求");
+    let status = state.get_status().await;
+    axum::Json(status)
+}
+
+/// Prometheus 文本格式的指标端点
+async fn handle_metrics(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let active_streams = state.active_streams.read().await.len();
+    let total_stations = state.stations.read().await.len();
+    let (p50, p95) = state.startup_latency_percentiles().await;
+
+    let mut body = String::new();
+    body.push_str("# HELP ouka2_active_streams 当前活动的转码流数量\n");
+    body.push_str("# TYPE ouka2_active_streams gauge\n");
+    body.push_str(&format!("ouka2_active_streams {}\n", active_streams));
+    body.push_str("# HELP ouka2_total_stations 已加载的电台数量\n");
+    body.push_str("# TYPE ouka2_total_stations gauge\n");
+    body.push_str(&format!("ouka2_total_stations {}\n", total_stations));
+    body.push_str("# HELP ouka2_stream_startup_latency_ms 流启动耗时分位数（请求到首个音频字节）\n");
+    body.push_str("# TYPE ouka2_stream_startup_latency_ms gauge\n");
+    if let Some(p50) = p50 {
+        body.push_str(&format!(
+            "ouka2_stream_startup_latency_ms{{quantile=\"0.5\"}} {}\n",
+            p50
+        ));
+    }
+    if let Some(p95) = p95 {
+        body.push_str(&format!(
+            "ouka2_stream_startup_latency_ms{{quantile=\"0.95\"}} {}\n",
+            p95
+        ));
+    }
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    )
+}
+
+/// 按调优设置创建监听套接字
+///
+/// TCP_NODELAY 和发送缓冲大小设置在监听套接字上，
+/// 接受的客户端连接会继承这些选项。
+fn bind_listener(
+    addr: std::net::SocketAddr,
+    tuning: &crate::settings::StreamTuningSettings,
+) -> std::io::Result<tokio::net::TcpListener> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::for_address(addr),
+        socket2::Type::STREAM,
+        Some(socket2::Protocol::TCP),
+    )?;
+    socket.set_nodelay(tuning.tcp_nodelay)?;
+    // 与 tokio 默认绑定行为一致：允许地址重用，否则停止后立即重启时
+    // TIME_WAIT 中的旧连接会占住首选端口，服务器被迫换端口导致
+    // 已安装的 SII 失效。Windows 上 SO_REUSEADDR 语义不同，不设置。
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    if tuning.send_buffer_bytes > 0 {
+        socket.set_send_buffer_size(tuning.send_buffer_bytes)?;
+    }
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+
+    let std_listener: std::net::TcpListener = socket.into();
+    std_listener.set_nonblocking(true)?;
+    tokio::net::TcpListener::from_std(std_listener)
+}
+
+/// 把服务器事件转发到 MQTT：维护保留的 now_playing 与 status 主题
+async fn publish_mqtt_event(
+    state: &Arc<ServerState>,
+    mqtt: &crate::settings::MqttSettings,
+    event: &ServerEvent,
+) -> anyhow::Result<()> {
+    let prefix = mqtt.topic_prefix.trim_end_matches('/');
+
+    match event {
+        ServerEvent::StreamStarted {
+            station_id,
+            station_name,
+        } => {
+            let genre = state
+                .stations
+                .read()
+                .await
+                .get(station_id)
+                .map(SiiGenerator::get_genre);
+            let payload = serde_json::json!({
+                "stationId": station_id,
+                "stationName": station_name,
+                "genre": genre,
+            });
+            crate::utils::mqtt::publish(
+                &mqtt.broker,
+                &format!("{}/now_playing", prefix),
+                payload.to_string().as_bytes(),
+                true,
+            )
+            .await?;
+        }
+        ServerEvent::StreamStopped { .. } => {
+            // 还有其他活动流时保留现有 now_playing，全部停止才清空
+            if state.active_streams.read().await.is_empty() {
+                crate::utils::mqtt::publish(
+                    &mqtt.broker,
+                    &format!("{}/now_playing", prefix),
+                    b"{}",
+                    true,
+                )
+                .await?;
+            }
+        }
+        ServerEvent::MetadataUpdated { .. } => {}
+    }
+
+    let status = state.get_status().await;
+    crate::utils::mqtt::publish(
+        &mqtt.broker,
+        &format!("{}/status", prefix),
+        &serde_json::to_vec(&status)?,
+        true,
+    )
+    .await?;
+    Ok(())
+}
+
+/// 服务器事件 SSE 端点：推送流的开始/停止和元数据更新
+async fn handle_events_sse(
+    State(state): State<Arc<ServerState>>,
+) -> Sse<impl tokio_stream::Stream<Item = Result<Event, std::convert::Infallible>>> {
+    use tokio_stream::StreamExt;
+
+    let rx = state.server_events_tx.subscribe();
+    let stream = tokio_stream::wrappers::BroadcastStream::new(rx).filter_map(|event| {
+        event
+            .ok()
+            .and_then(|e| Event::default().json_data(&e).ok())
+            .map(Ok)
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// 爬取进度 SSE 端点，镜像桌面端的 crawl-progress 事件
+async fn handle_crawl_progress_sse(
+    State(state): State<Arc<ServerState>>,
+) -> Sse<impl tokio_stream::Stream<Item = Result<Event, std::convert::Infallible>>> {
+    use tokio_stream::StreamExt;
+
+    let rx = state.crawl_progress_tx.subscribe();
+    let stream = tokio_stream::wrappers::BroadcastStream::new(rx).filter_map(|progress| {
+        progress
+            .ok()
+            .and_then(|p| Event::default().event("crawl-progress").json_data(&p).ok())
+            .map(Ok)
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// HTTP 访问日志
+///
+/// 请求结束后记录路由、电台 ID、状态码和耗时。写进诊断日志
+/// （级别随状态码升高），开启 JSON 行日志时一并落盘。
+async fn log_requests(
+    State(state): State<Arc<ServerState>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let enabled = load_settings_from_file(&state.data_dir).access_log;
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let started = std::time::Instant::now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16();
+    let duration_ms = started.elapsed().as_millis() as u64;
+    log::debug!("{} {} -> {} ({}ms)", method, path, status, duration_ms);
+    if enabled {
+        // /stream/:id 和 /images/:id 的路径参数就是电台 / 频道 ID
+        let station_id = path
+            .strip_prefix("/stream/")
+            .or_else(|| path.strip_prefix("/images/"))
+            .map(|id| id.to_string());
+        let level = if status >= 500 {
+            "error"
+        } else if status >= 400 {
+            "warn"
+        } else {
+            "info"
+        };
+        state.logger.push(
+            level,
+            "http",
+            format!("{} {} -> {} ({}ms)", method, path, status, duration_ms),
+            station_id,
+            None::<String>,
+            None::<String>,
+        );
+    }
+    response
+}
+
+/// 公共端点的按 IP 限流
+///
+/// 只约束 /api/ 和 /stream/ 前缀；固定一分钟窗口计数，超限返回 429。
+/// 未配置上限（0）时完全不记账，本机使用零开销。
+async fn enforce_rate_limit(
+    State(state): State<Arc<ServerState>>,
+    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let limit = load_settings_from_file(&state.data_dir).rate_limit_per_minute;
+    let path = request.uri().path();
+    if limit == 0 || !(path.starts_with("/api/") || path.starts_with("/stream/")) {
+        return next.run(request).await;
+    }
+
+    if state.register_rate_hit(addr.ip(), limit) {
+        return next.run(request).await;
+    }
+    state.logger.warn(
+        "server",
+        format!("IP {} 超出限流（{}/分钟），已拒绝请求", addr.ip(), limit),
+        None::<String>,
+    );
+    (StatusCode::TOO_MANY_REQUESTS, "请求过于频繁，请稍后再试").into_response()
+}
+
+/// 管理路由的 Basic 认证
+///
+/// 未启用时直接放行；启用后把请求头里的凭据与设置比对，
+/// 不匹配时返回 401 并带 WWW-Authenticate，浏览器会弹出登录框。
+async fn require_admin_auth(
+    State(state): State<Arc<ServerState>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let auth = load_settings_from_file(&state.data_dir).admin_auth;
+    if !auth.enabled {
+        return next.run(request).await;
+    }
+
+    use base64::Engine as _;
+    let expected = base64::engine::general_purpose::STANDARD
+        .encode(format!("{}:{}", auth.username, auth.password));
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .map(|credentials| constant_time_eq(credentials.as_bytes(), expected.as_bytes()))
+        .unwrap_or(false);
+    if authorized {
+        return next.run(request).await;
+    }
+
+    state
+        .logger
+        .warn("server", "管理接口认证失败，已拒绝请求", None::<String>);
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Basic realm=\"ouka2-admin\"")],
+        "管理接口需要认证",
+    )
+        .into_response()
+}
+
+/// 恒定时间比较两段字节
+///
+/// 凭据比对不能用普通 `==`：它在第一个不同字节处短路，
+/// 对外暴露服务时的耗时差异可被用来逐字节猜测凭据。
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 通过 HTTP API 停止全部活动流（管理接口）
+async fn handle_stop_streams(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let count = state.active_streams.read().await.len();
+    state.stop_active_streams().await;
+    axum::Json(serde_json::json!({ "stopped": count }))
+}
+
+/// 通过 HTTP API 触发一次爬取（供无界面部署使用）
+async fn handle_crawl_start(State(state): State<Arc<ServerState>>) -> Response {
+    if state.crawl_in_progress.swap(true, Ordering::Relaxed) {
+        return (StatusCode::CONFLICT, "爬取已在进行中").into_response();
+    }
+
+    state.logger.info("crawler", "通过 HTTP API 触发爬取");
+    let task_state = state.clone();
+    tokio::spawn(async move {
+        let crawler = crate::radio::Crawler::new(task_state.data_dir.clone());
+        let progress_state = task_state.clone();
+        let result = crawler
+            .crawl_all(move |progress| {
+                let _ = progress_state.crawl_progress_tx.send(progress);
+            })
+            .await;
+
+        match result {
+            Ok(mut stations) => {
+                task_state.logger.info(
+                    "crawler",
+                    format!("HTTP 触发的爬取完成，共 {} 个电台", stations.len()),
+                );
+                crate::commands::custom::merge_custom_stations(
+                    &task_state.data_dir,
+                    &mut stations,
+                );
+                task_state.load_stations(stations).await;
+            }
+            Err(e) => {
+                task_state.logger.push(
+                    "error",
+                    "crawler",
+                    "HTTP 触发的爬取失败",
+                    None::<String>,
+                    None::<String>,
+                    Some(e.to_string()),
+                );
+            }
+        }
+        task_state.crawl_in_progress.store(false, Ordering::Relaxed);
+    });
+
+    (StatusCode::ACCEPTED, "爬取已开始").into_response()
+}
+
+/// 电台列表 API 条目：电台信息加实时收听状态
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StationApiEntry {
+    #[serde(flatten)]
+    station: Station,
+    /// 当前收听该电台的客户端数
+    listeners: usize,
+    /// 最近一次开始播放的时间
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_played_at: Option<String>,
+    /// 不依赖 FFmpeg 也能播放（MP3/HLS-AAC 直通）
+    ffmpeg_free: bool,
+}
+
+/// 电台列表 API
+async fn handle_stations_api(
+    State(state): State<Arc<ServerState>>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    let base_url = external_base_url(&state, &headers).await;
+
+    // 统计各电台当前活动流数量
+    let mut listeners: HashMap<String, usize> = HashMap::new();
+    {
+        let active_streams = state.active_streams.read().await;
+        for stream in active_streams.values() {
+            *listeners.entry(stream.station_id.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let last_played = state.last_played.read().await;
+    let stations = state.stations.read().await;
+    let list: Vec<_> = stations
+        .values()
+        .map(|s| {
+            let mut station = s.clone();
+            // 添加本地流地址
+            station.mp3_play_url_high = Some(format!("{}/stream/{}", base_url, station.id));
+            let ffmpeg_free = s
+                .get_best_stream_url()
+                .map(|url| {
+                    crate::radio::hls::can_relay_natively(url)
+                        || crate::radio::hls::is_mp3_url(url)
+                })
+                .unwrap_or(false);
+            StationApiEntry {
+                listeners: listeners.get(&station.id).copied().unwrap_or(0),
+                last_played_at: last_played.get(&station.id).cloned(),
+                ffmpeg_free,
+                station,
+            }
+        })
+        .collect();
+
+    axum::Json(list)
+}
+
+/// SII 预览 API
+///
+/// 返回按当前设置生成 SII 时的最终条目列表（排序、显示名、下标
+/// 与生成文件逐行对应），前端预览和外部工具读它即可与
+/// `SiiGenerator` 保持同步，不必自己复刻排序逻辑。
+async fn handle_sii_json(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let settings = load_settings_from_file(&state.data_dir);
+    let port = *state.port.read().await;
+
+    // 与安装 SII 的命令走同一份电台来源和生成器配置
+    let mut stations = crate::radio::Crawler::new(state.data_dir.clone())
+        .load_stations()
+        .unwrap_or_default();
+    crate::commands::custom::merge_custom_stations(&state.data_dir, &mut stations);
+    crate::commands::vtc::merge_vtc_stations(&state.data_dir, &mut stations);
+    crate::commands::audiobook::merge_audiobook_stations(&state.data_dir, &mut stations);
+    crate::commands::config::remove_disabled_stations(&state.data_dir, &mut stations);
+    crate::commands::config::append_genre_channels(&state.data_dir, &mut stations);
+
+    let mut generator = SiiGenerator::new("127.0.0.1", port)
+        .with_pin_central_stations(settings.pin_central_stations)
+        .with_default_bitrate(settings.transcode_bitrate_kbps)
+        .with_encoding(settings.sii_encoding);
+    if settings.sii_order_by_play_count {
+        generator = generator.with_play_counts(load_play_counts(&state.data_dir));
+    }
+    axum::Json(generator.plan(&stations))
+}
+
+/// 省份地理统计 API
+///
+/// 返回各省份的电台数量和省份锚点坐标，
+/// 前端据此绘制中国地图热力视图。
+async fn handle_provinces_geo(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let stations: Vec<Station> = state.stations.read().await.values().cloned().collect();
+    axum::Json(crate::radio::get_province_geo_stats(&stations))
+}
+
+/// 播报插播 API
+///
+/// 外部工具（车队调度、导航等）上传一段音频，转码为与电台输出
+/// 一致的 MP3 后插入所有活动流，期间电台声音被完全压下，播完
+/// 自动恢复。文本播报请先用系统语音合成导出音频再上传。
+async fn handle_announce(
+    State(state): State<Arc<ServerState>>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> Response {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    if content_type.starts_with("application/json") {
+        return (
+            StatusCode::NOT_IMPLEMENTED,
+            "文本播报暂未支持，请先用系统语音合成导出音频后直接上传音频数据",
+        )
+            .into_response();
+    }
+    if body.is_empty() {
+        return (StatusCode::BAD_REQUEST, "播报音频不能为空").into_response();
+    }
+    if state.active_streams.read().await.is_empty() {
+        return (StatusCode::CONFLICT, "当前没有活动的播放流").into_response();
+    }
+
+    let settings = load_settings_from_file(&state.data_dir);
+    let bitrate = state.transcode_bitrate(&settings);
+
+    // FFmpeg 需要可寻址的输入来探测格式，经由临时文件转码
+    let input_path = state.data_dir.join("announce_upload.tmp");
+    if let Err(e) = std::fs::write(&input_path, &body) {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("写入播报临时文件失败: {}", e),
+        )
+            .into_response();
+    }
+    let result = transcode_announcement(&state.ffmpeg_path, &input_path, bitrate).await;
+    let _ = std::fs::remove_file(&input_path);
+
+    match result {
+        Ok(clip) => {
+            let size = clip.len();
+            *state.announcement_clip.write().await = Some(Arc::new(clip));
+            state
+                .announcement_seq
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            state.logger.info(
+                "announce",
+                format!("收到播报音频，转码后 {} 字节，已插入活动流", size),
+            );
+            (StatusCode::OK, "播报已插入活动流").into_response()
+        }
+        Err(e) => {
+            state
+                .logger
+                .warn("announce", "播报音频转码失败", Some(e.clone()));
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("播报音频转码失败: {}", e),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// 用一次性 FFmpeg 进程把播报音频转码为指定码率的 MP3
+async fn transcode_announcement(
+    ffmpeg_path: &std::path::Path,
+    input: &std::path::Path,
+    bitrate: u32,
+) -> Result<Vec<u8>, String> {
+    let mut cmd = tokio::process::Command::new(ffmpeg_path);
+    cmd.args(["-hide_banner", "-i"])
+        .arg(input)
+        .args(["-vn", "-acodec", "libmp3lame", "-ar", "44100", "-ac", "2"])
+        .args(["-b:a", &format!("{}k", bitrate), "-f", "mp3", "-"])
+        .stdin(Stdio::null());
+
+    // Windows: 隐藏控制台窗口
+    #[cfg(target_os = "windows")]
+    {
+        #[allow(unused_imports)]
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd.output().await.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .last()
+            .unwrap_or("FFmpeg 退出异常")
+            .to_string());
+    }
+    if output.stdout.is_empty() {
+        return Err("转码输出为空".to_string());
+    }
+    Ok(output.stdout)
 }
 
-/// 健康检查端点
-async fn handle_health(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
-    state.logger.info("server", "收到健康检查请求");
-    let status = state.get_status().await;
-    axum::Json(status)
+/// 语音检索请求体，query 为系统语音 API 识别出的文本
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VoiceSearchRequest {
+    query: String,
 }
 
-/// 电台列表 API
-async fn handle_stations_api(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+/// 语音检索结果条目
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VoiceSearchMatch {
+    #[serde(flatten)]
+    station: Station,
+    /// 匹配得分，越大越接近
+    score: u32,
+    /// 本机播放地址，手机端可直接点播
+    stream_url: String,
+}
+
+/// 语音检索返回的最大条数
+const VOICE_SEARCH_LIMIT: usize = 5;
+
+/// 语音检索匹配得分，0 表示不匹配
+///
+/// 语音识别结果常带指令词（"播放""切到"之类），先剥掉常见前缀，
+/// 再按宽松的包含关系打分；转写后的英文名兜住拼音 / 英文输入。
+pub(crate) fn voice_search_score(station: &Station, query: &str) -> u32 {
+    let mut text = query.trim().to_lowercase();
+    for prefix in ["播放", "我想听", "我要听", "来点", "切到", "换到", "收听"] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            text = rest.trim().to_string();
+        }
+    }
+    if text.is_empty() {
+        return 0;
+    }
+
+    let name = station.name.to_lowercase();
+    if name == text {
+        return 100;
+    }
+    if name.contains(&text) {
+        return 80;
+    }
+    if text.contains(&name) {
+        return 70;
+    }
+
+    // "浙江交通"这类省份 + 关键词的组合说法
+    let province = station.province.to_lowercase();
+    if !province.is_empty() && text.contains(&province) {
+        let rest = text.replace(&province, "");
+        let rest = rest.trim();
+        if !rest.is_empty() && name.contains(rest) {
+            return 75;
+        }
+        return 30;
+    }
+
+    // 转写英文名忽略空格比较，容忍拼读差异
+    let english = crate::radio::SiiGenerator::to_english_name(&station.name)
+        .to_lowercase()
+        .replace(' ', "");
+    let compact = text.replace(' ', "");
+    if english.contains(&compact) || compact.contains(&english) {
+        return 60;
+    }
+
+    0
+}
+
+/// 语音检索电台 API
+///
+/// 接收语音识别出的文本，按电台名 / 省份 / 转写英文名模糊匹配，
+/// 返回得分最高的几个电台及其本机播放地址，供驾驶中免手动换台。
+async fn handle_voice_search(
+    State(state): State<Arc<ServerState>>,
+    headers: axum::http::HeaderMap,
+    axum::Json(request): axum::Json<VoiceSearchRequest>,
+) -> Response {
+    if request.query.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "检索文本不能为空").into_response();
+    }
+
+    let base_url = external_base_url(&state, &headers).await;
     let stations = state.stations.read().await;
-    let port = *state.port.read().await;
-    let list: Vec<_> = stations
+    let mut matches: Vec<(u32, Station)> = stations
         .values()
-        .map(|s| {
-            let mut s = s.clone();
-            // 添加本地流地址
-            s.mp3_play_url_high = Some(format!("http://127.0.0.1:{}/stream/{}", port, s.id));
-            s
+        .filter_map(|station| {
+            let score = voice_search_score(station, &request.query);
+            (score > 0).then(|| (score, station.clone()))
         })
         .collect();
+    // 得分相同的按 ID 排序，保证结果稳定
+    matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.id.cmp(&b.1.id)));
+    matches.truncate(VOICE_SEARCH_LIMIT);
 
-    axum::Json(list)
+    let list: Vec<VoiceSearchMatch> = matches
+        .into_iter()
+        .map(|(score, station)| VoiceSearchMatch {
+            stream_url: format!("{}/stream/{}", base_url, station.id),
+            score,
+            station,
+        })
+        .collect();
+
+    axum::Json(list).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 创建独立的临时数据目录
+    fn temp_data_dir(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "ouka2-stream-test-{}-{}",
+            tag,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 写入一个持续输出伪音频数据的假 ffmpeg 脚本
+    #[cfg(unix)]
+    fn write_fake_ffmpeg(dir: &std::path::Path) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("fake-ffmpeg.sh");
+        std::fs::write(
+            &path,
+            "#!/bin/sh\nwhile :; do printf 'AUDIODATA'; sleep 0.05; done\n",
+        )
+        .unwrap();
+        let mut perms = std::fs::metadata(&path).unwrap().permissions();
+        perms.set_mode(0o755);
+        std::fs::set_permissions(&path, perms).unwrap();
+        path
+    }
+
+    /// 不需要访问网络的自定义测试电台
+    fn test_station(id: &str) -> Station {
+        Station {
+            id: id.to_string(),
+            name: "测试电台".to_string(),
+            subtitle: String::new(),
+            image: String::new(),
+            province: "测试".to_string(),
+            play_url_low: Some("http://127.0.0.1:9/fake.m3u8".to_string()),
+            mp3_play_url_low: None,
+            mp3_play_url_high: None,
+            is_custom: true,
+            bitrate: None,
+            language: None,
+            url_expires_at: None,
+        }
+    }
+
+    /// 启动一个加载了单个测试电台（custom:test）的服务器
+    #[cfg(unix)]
+    async fn start_test_server(
+        base_port: u16,
+        data_dir: &std::path::Path,
+    ) -> (StreamServer, Arc<ServerState>) {
+        let ffmpeg = write_fake_ffmpeg(data_dir);
+        let mut server = StreamServer::new(
+            base_port,
+            ffmpeg,
+            data_dir.to_path_buf(),
+            DiagnosticLogger::new(),
+        );
+        let state = server.state();
+        state.load_stations(vec![test_station("custom:test")]).await;
+        server.start().await.unwrap();
+        (server, state)
+    }
+
+    /// 轮询等待活动流清空，最多 5 秒
+    #[cfg(unix)]
+    async fn wait_for_no_active_streams(state: &Arc<ServerState>) -> bool {
+        for _ in 0..50 {
+            if state.active_streams.read().await.is_empty() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        false
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stream_serves_audio_and_cleans_up_on_disconnect() {
+        let data_dir = temp_data_dir("serve");
+        // 保温期设为 0，断开后应立即清理
+        std::fs::write(data_dir.join("settings.json"), r#"{"keepAliveGraceSecs":0}"#).unwrap();
+        let (mut server, state) = start_test_server(43710, &data_dir).await;
+        let port = *state.port.read().await;
+
+        let mut resp = reqwest::get(format!("http://127.0.0.1:{}/stream/custom:test", port))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "audio/mpeg"
+        );
+
+        let chunk = resp.chunk().await.unwrap().unwrap();
+        assert!(!chunk.is_empty());
+        assert_eq!(state.active_streams.read().await.len(), 1);
+
+        drop(resp);
+        assert!(wait_for_no_active_streams(&state).await, "断开后进程未清理");
+
+        server.stop().await;
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn reconnect_within_grace_reuses_warm_pipeline() {
+        let data_dir = temp_data_dir("warm");
+        std::fs::write(data_dir.join("settings.json"), r#"{"keepAliveGraceSecs":10}"#).unwrap();
+        let (mut server, state) = start_test_server(43720, &data_dir).await;
+        let port = *state.port.read().await;
+        let url = format!("http://127.0.0.1:{}/stream/custom:test", port);
+
+        let mut resp = reqwest::get(&url).await.unwrap();
+        resp.chunk().await.unwrap().unwrap();
+        let pid_before = state
+            .active_streams
+            .read()
+            .await
+            .values()
+            .map(|stream| stream.process_id)
+            .next()
+            .unwrap();
+
+        drop(resp);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        // 保温期内重连应接入同一个进程
+        let mut resp = reqwest::get(&url).await.unwrap();
+        resp.chunk().await.unwrap().unwrap();
+        let pid_after = state
+            .active_streams
+            .read()
+            .await
+            .values()
+            .map(|stream| stream.process_id)
+            .next()
+            .unwrap();
+        assert_eq!(pid_before, pid_after);
+
+        drop(resp);
+        server.stop().await;
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn same_station_clients_share_one_transcode_process() {
+        let data_dir = temp_data_dir("share");
+        std::fs::write(data_dir.join("settings.json"), r#"{"keepAliveGraceSecs":0}"#).unwrap();
+        let (mut server, state) = start_test_server(43760, &data_dir).await;
+        let port = *state.port.read().await;
+        let url = format!("http://127.0.0.1:{}/stream/custom:test", port);
+
+        let mut first = reqwest::get(&url).await.unwrap();
+        first.chunk().await.unwrap().unwrap();
+
+        let mut second = reqwest::get(&url).await.unwrap();
+        assert_eq!(second.status(), StatusCode::OK);
+        second.chunk().await.unwrap().unwrap();
+
+        // 第二个客户端接入同一个进程，不重复转码
+        assert_eq!(state.active_streams.read().await.len(), 1);
+
+        // 先断开一个，剩下的客户端继续收到数据
+        drop(first);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        second.chunk().await.unwrap().unwrap();
+        assert_eq!(state.active_streams.read().await.len(), 1);
+
+        drop(second);
+        assert!(
+            wait_for_no_active_streams(&state).await,
+            "全部断开后进程未清理"
+        );
+
+        server.stop().await;
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn pause_and_resume_suspend_stream_without_killing() {
+        let data_dir = temp_data_dir("pause");
+        std::fs::write(data_dir.join("settings.json"), r#"{"keepAliveGraceSecs":0}"#).unwrap();
+        let (mut server, state) = start_test_server(43745, &data_dir).await;
+        let port = *state.port.read().await;
+
+        let mut resp = reqwest::get(format!("http://127.0.0.1:{}/stream/custom:test", port))
+            .await
+            .unwrap();
+        resp.chunk().await.unwrap().unwrap();
+        let pid_before = state
+            .active_streams
+            .read()
+            .await
+            .values()
+            .map(|stream| stream.process_id)
+            .next()
+            .unwrap();
+
+        assert_eq!(state.pause_all_streams().await, 1);
+        // 管线里可能还有已缓冲的数据，排空后输出应当停住
+        let mut stalled = false;
+        for _ in 0..50 {
+            match tokio::time::timeout(Duration::from_millis(300), resp.chunk()).await {
+                Ok(Ok(Some(_))) => {}
+                Ok(_) => break, // 流被意外关闭，由下面的断言兜底
+                Err(_) => {
+                    stalled = true;
+                    break;
+                }
+            }
+        }
+        assert!(stalled, "暂停后输出未停止");
+
+        // 恢复后继续出声，且仍是同一个进程（没有重连周期）
+        assert_eq!(state.resume_all_streams().await, 1);
+        let chunk = tokio::time::timeout(Duration::from_secs(3), resp.chunk())
+            .await
+            .expect("恢复后未继续输出")
+            .unwrap()
+            .unwrap();
+        assert!(!chunk.is_empty());
+        let pid_after = state
+            .active_streams
+            .read()
+            .await
+            .values()
+            .map(|stream| stream.process_id)
+            .next()
+            .unwrap();
+        assert_eq!(pid_before, pid_after);
+
+        drop(resp);
+        server.stop().await;
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn admin_routes_enforce_basic_auth_when_enabled() {
+        let data_dir = temp_data_dir("auth");
+        std::fs::write(
+            data_dir.join("settings.json"),
+            r#"{"adminAuth":{"enabled":true,"username":"admin","password":"secret"}}"#,
+        )
+        .unwrap();
+        let (mut server, state) = start_test_server(43750, &data_dir).await;
+        let port = *state.port.read().await;
+        let client = reqwest::Client::new();
+
+        // 音频路由不受认证影响
+        let resp = client
+            .get(format!("http://127.0.0.1:{}/api/stations", port))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // 管理路由：无凭据 401，凭据正确放行
+        let resp = client
+            .post(format!("http://127.0.0.1:{}/api/streams/stop", port))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert!(resp.headers().contains_key(header::WWW_AUTHENTICATE));
+
+        let resp = client
+            .post(format!("http://127.0.0.1:{}/api/streams/stop", port))
+            .basic_auth("admin", Some("secret"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        server.stop().await;
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn rate_limit_rejects_excess_api_requests() {
+        let data_dir = temp_data_dir("ratelimit");
+        std::fs::write(data_dir.join("settings.json"), r#"{"rateLimitPerMinute":3}"#).unwrap();
+        let (mut server, state) = start_test_server(43755, &data_dir).await;
+        let port = *state.port.read().await;
+        let url = format!("http://127.0.0.1:{}/api/stations", port);
+
+        for _ in 0..3 {
+            let resp = reqwest::get(&url).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        let resp = reqwest::get(&url).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // 限流不影响前缀之外的端点
+        let resp = reqwest::get(format!("http://127.0.0.1:{}/health", port))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        server.stop().await;
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unknown_station_and_status_endpoints() {
+        let data_dir = temp_data_dir("endpoints");
+        let (mut server, state) = start_test_server(43730, &data_dir).await;
+        let port = *state.port.read().await;
+
+        let resp = reqwest::get(format!("http://127.0.0.1:{}/stream/missing", port))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let status: ServerStatus = reqwest::get(format!("http://127.0.0.1:{}/health", port))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(status.running);
+        assert_eq!(status.total_stations, 1);
+
+        let metrics = reqwest::get(format!("http://127.0.0.1:{}/metrics", port))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(metrics.contains("ouka2_active_streams"));
+
+        server.stop().await;
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn forwarded_headers_rewrite_playlist_urls() {
+        let data_dir = temp_data_dir("forwarded");
+        let (mut server, state) = start_test_server(43735, &data_dir).await;
+        let port = *state.port.read().await;
+
+        // 带反向代理头：播放列表里的地址应指向对外域名
+        let playlist = reqwest::Client::new()
+            .get(format!("http://127.0.0.1:{}/playlist.xspf", port))
+            .header("X-Forwarded-Host", "radio.example.com")
+            .header("X-Forwarded-Proto", "https")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(playlist.contains("https://radio.example.com/stream/"));
+
+        // 不带代理头：回退到本机回环地址
+        let plain = reqwest::get(format!("http://127.0.0.1:{}/playlist.xspf", port))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(plain.contains(&format!("http://127.0.0.1:{}/stream/", port)));
+
+        server.stop().await;
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn transcode_budget_leaves_headroom() {
+        let budget = max_concurrent_transcodes();
+        assert!(budget >= 2);
+        let cores = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4);
+        assert!(budget <= cores.max(2));
+    }
+
+    #[test]
+    fn preferred_encoder_falls_back_when_lame_missing() {
+        let caps = crate::utils::ffmpeg::EncoderCapabilities {
+            libmp3lame: false,
+            libshine: true,
+            aac: false,
+            libopus: true,
+        };
+        assert_eq!(OutputCodec::Mp3.preferred_encoder(&caps), Some("libshine"));
+        assert_eq!(OutputCodec::AdtsAac.preferred_encoder(&caps), None);
+        assert_eq!(
+            OutputCodec::OggOpus.preferred_encoder(&caps),
+            Some("libopus")
+        );
+    }
+
+    #[test]
+    fn voice_search_scores_common_phrasings() {
+        let mut station = test_station("custom:test");
+        station.name = "浙江交通之声".to_string();
+        station.province = "浙江".to_string();
+
+        assert_eq!(voice_search_score(&station, "浙江交通之声"), 100);
+        assert_eq!(voice_search_score(&station, "播放浙江交通"), 80);
+        assert_eq!(voice_search_score(&station, "新疆新闻"), 0);
+    }
+
+    #[test]
+    fn truncate_utf8_keeps_char_boundary() {
+        assert_eq!(truncate_utf8("中国之声", 7), "中国");
+        assert_eq!(truncate_utf8("abc", 7), "abc");
+    }
+
+    #[test]
+    fn parse_ebur128_levels_reads_momentary_and_short_term() {
+        let line = "[Parsed_ebur128_0 @ 0x55] t: 2.10267  TARGET:-23 LUFS    M: -17.9 S: -18.4     I: -18.0 LUFS       LRA: 0.1 LU";
+        assert_eq!(parse_ebur128_levels(line), Some((-17.9, -18.4)));
+        assert_eq!(parse_ebur128_levels("size= 123kB bitrate= 128kbps"), None);
+    }
+
+    #[test]
+    fn output_codec_parses_request_suffix() {
+        assert_eq!(
+            OutputCodec::from_request_id("1600"),
+            (OutputCodec::Mp3, "1600")
+        );
+        assert_eq!(
+            OutputCodec::from_request_id("1600.aac"),
+            (OutputCodec::AdtsAac, "1600")
+        );
+        assert_eq!(
+            OutputCodec::from_request_id("bili_BV1xx/123.opus"),
+            (OutputCodec::OggOpus, "bili_BV1xx/123")
+        );
+    }
 }
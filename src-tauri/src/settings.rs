@@ -0,0 +1,641 @@
+//! 应用设置
+//!
+//! 以 JSON 文件形式持久化在应用数据目录，命令层和流媒体服务器都会读取。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 设置文件名
+const SETTINGS_FILE: &str = "settings.json";
+
+/// 当前设置文件的结构版本
+///
+/// 结构不兼容调整时递增，并在 `migrate_settings` 里补一个迁移步骤。
+pub const CURRENT_SETTINGS_VERSION: u32 = 2;
+
+/// 应用设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppSettings {
+    /// 设置文件结构版本，加载时自动逐版本迁移
+    pub schema_version: u32,
+    /// 生成 SII 时是否将央广主频率（中国之声等）固定在列表顶部
+    pub pin_central_stations: bool,
+    /// SII 文件输出编码
+    pub sii_encoding: SiiEncoding,
+    /// 生成 SII 时按本机播放次数降序排列电台
+    ///
+    /// 常听的电台会随时间自动排到游戏内列表前面；
+    /// 央广置顶（如开启）仍然优先于此排序。
+    pub sii_order_by_play_count: bool,
+    /// icy-name 是否使用转写后的英文名（否则回退为 URL 编码的中文名）
+    pub icy_ascii_names: bool,
+    /// icy-name 最大字节数，超出部分按字符边界安全截断
+    pub icy_name_max_len: usize,
+    /// 各电台音量增益（dB），key 为电台 ID，通过 FFmpeg volume 滤镜应用
+    pub station_gains: HashMap<String, f32>,
+    /// 是否启用全局限幅器（FFmpeg alimiter），防止广告/台呼突然炸耳
+    pub enable_limiter: bool,
+    /// 转码输出码率（kbps），同时用于 SII 条目中显示的码率
+    pub transcode_bitrate_kbps: u32,
+    /// 客户端断开后 FFmpeg 保温多少秒等待重新接入，0 表示立即停止
+    pub keep_alive_grace_secs: u64,
+    /// 输出缓冲秒数（0 表示关闭）
+    ///
+    /// 在 FFmpeg 输出和 HTTP 响应之间预填充若干秒音频，
+    /// 用少量延迟换取对上游 HLS 分片抖动的吸收，减少可闻的卡顿。
+    pub output_buffer_secs: u64,
+    /// 定时插播虚拟频道配置
+    pub interrupt_channel: InterruptChannelSettings,
+    /// 环游中国虚拟电台配置
+    pub tour_channel: TourChannelSettings,
+    /// 流派聚合虚拟电台配置
+    pub genre_channels: GenreChannelSettings,
+    /// MQTT 集成配置
+    pub mqtt: MqttSettings,
+    /// 是否启用 Discord Rich Presence（把正在收听的电台显示到 Discord 状态）
+    pub discord_rich_presence: bool,
+    /// OBS 标题文件路径，空字符串表示不输出
+    ///
+    /// 正在播放的电台变化时把一行文本写到该文件，
+    /// OBS 的文本源指向它即可显示当前电台，无需额外工具。
+    pub obs_title_file: String,
+    /// 启动应用时自动启动流媒体服务器并恢复上次会话
+    pub auto_start_server: bool,
+    /// 主流媒体服务器监听端口
+    ///
+    /// 被其他程序占用时启动仍会自动向后顺延找可用端口；
+    /// 这里配置的是首选端口，生成 SII 也以实际监听端口为准。
+    pub server_port: u16,
+    /// 是否监听所有网卡（0.0.0.0），供局域网内其他设备收听
+    ///
+    /// 默认只监听本机回环地址，仅本机和游戏可访问；开启后手机、
+    /// 音箱等设备可直接打开内置网页播放器和流端点（主实例和
+    /// 额外实例都生效）。对局域网开放时建议配合管理接口认证与限流。
+    pub listen_on_lan: bool,
+    /// 对外的服务器基础地址，空字符串表示自动推断
+    ///
+    /// 形如 `https://radio.example.com`（不带末尾斜杠），经 nginx / Caddy
+    /// 反向代理对外时填写。播放列表和 API 中生成的绝对地址会优先用它；
+    /// 未配置时按请求的 X-Forwarded-Host / X-Forwarded-Proto 推断，
+    /// 最后回退到本机回环地址。
+    pub external_url: String,
+    /// 管理接口的 Basic 认证配置
+    pub admin_auth: AdminAuthSettings,
+    /// 公共端点（/api/*、/stream/*）按 IP 的每分钟请求上限，0 表示不限流
+    ///
+    /// 端口转发到公网做远程收听时建议开启，防止恶意刷请求
+    /// 拖垮转码进程和上游接口。
+    pub rate_limit_per_minute: u64,
+    /// 是否记录 HTTP 访问日志
+    ///
+    /// 每个请求结束后把路由、电台 ID、状态码和耗时写进诊断日志
+    /// （开启 JSON 行日志时一并落盘），排查"游戏连不上"时
+    /// 可以直接对照请求时间线。
+    pub access_log: bool,
+    /// 云听签名 key 覆盖，空字符串表示使用内置常量
+    ///
+    /// 云听轮换 MD5 签名 key 时所有接口会集体失败；在应用更新
+    /// 跟上之前，把新 key（32 位十六进制）填到这里即可热修。
+    pub yunting_api_key: String,
+    /// 云听 key 清单地址，空字符串表示不启用
+    ///
+    /// 指向一个返回 `{"apiKey": "..."}` 的 JSON 地址（如 GitHub raw），
+    /// 通过"刷新云听 key"命令拉取并应用，无需手动传抄新 key。
+    pub yunting_key_manifest_url: String,
+    /// 云听镜像地址列表，空列表表示只用内置官方地址
+    ///
+    /// `ytmsout.radio.cn` 在个别运营商下会被地理封锁或长时间超时；
+    /// 填入镜像地址（形如 `https://mirror.example.com`，不带末尾斜杠）
+    /// 后按顺序自动故障转移，官方地址始终作为最后兜底。
+    pub yunting_mirror_urls: Vec<String>,
+    /// B 站音频流的音质偏好
+    pub bilibili_audio_quality: BilibiliAudioQuality,
+    /// B 站 CDN 偏好配置
+    pub bilibili_cdn: BilibiliCdnSettings,
+    /// B 站搜索限定的分区 tid，0 表示全站
+    ///
+    /// 限定到曲艺（136）、音乐（3）等分区能减少只是提到关键词的
+    /// vlog 之类跑题结果；分区编号见 B 站开放的 tid 列表。
+    pub bilibili_search_tid: u32,
+    /// 启用弹幕高能起播的 B 站电台 ID 列表（实验性）
+    ///
+    /// 播放时抓取弹幕密度曲线，从最密集的片段开始播放，
+    /// 跳过长综艺上传常见的空场开头。
+    pub bilibili_highlight_stations: Vec<String>,
+    /// 流输出高级调优
+    pub stream_tuning: StreamTuningSettings,
+    /// 录音目录磁盘配额（MB），超出时自动删除最旧的录音，0 表示不限制
+    pub recordings_quota_mb: u64,
+    /// 额外流媒体服务器实例，主实例之外按需启用
+    pub extra_servers: Vec<ExtraServerSettings>,
+    /// 车队共享电台列表同步配置
+    pub vtc_sync: VtcSyncSettings,
+    /// 跟随游戏：检测到欧卡2 / 美卡启动时自动启动服务器，游戏退出后自动停止
+    pub follow_game: bool,
+    /// 连续空闲多少分钟后自动停止流媒体服务器，0 表示不自动停止
+    ///
+    /// 停止后端口随之释放；被空闲停止的服务器会在用户下一次
+    /// 相关操作时自动拉起。
+    pub idle_stop_minutes: u64,
+    /// 端口变化时自动重新生成并安装 SII 到游戏目录
+    ///
+    /// 端口被占用自动切换后，游戏里的 SII 仍指向旧地址会导致电台
+    /// 集体失效；开启后服务器启动时检测到不一致就直接重装。
+    pub auto_reinstall_sii: bool,
+    /// 流地址解析超时（秒），0 表示不限制
+    ///
+    /// 云听接口偶尔会慢到游戏先放弃；超时后立即回退缓存地址出声，
+    /// 同时在后台继续刷新，下次播放就能拿到新地址。
+    pub resolve_timeout_secs: u64,
+    /// 是否把诊断日志同时写成 JSON 行文件
+    ///
+    /// 每条日志一行 JSON（含 level/module/stationId 等字段），写到数据
+    /// 目录的 diagnostics.jsonl，方便脚本分析或附在问题反馈里。
+    pub json_log: bool,
+    /// 崩溃上报端点，空字符串表示不开启
+    ///
+    /// 填写后 panic 时会把回溯和最近日志写成崩溃报告，
+    /// 下次启动时上传到该地址。
+    pub crash_report_url: String,
+    /// 是否启用音频电平监测
+    ///
+    /// 在转码链路上挂 FFmpeg ebur128 滤镜，把响度值作为事件推给前端，
+    /// UI 可以用电平表确认声音确实在流动（切进游戏前最后看一眼）。
+    pub level_meter: bool,
+    /// 流水印（台名口播）混音配置
+    pub watermark: WatermarkSettings,
+}
+
+/// 流水印混音配置
+///
+/// 周期性把一段台名口播音频低音量混入转码输出，供直播场景
+/// 标识来源。实现方式：口播片段尾部补齐静音到间隔长度，
+/// 循环输入后与电台声音 amix 混合。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatermarkSettings {
+    /// 是否启用
+    pub enabled: bool,
+    /// 默认水印音频路径；为空时只有配置了专属片段的电台有水印
+    pub clip_path: String,
+    /// 各电台专属水印音频路径，key 为电台 ID
+    pub station_clips: HashMap<String, String>,
+    /// 水印重复间隔（秒）
+    pub interval_secs: u64,
+    /// 水印音量（0.0 - 1.0）
+    pub volume: f32,
+}
+
+impl Default for WatermarkSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            clip_path: String::new(),
+            station_clips: HashMap::new(),
+            interval_secs: 300,
+            volume: 0.35,
+        }
+    }
+}
+
+/// 管理接口的 Basic 认证配置
+///
+/// 流媒体服务器的控制类端点（触发爬取、下载 SII、停止流等）
+/// 单独成组；对局域网开放音频服务时启用认证，别人能听但不能操作。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AdminAuthSettings {
+    /// 是否启用
+    pub enabled: bool,
+    /// 用户名
+    pub username: String,
+    /// 密码
+    pub password: String,
+}
+
+/// SII 文件输出编码
+///
+/// 个别旧版游戏或配置环境对 UTF-8 中文支持不佳，
+/// 可以改用带 BOM 的 UTF-8 或纯 ASCII（电台名转写为英文）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SiiEncoding {
+    /// 标准 UTF-8（默认）
+    #[default]
+    Utf8,
+    /// 带 BOM 的 UTF-8
+    Utf8Bom,
+    /// 严格 ASCII，电台名用转写后的英文名
+    AsciiStrict,
+}
+
+/// 额外流媒体服务器实例配置
+///
+/// 主实例服务游戏；额外实例监听独立端口，可覆盖转码码率，
+/// 用于局域网收听等与游戏档位不同的场景。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExtraServerSettings {
+    /// 是否启用
+    pub enabled: bool,
+    /// 监听端口
+    pub port: u16,
+    /// 转码码率覆盖（kbps），空缺时使用全局设置
+    pub transcode_bitrate_kbps: Option<u32>,
+    /// 显示名（如"客厅音箱"）
+    pub label: String,
+}
+
+impl Default for ExtraServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 3100,
+            transcode_bitrate_kbps: None,
+            label: String::new(),
+        }
+    }
+}
+
+/// 车队共享电台列表同步配置
+///
+/// 定时从车队维护的 URL（如 GitHub raw JSON）拉取一份电台列表
+/// 并合并到本地，让车队成员游戏内的电台列表保持一致。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VtcSyncSettings {
+    /// 是否启用
+    pub enabled: bool,
+    /// 共享列表地址，返回 Station 数组的 JSON
+    pub url: String,
+    /// 同步间隔（分钟）
+    pub interval_minutes: u64,
+    /// ID 冲突时的处理策略
+    pub conflict_policy: VtcConflictPolicy,
+}
+
+impl Default for VtcSyncSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            interval_minutes: 60,
+            conflict_policy: VtcConflictPolicy::default(),
+        }
+    }
+}
+
+/// 车队列表与本地电台 ID 冲突时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VtcConflictPolicy {
+    /// 保留本地版本（默认）
+    #[default]
+    PreferLocal,
+    /// 用车队版本覆盖本地
+    PreferRemote,
+}
+
+/// 定时插播虚拟频道配置
+///
+/// 正常播放基础电台，每隔 `interval_minutes` 切到插播电台
+/// （如交通广播）播放 `cutin_minutes` 分钟后切回。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InterruptChannelSettings {
+    /// 是否启用
+    pub enabled: bool,
+    /// 基础电台 ID
+    pub base_station_id: String,
+    /// 插播电台 ID
+    pub cutin_station_id: String,
+    /// 每隔多少分钟插播一次
+    pub interval_minutes: u64,
+    /// 每次插播持续多少分钟
+    pub cutin_minutes: u64,
+}
+
+impl Default for InterruptChannelSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_station_id: String::new(),
+            cutin_station_id: String::new(),
+            interval_minutes: 30,
+            cutin_minutes: 3,
+        }
+    }
+}
+
+/// 环游中国虚拟电台配置
+///
+/// 按地理顺序每个省份轮播一个电台，长途驾驶变成一场穿越中国的声音旅行。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TourChannelSettings {
+    /// 是否启用
+    pub enabled: bool,
+    /// 每个电台播放多少分钟
+    pub minutes_per_station: u64,
+}
+
+impl Default for TourChannelSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            minutes_per_station: 10,
+        }
+    }
+}
+
+/// 流派聚合虚拟电台配置
+///
+/// 把同一流派的所有电台（如全部交通广播）聚合成一个轮播电台，
+/// 在游戏里只占一个 SII 条目，保持电台列表紧凑。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GenreChannelSettings {
+    /// 是否启用
+    pub enabled: bool,
+    /// 每个电台播放多少分钟
+    pub minutes_per_station: u64,
+}
+
+impl Default for GenreChannelSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            minutes_per_station: 15,
+        }
+    }
+}
+
+/// B 站音频流音质偏好
+///
+/// 默认总是选最高音质；网络不稳时可以手动降档，
+/// 或用 auto 根据最近实测吞吐自动选择。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BilibiliAudioQuality {
+    /// 最高音质（默认）
+    #[default]
+    High,
+    /// 中间档
+    Medium,
+    /// 最低音质
+    Low,
+    /// 根据最近实测吞吐自动选择
+    Auto,
+}
+
+/// 流输出高级调优
+///
+/// 默认值适合大多数环境；有人反馈更大的块能消除卡顿，
+/// 也有人要最低延迟，因此把块大小和套接字选项开放出来。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StreamTuningSettings {
+    /// 读取 FFmpeg 输出的块大小（字节），取值范围 1024-65536
+    pub read_chunk_bytes: usize,
+    /// 是否对客户端连接启用 TCP_NODELAY（关闭 Nagle 算法，降低延迟）
+    pub tcp_nodelay: bool,
+    /// 套接字发送缓冲大小（字节），0 表示使用系统默认
+    pub send_buffer_bytes: usize,
+}
+
+impl Default for StreamTuningSettings {
+    fn default() -> Self {
+        Self {
+            read_chunk_bytes: 4096,
+            tcp_nodelay: true,
+            send_buffer_bytes: 0,
+        }
+    }
+}
+
+/// B 站 CDN 偏好配置
+///
+/// 不同 CDN（upos、mcdn 等）在不同运营商下速度差异很大。
+/// 按主机名子串匹配：偏好的地址优先尝试，避开的放到最后。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BilibiliCdnSettings {
+    /// 优先尝试的 CDN 主机名子串（如 `upos`）
+    pub preferred_patterns: Vec<String>,
+    /// 尽量避开的 CDN 主机名子串（如 `mcdn`）
+    pub avoided_patterns: Vec<String>,
+}
+
+/// MQTT 集成配置
+///
+/// 把正在播放的电台和服务器状态发布到 MQTT broker（保留消息），
+/// 供 Home Assistant 等智能家居面板订阅展示或按流派触发自动化。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MqttSettings {
+    /// 是否启用
+    pub enabled: bool,
+    /// broker 地址（host:port）
+    pub broker: String,
+    /// 主题前缀，如 `ouka2` 则发布到 `ouka2/now_playing`
+    pub topic_prefix: String,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            broker: "127.0.0.1:1883".to_string(),
+            topic_prefix: "ouka2".to_string(),
+        }
+    }
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            schema_version: CURRENT_SETTINGS_VERSION,
+            pin_central_stations: true,
+            sii_encoding: SiiEncoding::default(),
+            sii_order_by_play_count: false,
+            icy_ascii_names: true,
+            icy_name_max_len: 64,
+            station_gains: HashMap::new(),
+            enable_limiter: false,
+            transcode_bitrate_kbps: 128,
+            keep_alive_grace_secs: 20,
+            output_buffer_secs: 0,
+            interrupt_channel: InterruptChannelSettings::default(),
+            tour_channel: TourChannelSettings::default(),
+            genre_channels: GenreChannelSettings::default(),
+            mqtt: MqttSettings::default(),
+            discord_rich_presence: false,
+            obs_title_file: String::new(),
+            auto_start_server: false,
+            server_port: 3000,
+            listen_on_lan: false,
+            external_url: String::new(),
+            admin_auth: AdminAuthSettings::default(),
+            rate_limit_per_minute: 0,
+            access_log: false,
+            yunting_api_key: String::new(),
+            yunting_key_manifest_url: String::new(),
+            yunting_mirror_urls: Vec::new(),
+            bilibili_audio_quality: BilibiliAudioQuality::default(),
+            bilibili_cdn: BilibiliCdnSettings::default(),
+            bilibili_search_tid: 0,
+            bilibili_highlight_stations: Vec::new(),
+            stream_tuning: StreamTuningSettings::default(),
+            recordings_quota_mb: 2048,
+            extra_servers: Vec::new(),
+            vtc_sync: VtcSyncSettings::default(),
+            follow_game: false,
+            idle_stop_minutes: 0,
+            auto_reinstall_sii: false,
+            resolve_timeout_secs: 4,
+            json_log: false,
+            crash_report_url: String::new(),
+            level_meter: false,
+            watermark: WatermarkSettings::default(),
+        }
+    }
+}
+
+/// 设置内容的指纹，写入 SII 元数据块用于过期检测
+pub fn settings_fingerprint(settings: &AppSettings) -> String {
+    serde_json::to_string(settings)
+        .map(|json| format!("{:x}", md5::compute(json)))
+        .unwrap_or_default()
+}
+
+/// 逐版本迁移设置 JSON，返回是否有改动
+///
+/// 每个迁移步骤只负责相邻两个版本之间的转换，
+/// 新增版本时在 match 里补一个分支即可。
+fn migrate_settings(value: &mut serde_json::Value) -> bool {
+    // 没有版本号的文件视为版本 1（引入版本号之前的所有旧版）
+    let mut version = value
+        .get("schemaVersion")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1) as u32;
+    if version >= CURRENT_SETTINGS_VERSION {
+        return false;
+    }
+
+    while version < CURRENT_SETTINGS_VERSION {
+        if version == 1 {
+            migrate_v1_to_v2(value);
+        }
+        version += 1;
+    }
+
+    if let Some(map) = value.as_object_mut() {
+        map.insert(
+            "schemaVersion".to_string(),
+            CURRENT_SETTINGS_VERSION.into(),
+        );
+    }
+    true
+}
+
+/// v1 -> v2：剔除类型不符的字段
+///
+/// 无版本号时代，单个类型写坏的字段（手改出错或旧版 bug）会让整份
+/// 文件解析失败、所有设置回落默认值。迁移时按默认设置的字段类型校验，
+/// 只丢弃坏掉的字段，其余设置原样保留。
+fn migrate_v1_to_v2(value: &mut serde_json::Value) {
+    let template = serde_json::to_value(AppSettings::default()).unwrap_or_default();
+    let (Some(map), Some(template)) = (value.as_object_mut(), template.as_object()) else {
+        return;
+    };
+    map.retain(|key, field| match template.get(key) {
+        Some(expected) => json_kind(field) == json_kind(expected),
+        // 未知字段保留，可能来自降级前的更新版本
+        None => true,
+    });
+}
+
+/// JSON 值的类型序号，用于比较两个值的类型是否一致
+fn json_kind(value: &serde_json::Value) -> u8 {
+    match value {
+        serde_json::Value::Null => 0,
+        serde_json::Value::Bool(_) => 1,
+        serde_json::Value::Number(_) => 2,
+        serde_json::Value::String(_) => 3,
+        serde_json::Value::Array(_) => 4,
+        serde_json::Value::Object(_) => 5,
+    }
+}
+
+/// 从文件加载设置，文件不存在或损坏时返回默认值
+///
+/// 旧版本的文件会先自动迁移到当前结构并写回。
+pub fn load_settings_from_file(data_dir: &std::path::Path) -> AppSettings {
+    let path = data_dir.join(SETTINGS_FILE);
+    if !path.exists() {
+        return AppSettings::default();
+    }
+
+    let json = match std::fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(_) => return AppSettings::default(),
+    };
+    let mut value: serde_json::Value = match serde_json::from_str(&json) {
+        Ok(value) => value,
+        Err(e) => {
+            log::warn!("设置文件不是合法 JSON，使用默认设置: {}", e);
+            return AppSettings::default();
+        }
+    };
+
+    if migrate_settings(&mut value) {
+        // 迁移后立即写回，避免每次启动重复迁移
+        if let Ok(migrated) = serde_json::to_string_pretty(&value) {
+            let _ = crate::utils::fs::write_atomic(&path, migrated);
+        }
+        log::info!("设置文件已迁移到版本 {}", CURRENT_SETTINGS_VERSION);
+    }
+
+    serde_json::from_value(value).unwrap_or_default()
+}
+
+/// 保存设置到文件
+pub fn save_settings_to_file(
+    data_dir: &std::path::Path,
+    settings: &AppSettings,
+) -> Result<(), String> {
+    let path = data_dir.join(SETTINGS_FILE);
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    crate::utils::fs::write_atomic(&path, json).map_err(|e| e.to_string())?;
+    log::debug!("settings saved: {:?}", path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_v1_drops_only_bad_typed_fields() {
+        let mut value = serde_json::json!({
+            "transcodeBitrateKbps": "很高",
+            "icyAsciiNames": false,
+            "keepAliveGraceSecs": 60,
+        });
+        assert!(migrate_settings(&mut value));
+
+        let settings: AppSettings = serde_json::from_value(value).unwrap();
+        // 坏字段回落默认值，好字段原样保留
+        assert_eq!(settings.transcode_bitrate_kbps, 128);
+        assert!(!settings.icy_ascii_names);
+        assert_eq!(settings.keep_alive_grace_secs, 60);
+        assert_eq!(settings.schema_version, CURRENT_SETTINGS_VERSION);
+    }
+
+    #[test]
+    fn migrate_skips_current_version() {
+        let mut value = serde_json::to_value(AppSettings::default()).unwrap();
+        assert!(!migrate_settings(&mut value));
+    }
+}
@@ -0,0 +1,210 @@
+//! 数据目录磁盘用量统计与录音配额清理
+//!
+//! 录音文件统一落在 recordings/ 下，超出配额时从最旧的开始删除；
+//! 同时按类别统计数据目录占用，供设置页展示。
+
+use std::path::Path;
+
+/// 录音目录名
+pub const RECORDINGS_DIR: &str = "recordings";
+
+/// 单个类别的磁盘用量
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryUsage {
+    /// 类别名（面向用户展示）
+    pub name: String,
+    /// 占用字节数
+    pub bytes: u64,
+    /// 文件数量
+    pub files: usize,
+}
+
+/// 数据目录磁盘用量报告
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    pub categories: Vec<CategoryUsage>,
+    pub total_bytes: u64,
+}
+
+/// 按类别统计数据目录占用
+///
+/// 类别：数据文件（顶层 JSON）、备份、录音、其他。
+pub fn storage_usage(data_dir: &Path) -> StorageUsage {
+    let mut data = CategoryUsage {
+        name: "数据文件".to_string(),
+        bytes: 0,
+        files: 0,
+    };
+    let mut other = CategoryUsage {
+        name: "其他".to_string(),
+        bytes: 0,
+        files: 0,
+    };
+
+    if let Ok(entries) = std::fs::read_dir(data_dir) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                continue; // 子目录按各自类别单独统计
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            let is_json = path.extension().map(|ext| ext == "json").unwrap_or(false);
+            let target = if is_json { &mut data } else { &mut other };
+            target.bytes += size;
+            target.files += 1;
+        }
+    }
+
+    let backups = dir_usage(&data_dir.join("backups"), "备份");
+    let recordings = dir_usage(&data_dir.join(RECORDINGS_DIR), "录音");
+
+    let total_bytes = data.bytes + other.bytes + backups.bytes + recordings.bytes;
+    StorageUsage {
+        categories: vec![data, backups, recordings, other],
+        total_bytes,
+    }
+}
+
+/// 递归统计一个目录的占用
+fn dir_usage(dir: &Path, name: &str) -> CategoryUsage {
+    let (bytes, files) = dir_size(dir);
+    CategoryUsage {
+        name: name.to_string(),
+        bytes,
+        files,
+    }
+}
+
+/// 递归求目录的（字节数，文件数）
+fn dir_size(dir: &Path) -> (u64, usize) {
+    let mut bytes = 0;
+    let mut files = 0;
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                let (b, f) = dir_size(&path);
+                bytes += b;
+                files += f;
+            } else {
+                bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+                files += 1;
+            }
+        }
+    }
+    (bytes, files)
+}
+
+/// 按配额清理录音目录，返回删除的文件数
+///
+/// 超出 `quota_mb` 时按修改时间从最旧的录音开始删除，直到回到配额内。
+/// `quota_mb` 为 0 表示不限制。
+pub fn cleanup_recordings(data_dir: &Path, quota_mb: u64) -> usize {
+    if quota_mb == 0 {
+        return 0;
+    }
+    let dir = data_dir.join(RECORDINGS_DIR);
+    let quota_bytes = quota_mb * 1024 * 1024;
+
+    // 收集（修改时间，大小，路径），按时间从旧到新排序
+    let mut entries: Vec<(std::time::SystemTime, u64, std::path::PathBuf)> = Vec::new();
+    let mut total = 0u64;
+    if let Ok(dir_entries) = std::fs::read_dir(&dir) {
+        for entry in dir_entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            if let Ok(meta) = entry.metadata() {
+                let modified = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
+                total += meta.len();
+                entries.push((modified, meta.len(), path));
+            }
+        }
+    }
+    if total <= quota_bytes {
+        return 0;
+    }
+    entries.sort_by_key(|(modified, _, _)| *modified);
+
+    let mut removed = 0;
+    for (_, size, path) in entries {
+        if total <= quota_bytes {
+            break;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                total = total.saturating_sub(size);
+                removed += 1;
+                log::info!("录音超出配额，已删除最旧文件: {:?}", path);
+            }
+            Err(e) => log::warn!("删除旧录音失败 {:?}: {}", path, e),
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_data_dir(tag: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("ouka2_storage_{}_{}", tag, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(RECORDINGS_DIR)).unwrap();
+        dir
+    }
+
+    #[test]
+    fn cleanup_removes_oldest_until_under_quota() {
+        let dir = temp_data_dir("cleanup");
+        let recordings = dir.join(RECORDINGS_DIR);
+        // 三个 1MB 文件，配额 2MB，应删掉最旧的一个
+        for (i, name) in ["old.mp3", "mid.mp3", "new.mp3"].iter().enumerate() {
+            let path = recordings.join(name);
+            std::fs::write(&path, vec![0u8; 1024 * 1024]).unwrap();
+            let mtime = std::time::SystemTime::UNIX_EPOCH
+                + std::time::Duration::from_secs(1_700_000_000 + i as u64 * 3600);
+            let file = std::fs::File::open(&path).unwrap();
+            file.set_modified(mtime).unwrap();
+        }
+
+        let removed = cleanup_recordings(&dir, 2);
+        assert_eq!(removed, 1);
+        assert!(!recordings.join("old.mp3").exists());
+        assert!(recordings.join("mid.mp3").exists());
+        assert!(recordings.join("new.mp3").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cleanup_quota_zero_is_unlimited() {
+        let dir = temp_data_dir("unlimited");
+        let path = dir.join(RECORDINGS_DIR).join("a.mp3");
+        std::fs::write(&path, vec![0u8; 4096]).unwrap();
+
+        assert_eq!(cleanup_recordings(&dir, 0), 0);
+        assert!(path.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn storage_usage_reports_categories() {
+        let dir = temp_data_dir("usage");
+        std::fs::write(dir.join("stations.json"), "[]").unwrap();
+        std::fs::write(dir.join(RECORDINGS_DIR).join("a.mp3"), vec![0u8; 100]).unwrap();
+
+        let usage = storage_usage(&dir);
+        assert_eq!(usage.categories.len(), 4);
+        assert!(usage.total_bytes >= 102);
+        let recordings = usage.categories.iter().find(|c| c.name == "录音").unwrap();
+        assert_eq!(recordings.files, 1);
+        assert_eq!(recordings.bytes, 100);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
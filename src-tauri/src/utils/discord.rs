@@ -0,0 +1,115 @@
+//! Discord Rich Presence 集成
+//!
+//! 通过本机 Discord 客户端的 IPC 通道设置"正在收听"状态。
+//! 协议只是 op + 长度前缀的 JSON 帧，和 MQTT 集成一样每次短连接完成，
+//! 不引入官方 SDK 依赖。Discord 未运行时静默失败。
+
+use anyhow::{bail, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// 在 Discord 开发者后台注册的应用 ID
+const DISCORD_CLIENT_ID: &str = "1219473087901204480";
+
+/// 握手和写入的等待上限
+const IPC_TIMEOUT_SECS: u64 = 3;
+
+/// 帧操作码：握手
+const OP_HANDSHAKE: u32 = 0;
+/// 帧操作码：普通消息
+const OP_FRAME: u32 = 1;
+
+/// 设置 Rich Presence 为"Listening to {station_name} in ETS2"
+pub async fn set_activity(station_name: &str) -> Result<()> {
+    let activity = serde_json::json!({
+        "details": format!("Listening to {}", station_name),
+        "state": "in Euro Truck Simulator 2",
+    });
+    send_activity(Some(activity)).await
+}
+
+/// 清除 Rich Presence 状态
+pub async fn clear_activity() -> Result<()> {
+    send_activity(None).await
+}
+
+async fn send_activity(activity: Option<serde_json::Value>) -> Result<()> {
+    let mut conn = connect().await?;
+
+    // 握手
+    let handshake = serde_json::json!({ "v": 1, "client_id": DISCORD_CLIENT_ID });
+    write_frame(&mut conn, OP_HANDSHAKE, &handshake).await?;
+    read_frame(&mut conn).await?;
+
+    // SET_ACTIVITY，activity 为 null 时表示清除
+    let payload = serde_json::json!({
+        "cmd": "SET_ACTIVITY",
+        "args": {
+            "pid": std::process::id(),
+            "activity": activity,
+        },
+        "nonce": format!("ouka2-{}", chrono::Local::now().timestamp_millis()),
+    });
+    write_frame(&mut conn, OP_FRAME, &payload).await?;
+    read_frame(&mut conn).await?;
+    Ok(())
+}
+
+/// 写一帧：小端 op + 小端长度 + JSON
+async fn write_frame<S: AsyncWriteExt + Unpin>(
+    conn: &mut S,
+    op: u32,
+    payload: &serde_json::Value,
+) -> Result<()> {
+    let body = serde_json::to_vec(payload)?;
+    let mut frame = Vec::with_capacity(8 + body.len());
+    frame.extend_from_slice(&op.to_le_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&body);
+    tokio::time::timeout(
+        std::time::Duration::from_secs(IPC_TIMEOUT_SECS),
+        conn.write_all(&frame),
+    )
+    .await??;
+    Ok(())
+}
+
+/// 读一帧并丢弃内容，只确认 Discord 有响应
+async fn read_frame<S: AsyncReadExt + Unpin>(conn: &mut S) -> Result<()> {
+    let timeout = std::time::Duration::from_secs(IPC_TIMEOUT_SECS);
+    let mut header = [0u8; 8];
+    tokio::time::timeout(timeout, conn.read_exact(&mut header)).await??;
+    let len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+    if len > 64 * 1024 {
+        bail!("Discord IPC 响应长度异常: {}", len);
+    }
+    let mut body = vec![0u8; len];
+    tokio::time::timeout(timeout, conn.read_exact(&mut body)).await??;
+    Ok(())
+}
+
+/// 依次尝试 discord-ipc-0 到 discord-ipc-9
+#[cfg(unix)]
+async fn connect() -> Result<tokio::net::UnixStream> {
+    let base_dir = std::env::var("XDG_RUNTIME_DIR")
+        .or_else(|_| std::env::var("TMPDIR"))
+        .unwrap_or_else(|_| "/tmp".to_string());
+    for i in 0..10 {
+        let path = std::path::Path::new(&base_dir).join(format!("discord-ipc-{}", i));
+        if let Ok(stream) = tokio::net::UnixStream::connect(&path).await {
+            return Ok(stream);
+        }
+    }
+    bail!("未找到 Discord IPC 通道，Discord 可能未运行")
+}
+
+/// 依次尝试 discord-ipc-0 到 discord-ipc-9
+#[cfg(windows)]
+async fn connect() -> Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    for i in 0..10 {
+        let path = format!(r"\\.\pipe\discord-ipc-{}", i);
+        if let Ok(pipe) = tokio::net::windows::named_pipe::ClientOptions::new().open(&path) {
+            return Ok(pipe);
+        }
+    }
+    bail!("未找到 Discord IPC 通道，Discord 可能未运行")
+}
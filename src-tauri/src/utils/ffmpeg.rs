@@ -8,12 +8,16 @@ use tauri::Manager;
 pub struct FFmpegManager;
 
 impl FFmpegManager {
-    /// 检测系统中的 FFmpeg
+    /// 检测系统中的 FFmpeg，并报告使用了哪种查找机制
     ///
     /// 按以下顺序查找：
     /// 1. 应用资源目录中的 FFmpeg (binaries/ffmpeg 或 binaries/ffmpeg.exe)
     /// 2. 系统 PATH 中的 FFmpeg
-    pub fn detect_ffmpeg(app_resource_dir: Option<&PathBuf>) -> Option<PathBuf> {
+    /// 3. Linux 常见安装位置 (/usr/bin、/usr/local/bin)
+    /// 4. Flatpak 沙箱内通过 flatpak-spawn 调用宿主机的 FFmpeg
+    pub fn detect_ffmpeg_with_source(
+        app_resource_dir: Option<&PathBuf>,
+    ) -> Option<(PathBuf, &'static str)> {
         // 根据目标系统确定 FFmpeg 二进制文件名
         #[cfg(target_os = "windows")]
         let ffmpeg_binary = "ffmpeg.exe";
@@ -31,7 +35,7 @@ impl FFmpegManager {
             for bundled_ffmpeg in bundled_paths {
                 if bundled_ffmpeg.exists() {
                     log::debug!("bundled ffmpeg: {:?}", bundled_ffmpeg);
-                    return Some(bundled_ffmpeg);
+                    return Some((bundled_ffmpeg, "应用内置"));
                 }
             }
         }
@@ -39,13 +43,55 @@ impl FFmpegManager {
         // 2. 检查系统 PATH
         if Self::check_ffmpeg_in_path() {
             log::debug!("system ffmpeg from PATH");
-            return Some(PathBuf::from("ffmpeg"));
+            return Some((PathBuf::from("ffmpeg"), "系统 PATH"));
+        }
+
+        // 3. Linux 常见安装位置（沙箱内 PATH 可能不含这些目录）
+        #[cfg(target_os = "linux")]
+        {
+            for dir in ["/usr/bin", "/usr/local/bin", "/app/bin"] {
+                let candidate = PathBuf::from(dir).join(ffmpeg_binary);
+                if candidate.exists() {
+                    log::debug!("ffmpeg at {:?}", candidate);
+                    return Some((candidate, "系统安装目录"));
+                }
+            }
+
+            // 4. Flatpak 沙箱：通过 flatpak-spawn 调用宿主机的 FFmpeg。
+            //    生成一个转发脚本，保持调用方只拿一个可执行路径的约定。
+            if std::env::var("FLATPAK_ID").is_ok() && Self::check_flatpak_host_ffmpeg() {
+                if let Some(wrapper) = Self::write_flatpak_wrapper() {
+                    log::debug!("ffmpeg via flatpak-spawn: {:?}", wrapper);
+                    return Some((wrapper, "flatpak 宿主机"));
+                }
+            }
         }
 
         log::error!("未找到 FFmpeg");
         None
     }
 
+    /// 检查能否通过 flatpak-spawn 调用宿主机的 FFmpeg
+    #[cfg(target_os = "linux")]
+    fn check_flatpak_host_ffmpeg() -> bool {
+        Command::new("flatpak-spawn")
+            .args(["--host", "ffmpeg", "-version"])
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    /// 写出 flatpak-spawn 转发脚本并返回其路径
+    #[cfg(target_os = "linux")]
+    fn write_flatpak_wrapper() -> Option<PathBuf> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let wrapper = std::env::temp_dir().join("ouka2-ffmpeg-host.sh");
+        std::fs::write(&wrapper, "#!/bin/sh\nexec flatpak-spawn --host ffmpeg \"$@\"\n").ok()?;
+        std::fs::set_permissions(&wrapper, std::fs::Permissions::from_mode(0o755)).ok()?;
+        Some(wrapper)
+    }
+
     /// 检查 FFmpeg 是否在系统 PATH 中
     fn check_ffmpeg_in_path() -> bool {
         #[cfg(target_os = "windows")]
@@ -83,15 +129,92 @@ impl FFmpegManager {
     }
 }
 
+/// FFmpeg 编码器能力标志
+///
+/// 系统自带的 FFmpeg 可能因编译选项缺少个别编码器
+/// （最常见的是发行版裁掉 libmp3lame），流启动前据此选择可用编码器。
+#[derive(Debug, Clone, Copy)]
+pub struct EncoderCapabilities {
+    /// MP3 编码器（LAME）
+    pub libmp3lame: bool,
+    /// MP3 编码器（Shine，定点实现，部分精简版自带）
+    pub libshine: bool,
+    /// FFmpeg 内置 AAC 编码器
+    pub aac: bool,
+    /// Opus 编码器
+    pub libopus: bool,
+}
+
+impl EncoderCapabilities {
+    /// 探测失败时的保守假设：全部可用，保持旧版行为
+    pub fn assume_all() -> Self {
+        Self {
+            libmp3lame: true,
+            libshine: true,
+            aac: true,
+            libopus: true,
+        }
+    }
+}
+
+/// 探测 FFmpeg 支持的音频编码器
+///
+/// 运行 `ffmpeg -encoders` 并解析列表。探测失败或超时
+/// （如 FFmpeg 损坏、flatpak 转发异常）返回 None，调用方应假定全部可用。
+pub async fn probe_encoders(ffmpeg_path: &PathBuf) -> Option<EncoderCapabilities> {
+    let mut cmd = tokio::process::Command::new(ffmpeg_path);
+    cmd.args(["-hide_banner", "-encoders"])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        #[allow(unused_imports)]
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(5), cmd.output())
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let listing = String::from_utf8_lossy(&output.stdout);
+    Some(parse_encoder_listing(&listing))
+}
+
+/// 从 `-encoders` 输出解析编码器能力
+///
+/// 每行形如 ` A....D libmp3lame  MP3 (MPEG audio layer 3)`，取第二列为编码器名。
+fn parse_encoder_listing(listing: &str) -> EncoderCapabilities {
+    let has = |name: &str| {
+        listing
+            .lines()
+            .any(|line| line.split_whitespace().nth(1) == Some(name))
+    };
+    EncoderCapabilities {
+        libmp3lame: has("libmp3lame"),
+        libshine: has("libshine"),
+        aac: has("aac"),
+        libopus: has("libopus"),
+    }
+}
+
 /// 检查 FFmpeg 是否可用
 #[tauri::command]
 pub fn check_ffmpeg(app_handle: tauri::AppHandle) -> Result<String, String> {
     // 获取资源目录，与 lib.rs 初始化时的逻辑保持一致
     let resource_dir = app_handle.path().resource_dir().ok();
 
-    if let Some(path) = FFmpegManager::detect_ffmpeg(resource_dir.as_ref()) {
+    if let Some((path, source)) = FFmpegManager::detect_ffmpeg_with_source(resource_dir.as_ref()) {
         if let Some(version) = FFmpegManager::get_version(&path) {
-            Ok(version)
+            Ok(format!("{}（来源: {}）", version, source))
         } else {
             Err("FFmpeg 存在但无法获取版本信息".to_string())
         }
@@ -102,3 +225,22 @@ pub fn check_ffmpeg(app_handle: tauri::AppHandle) -> Result<String, String> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_encoder_listing_detects_available_encoders() {
+        let listing = "Encoders:\n\
+                       V..... = Video\n\
+                       ------\n\
+                       A....D aac              AAC (Advanced Audio Coding)\n\
+                       A....D libopus          libopus Opus (codec opus)\n";
+        let caps = parse_encoder_listing(listing);
+        assert!(caps.aac);
+        assert!(caps.libopus);
+        assert!(!caps.libmp3lame);
+        assert!(!caps.libshine);
+    }
+}
@@ -0,0 +1,39 @@
+//! 文件写入工具
+
+use std::path::Path;
+
+/// 原子写入文件：先写同目录临时文件，再重命名覆盖目标
+///
+/// `std::fs::write` 在应用中途被杀时可能留下截断的半份文件。
+/// 重命名在同一文件系统内是原子操作，目标文件要么保持旧内容、
+/// 要么是完整的新内容，设置和电台数据等持久化文件统一走这里。
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    let tmp = std::path::PathBuf::from(tmp);
+
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_atomic_creates_and_overwrites() {
+        let dir = std::env::temp_dir().join(format!("ouka2_fs_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.json");
+
+        write_atomic(&path, "第一版").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "第一版");
+
+        write_atomic(&path, "第二版").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "第二版");
+        // 临时文件不残留
+        assert!(!dir.join("data.json.tmp").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,20 @@
+//! 游戏进程检测
+//!
+//! 检测欧卡2 / 美卡是否正在运行，供"跟随游戏"模式
+//! 在游戏启动时拉起服务器、退出时停止。
+
+use sysinfo::System;
+
+/// ETS2 / ATS 的进程名（Windows 带 .exe，Linux/macOS 原生版不带）
+const GAME_PROCESS_NAMES: &[&str] = &["eurotrucks2.exe", "eurotrucks2", "amtrucks.exe", "amtrucks"];
+
+/// 是否有卡车游戏进程正在运行
+///
+/// 复用调用方持有的 `System`，每次只刷新进程列表。
+pub fn is_game_running(sys: &mut System) -> bool {
+    sys.refresh_processes();
+    sys.processes().values().any(|process| {
+        let name = process.name().to_lowercase();
+        GAME_PROCESS_NAMES.contains(&name.as_str())
+    })
+}
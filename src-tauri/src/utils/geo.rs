@@ -0,0 +1,45 @@
+//! IP 地理位置检测
+//!
+//! 通过 IP 归属地接口粗略判断用户所在省份，
+//! 用于首次使用时的本地电台推荐。精度到省级已足够。
+
+/// 地理位置接口地址，lang=zh-CN 让省份直接返回中文名
+const GEOIP_URL: &str = "http://ip-api.com/json/?lang=zh-CN&fields=status,regionName";
+
+/// 通过 IP 归属地检测所在省份，失败返回 None
+///
+/// 返回的省份名已去掉"省 / 市 / 自治区"等后缀，
+/// 与电台数据中的省份字段直接可比。
+pub async fn detect_province() -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct GeoResponse {
+        status: String,
+        #[serde(rename = "regionName", default)]
+        region_name: String,
+    }
+
+    let response: GeoResponse = reqwest::Client::new()
+        .get(GEOIP_URL)
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    if response.status != "success" {
+        return None;
+    }
+
+    let region = response
+        .region_name
+        .trim()
+        .trim_end_matches("维吾尔自治区")
+        .trim_end_matches("壮族自治区")
+        .trim_end_matches("回族自治区")
+        .trim_end_matches("自治区")
+        .trim_end_matches('省')
+        .trim_end_matches('市')
+        .to_string();
+    (!region.is_empty()).then_some(region)
+}
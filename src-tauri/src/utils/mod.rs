@@ -1,5 +1,10 @@
 //! 工具模块
 
+pub mod discord;
 pub mod ffmpeg;
+pub mod fs;
+pub mod game;
+pub mod geo;
+pub mod mqtt;
 
 pub use ffmpeg::*;
@@ -0,0 +1,69 @@
+//! 极简 MQTT 3.1.1 发布客户端
+//!
+//! 只实现 CONNECT / PUBLISH (QoS 0) / DISCONNECT。播放事件频率很低，
+//! 每次发布建立短连接即可，省去维护长连接和引入完整客户端依赖。
+
+use anyhow::{bail, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// 连接和 CONNACK 的等待上限
+const CONNECT_TIMEOUT_SECS: u64 = 5;
+
+/// 向 broker 发布一条 QoS 0 消息（可选保留），完成后立即断开
+pub async fn publish(broker: &str, topic: &str, payload: &[u8], retain: bool) -> Result<()> {
+    let timeout = std::time::Duration::from_secs(CONNECT_TIMEOUT_SECS);
+    let mut stream = tokio::time::timeout(timeout, TcpStream::connect(broker)).await??;
+
+    // CONNECT：协议级别 4（3.1.1），clean session
+    let mut var = Vec::new();
+    encode_utf8_string("MQTT", &mut var);
+    var.push(4);
+    var.push(0x02);
+    var.extend_from_slice(&60u16.to_be_bytes());
+    encode_utf8_string(&format!("ouka2-{}", std::process::id()), &mut var);
+    stream.write_all(&packet(0x10, &var)).await?;
+
+    // CONNACK
+    let mut ack = [0u8; 4];
+    tokio::time::timeout(timeout, stream.read_exact(&mut ack)).await??;
+    if ack[0] != 0x20 || ack[3] != 0 {
+        bail!("MQTT broker 拒绝连接，返回码 {}", ack[3]);
+    }
+
+    // PUBLISH (QoS 0)
+    let mut var = Vec::new();
+    encode_utf8_string(topic, &mut var);
+    var.extend_from_slice(payload);
+    let first_byte = 0x30 | u8::from(retain);
+    stream.write_all(&packet(first_byte, &var)).await?;
+
+    // DISCONNECT
+    stream.write_all(&[0xE0, 0x00]).await?;
+    Ok(())
+}
+
+/// 组装完整报文：固定头 + 可变长度 + 内容
+fn packet(first_byte: u8, var: &[u8]) -> Vec<u8> {
+    let mut out = vec![first_byte];
+    let mut len = var.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(var);
+    out
+}
+
+/// 编码 MQTT UTF-8 字符串（2 字节长度前缀）
+fn encode_utf8_string(s: &str, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    out.extend_from_slice(s.as_bytes());
+}